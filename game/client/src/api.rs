@@ -1,22 +1,26 @@
 use bevy::prelude::*;
 use bevy_mod_reqwest::*;
 
-use common::{check_reqwest_error, user::UserId};
+use uuid::Uuid;
 
-const HOST: &str = "http://localhost:8000";
+use common::{check_reqwest_error, user::UserId, REQUEST_ID_HEADER};
 
 pub fn find_server<'a>(
     client: &'a mut BevyReqwest,
+    api_url: &str,
     user_id: UserId,
 ) -> anyhow::Result<BevyReqwestBuilder<'a>> {
-    info!("finding server ...");
+    // so the request can be followed through the backend logs
+    let request_id = Uuid::new_v4().to_string();
+    info!("finding server (request {}) ...", request_id);
 
-    let url = format!("{}/gameclient/find_server/v1", HOST);
+    let url = format!("{}/gameclient/find_server/v1", api_url);
 
     let req = client
         .get(url)
         // TODO: should be auth JWT token
         .bearer_auth(user_id.to_string())
+        .header(REQUEST_ID_HEADER, request_id)
         .build()?;
 
     Ok(client
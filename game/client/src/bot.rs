@@ -0,0 +1,144 @@
+use bevy::prelude::*;
+
+use common::netcode;
+use game_common::{GameState, InputState};
+
+use crate::{input, options::Options, AppState};
+
+// how often the bot picks something new to do
+const WANDER_INTERVAL_SECS: f32 = 2.0;
+
+// chance to jump each time the bot changes direction
+const JUMP_CHANCE: f32 = 0.25;
+
+const MAX_TURN_RATE: f32 = 3.0;
+
+#[derive(Debug, Resource)]
+struct FindServerTimer(Timer);
+
+#[derive(Debug, Resource)]
+struct BotState {
+    rng: u64,
+    wander: Timer,
+    r#move: Vec2,
+    turn: f32,
+}
+
+impl BotState {
+    fn new(seed: u64) -> Self {
+        Self {
+            // xorshift can't start from 0
+            rng: seed.max(1),
+            wander: Timer::from_seconds(WANDER_INTERVAL_SECS, TimerMode::Repeating),
+            r#move: Vec2::default(),
+            turn: 0.0,
+        }
+    }
+
+    // xorshift64, plenty random enough to wander around
+    fn next(&mut self) -> f32 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+
+        (self.rng >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    fn next_signed(&mut self) -> f32 {
+        self.next() * 2.0 - 1.0
+    }
+}
+
+fn is_bot(options: Res<Options>) -> bool {
+    options.bot
+}
+
+#[derive(Debug)]
+pub struct BotPlugin;
+
+impl Plugin for BotPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(AppState::MainMenu),
+            start_find_server_timer.run_if(is_bot),
+        )
+        .add_systems(OnExit(AppState::MainMenu), stop_find_server_timer)
+        .add_systems(OnEnter(AppState::InGame), enter.run_if(is_bot))
+        .add_systems(OnExit(AppState::InGame), exit)
+        .add_systems(
+            Update,
+            (
+                update_find_server_timer.run_if(in_state(AppState::MainMenu)),
+                update_input
+                    .in_set(input::InputSet)
+                    .run_if(in_state(GameState::InGame)),
+            )
+                .run_if(is_bot),
+        );
+    }
+}
+
+// bots (re)join as soon as they land in the main menu
+fn start_find_server_timer(mut commands: Commands, options: Res<Options>) {
+    info!(
+        "bot finding server in {:?} ...",
+        options.bot_find_server_delay()
+    );
+
+    commands.insert_resource(FindServerTimer(Timer::new(
+        options.bot_find_server_delay(),
+        TimerMode::Once,
+    )));
+}
+
+fn stop_find_server_timer(mut commands: Commands) {
+    commands.remove_resource::<FindServerTimer>();
+}
+
+fn update_find_server_timer(
+    time: Res<Time>,
+    timer: Option<ResMut<FindServerTimer>>,
+    mut app_state: ResMut<NextState<AppState>>,
+) {
+    let Some(mut timer) = timer else {
+        return;
+    };
+
+    if timer.0.tick(time.delta()).just_finished() {
+        app_state.set(AppState::ConnectToServer);
+    }
+}
+
+fn enter(mut commands: Commands, options: Res<Options>) {
+    info!("bot entering game ...");
+
+    commands.insert_resource(BotState::new(netcode::client_id_for_user(options.user_id)));
+}
+
+fn exit(mut commands: Commands) {
+    commands.remove_resource::<BotState>();
+}
+
+fn update_input(
+    time: Res<Time>,
+    bot: Option<ResMut<BotState>>,
+    mut input_state: ResMut<InputState>,
+    mut evw_jump: EventWriter<input::JumpPressedEvent>,
+) {
+    let Some(mut bot) = bot else {
+        return;
+    };
+
+    if bot.wander.tick(time.delta()).just_finished() {
+        let r#move = Vec2::new(bot.next_signed(), bot.next_signed());
+        bot.r#move = r#move.normalize_or_zero();
+        bot.turn = bot.next_signed() * MAX_TURN_RATE;
+
+        if bot.next() < JUMP_CHANCE {
+            evw_jump.send_default();
+        }
+    }
+
+    input_state.r#move += bot.r#move;
+    input_state.look += Vec2::new(bot.turn, 0.0);
+}
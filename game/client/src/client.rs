@@ -1,3 +1,5 @@
+use std::collections::VecDeque;
+
 use bevy::prelude::*;
 use bevy_mod_websocket::*;
 use bevy_replicon::prelude::*;
@@ -8,13 +10,16 @@ use bevy_replicon_renet::{
 
 use common::user::UserId;
 use game_common::{
-    network::{ConnectEvent, InputUpdateEvent, PlayerClientId, PlayerJumpEvent},
-    GameState, InputState,
+    network::{
+        ConnectEvent, IdleWarningEvent, InputUpdateEvent, PingEvent, PlayerClientId, PongEvent,
+        SequencedInput, ServerDisconnectEvent, ServerShutdownWarningEvent, INPUT_REDUNDANCY,
+    },
+    GameState, InputState, MAX_LOOK_DELTA,
 };
 
 use crate::{
-    camera, connect_server, game, game_menu, input, main_menu, notifs, options::Options, ui,
-    AppState, Settings,
+    bot, camera, clock, connect_server, game, game_menu, input, interpolation, main_menu, notifs,
+    options::Options, prediction, ui, AppState, Settings,
 };
 
 #[derive(Debug, Default, Resource)]
@@ -36,6 +41,29 @@ impl ClientState {
     }
 }
 
+// why the server said it was disconnecting us
+#[derive(Debug, Default, Resource)]
+struct DisconnectReason(Option<String>);
+
+// input is sent from here each fixed tick
+#[derive(Debug, Clone, PartialEq, Eq, Hash, SystemSet)]
+pub struct SendInputSet;
+
+// input gathered since the last tick and the inputs we've sent recently
+#[derive(Debug, Default, Resource)]
+struct InputHistory {
+    pending: InputState,
+    pending_jump: bool,
+    next_sequence: u32,
+    sent: VecDeque<SequencedInput>,
+}
+
+#[derive(Debug, Component)]
+struct ShutdownWarning;
+
+#[derive(Debug, Component)]
+struct IdleWarning;
+
 #[derive(Debug)]
 pub struct ClientPlugin;
 
@@ -49,16 +77,34 @@ impl Plugin for ClientPlugin {
             input::InputPlugin,
             ui::UiPlugin,
             game::GamePlugin,
+            bot::BotPlugin,
+            prediction::PredictionPlugin,
+            interpolation::InterpolationPlugin,
+            clock::ClockPlugin,
         ))
         .init_resource::<Settings>()
         .init_resource::<ClientState>()
+        .init_resource::<DisconnectReason>()
+        .init_resource::<InputHistory>()
         .add_systems(Startup, setup)
         .add_systems(OnEnter(AppState::InGame), enter)
-        .add_systems(Update, handle_network_error)
+        // connect_server handles its own errors while trying addresses
+        .add_systems(
+            Update,
+            (
+                (handle_disconnect_event, handle_network_error)
+                    .chain()
+                    .run_if(not(in_state(AppState::ConnectToServer))),
+                (handle_ping, accumulate_input.after(input::InputSet))
+                    .run_if(in_state(AppState::InGame)),
+                (handle_shutdown_warning, handle_idle_warning).run_if(in_state(GameState::InGame)),
+            ),
+        )
+        // input is sampled at the same rate the server simulates it
         .add_systems(
-            PostUpdate,
-            (send_input_update, send_jump_event)
-                .before(ClientSet::Send)
+            FixedUpdate,
+            send_input_update
+                .in_set(SendInputSet)
                 .run_if(in_state(AppState::InGame)),
         )
         .add_systems(OnExit(AppState::InGame), exit);
@@ -68,7 +114,7 @@ impl Plugin for ClientPlugin {
 fn setup(options: Res<Options>, mut ws_client: WebSocketClient) {
     info!("starting client app {}", options.user_id);
 
-    notifs::subscribe(&mut ws_client, options.user_id);
+    notifs::subscribe(&mut ws_client, &options.notifs_url, options.user_id);
 }
 
 fn enter(mut game_state: ResMut<NextState<GameState>>) {
@@ -80,15 +126,28 @@ fn enter(mut game_state: ResMut<NextState<GameState>>) {
 fn exit(mut commands: Commands) {
     info!("exiting client app game ...");
 
-    commands.init_resource::<ClientState>();
+    commands.insert_resource(ClientState::default());
+    commands.insert_resource(DisconnectReason::default());
+    commands.insert_resource(InputHistory::default());
 
     commands.remove_resource::<PlayerClientId>();
     commands.remove_resource::<RenetClient>();
     commands.remove_resource::<NetcodeClientTransport>();
 }
 
+fn handle_disconnect_event(
+    mut disconnect_reason: ResMut<DisconnectReason>,
+    mut evr_disconnect: EventReader<ServerDisconnectEvent>,
+) {
+    if let Some(evt) = evr_disconnect.read().last() {
+        info!("server is disconnecting us: {}", evt.reason);
+        disconnect_reason.0 = Some(evt.reason.clone());
+    }
+}
+
 fn handle_network_error(
     mut commands: Commands,
+    mut disconnect_reason: ResMut<DisconnectReason>,
     mut evr_error: EventReader<NetcodeTransportError>,
     mut app_state: ResMut<NextState<AppState>>,
 ) {
@@ -96,8 +155,14 @@ fn handle_network_error(
         return;
     }
 
-    for evt in evr_error.read() {
-        error!("network error: {}", evt);
+    // an expected disconnect isn't an error
+    if let Some(reason) = disconnect_reason.0.take() {
+        evr_error.clear();
+        warn!("disconnected from server: {}", reason);
+    } else {
+        for evt in evr_error.read() {
+            error!("network error: {}", evt);
+        }
     }
 
     commands.remove_resource::<RenetClient>();
@@ -128,23 +193,109 @@ pub fn on_connected_server(
     app_state.set(AppState::InGame);
 }
 
-fn send_input_update(
-    mut input: ResMut<InputState>,
-    mut evw_input_update: EventWriter<InputUpdateEvent>,
+// answer right away so the server gets an accurate rtt
+fn handle_ping(mut evr_ping: EventReader<PingEvent>, mut evw_pong: EventWriter<PongEvent>) {
+    for evt in evr_ping.read() {
+        evw_pong.send(PongEvent(evt.0));
+    }
+}
+
+fn handle_shutdown_warning(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut evr_warning: EventReader<ServerShutdownWarningEvent>,
+    mut warnings: Query<&mut Text, With<ShutdownWarning>>,
+) {
+    let Some(evt) = evr_warning.read().last() else {
+        return;
+    };
+
+    let message = format!("Server shutting down in {}s", evt.seconds_remaining);
+    if let Ok(mut text) = warnings.get_single_mut() {
+        text.0 = message;
+        return;
+    }
+
+    warn!("server is shutting down ...");
+
+    // cleaned up with the rest of the game ui
+    ui::spawn_banner(&mut commands, &asset_server, message, 20.0).insert(ShutdownWarning);
+}
+
+fn handle_idle_warning(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut evr_warning: EventReader<IdleWarningEvent>,
+    mut warnings: Query<(Entity, &mut Text), With<IdleWarning>>,
 ) {
-    evw_input_update.send(InputUpdateEvent(*input));
+    let Some(evt) = evr_warning.read().last() else {
+        return;
+    };
+
+    let Some(seconds_remaining) = evt.seconds_remaining else {
+        for (entity, _) in &warnings {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    };
+
+    let message = format!("Kicking for inactivity in {}s", seconds_remaining);
+    if let Ok((_, mut text)) = warnings.get_single_mut() {
+        text.0 = message;
+        return;
+    }
+
+    warn!("idle, about to be kicked ...");
 
-    input.look = Vec2::default();
-    input.r#move = Vec2::default();
+    ui::spawn_banner(&mut commands, &asset_server, message, 60.0).insert(IdleWarning);
 }
 
-fn send_jump_event(
+// there can be any number of frames between ticks
+fn accumulate_input(
+    input: Res<InputState>,
+    mut history: ResMut<InputHistory>,
     mut evr_jump: EventReader<input::JumpPressedEvent>,
-    mut evw_jump: EventWriter<PlayerJumpEvent>,
 ) {
+    history.pending.look += input.look;
+    history.pending.r#move = input.r#move;
+    history.pending.crouch = input.crouch;
+
     if !evr_jump.is_empty() {
         // TODO: only send if we *can* jump
-        evw_jump.send_default();
+        history.pending_jump = true;
         evr_jump.clear();
     }
 }
+
+fn send_input_update(
+    mut history: ResMut<InputHistory>,
+    mut evw_input_update: EventWriter<InputUpdateEvent>,
+) {
+    // keyboard diagonals, multiple devices and fast mouse movement
+    // can add up past what the server accepts
+    let mut input_state = history.pending;
+    input_state.r#move = input_state.r#move.clamp_length_max(1.0);
+    input_state.look = input_state
+        .look
+        .clamp(Vec2::splat(-MAX_LOOK_DELTA), Vec2::splat(MAX_LOOK_DELTA));
+
+    // look and jump are one-off, the rest is held until the next frame updates it
+    history.pending.look = Vec2::default();
+    let jump = std::mem::take(&mut history.pending_jump);
+
+    let sequence = history.next_sequence;
+    history.next_sequence = sequence.wrapping_add(1);
+
+    history.sent.push_back(SequencedInput {
+        sequence,
+        input_state,
+        jump,
+    });
+    if history.sent.len() > INPUT_REDUNDANCY {
+        history.sent.pop_front();
+    }
+
+    evw_input_update.send(InputUpdateEvent {
+        inputs: history.sent.iter().copied().collect(),
+    });
+}
@@ -0,0 +1,234 @@
+use std::collections::VecDeque;
+
+use bevy::{prelude::*, utils::Duration};
+use bevy_replicon::prelude::*;
+
+use game_common::network::{TimeSyncRequestEvent, TimeSyncResponseEvent};
+
+use crate::AppState;
+
+// sample quickly until we have a decent estimate, then just keep it from drifting
+const STARTUP_SYNC_INTERVAL: Duration = Duration::from_millis(200);
+const SYNC_INTERVAL: Duration = Duration::from_secs(2);
+const STARTUP_SAMPLES: usize = 8;
+
+const MAX_SAMPLES: usize = 16;
+
+// the fastest round trips have the least queueing in them, so trust those
+const BEST_SAMPLES: usize = 4;
+
+const OFFSET_SMOOTHING: f64 = 0.2;
+
+// drift is fit over the raw estimates, the smoothed offset lags behind them
+const MAX_ESTIMATES: usize = 16;
+const MIN_DRIFT_ESTIMATES: usize = 4;
+
+#[derive(Debug, Copy, Clone)]
+struct TimeSample {
+    rtt: f64,
+    offset: f64,
+}
+
+// best-of-N offset at the time it was taken
+#[derive(Debug, Copy, Clone)]
+struct OffsetEstimate {
+    client_time: f64,
+    offset: f64,
+}
+
+// our best guess at the server's clock
+#[derive(Debug, Resource)]
+pub struct ServerClock {
+    samples: VecDeque<TimeSample>,
+    estimates: VecDeque<OffsetEstimate>,
+    sync_timer: Timer,
+
+    // server time - client time, in seconds
+    offset: Option<f64>,
+
+    // how fast the offset is changing, in seconds per second
+    drift: f64,
+
+    // client time the estimate was last updated
+    updated: f64,
+
+    // the last tick the server told us about and when it was
+    tick: u32,
+    tick_time: f64,
+    tick_secs: f64,
+}
+
+impl Default for ServerClock {
+    fn default() -> Self {
+        Self {
+            samples: VecDeque::with_capacity(MAX_SAMPLES),
+            estimates: VecDeque::with_capacity(MAX_ESTIMATES),
+            sync_timer: Timer::new(STARTUP_SYNC_INTERVAL, TimerMode::Repeating),
+            offset: None,
+            drift: 0.0,
+            updated: 0.0,
+            tick: 0,
+            tick_time: 0.0,
+            tick_secs: 0.0,
+        }
+    }
+}
+
+impl ServerClock {
+    #[inline]
+    pub fn offset(&self) -> Option<f64> {
+        self.offset
+    }
+
+    #[inline]
+    pub fn drift(&self) -> f64 {
+        self.drift
+    }
+
+    pub fn server_time(&self, client_time: f64) -> Option<f64> {
+        let offset = self.offset?;
+        Some(client_time + offset + self.drift * (client_time - self.updated))
+    }
+
+    pub fn server_tick(&self, client_time: f64) -> Option<u32> {
+        self.server_ticks(client_time).map(|ticks| ticks as u32)
+    }
+
+    // fractional, for rendering between ticks
+    pub fn server_ticks(&self, client_time: f64) -> Option<f64> {
+        if self.tick_secs <= 0.0 {
+            return None;
+        }
+
+        let elapsed = (self.server_time(client_time)? - self.tick_time).max(0.0);
+        Some(self.tick as f64 + elapsed / self.tick_secs)
+    }
+
+    fn add_sample(&mut self, sample: TimeSample, client_time: f64) {
+        self.samples.push_back(sample);
+        if self.samples.len() > MAX_SAMPLES {
+            self.samples.pop_front();
+        }
+
+        if self.samples.len() == STARTUP_SAMPLES {
+            self.sync_timer.set_duration(SYNC_INTERVAL);
+        }
+
+        let mut best = self.samples.iter().copied().collect::<Vec<_>>();
+        best.sort_by(|a, b| a.rtt.total_cmp(&b.rtt));
+        best.truncate(BEST_SAMPLES);
+        let estimate = best.iter().map(|sample| sample.offset).sum::<f64>() / best.len() as f64;
+
+        self.estimates.push_back(OffsetEstimate {
+            client_time,
+            offset: estimate,
+        });
+        if self.estimates.len() > MAX_ESTIMATES {
+            self.estimates.pop_front();
+        }
+
+        if let Some(drift) = self.fit_drift() {
+            self.drift = drift;
+        }
+
+        self.offset = Some(match self.offset {
+            Some(offset) => offset + (estimate - offset) * OFFSET_SMOOTHING,
+            None => estimate,
+        });
+        self.updated = client_time;
+    }
+
+    // least squares slope of the estimates over client time
+    fn fit_drift(&self) -> Option<f64> {
+        if self.estimates.len() < MIN_DRIFT_ESTIMATES {
+            return None;
+        }
+
+        let n = self.estimates.len() as f64;
+        let mean_time = self.estimates.iter().map(|e| e.client_time).sum::<f64>() / n;
+        let mean_offset = self.estimates.iter().map(|e| e.offset).sum::<f64>() / n;
+
+        let (covariance, variance) =
+            self.estimates
+                .iter()
+                .fold((0.0, 0.0), |(covariance, variance), estimate| {
+                    let dt = estimate.client_time - mean_time;
+                    (
+                        covariance + dt * (estimate.offset - mean_offset),
+                        variance + dt * dt,
+                    )
+                });
+
+        // all taken at once, there's nothing to fit
+        if variance <= f64::EPSILON {
+            return None;
+        }
+
+        Some(covariance / variance)
+    }
+}
+
+#[derive(Debug)]
+pub struct ClockPlugin;
+
+impl Plugin for ClockPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ServerClock>()
+            .add_systems(
+                Update,
+                (send_time_sync_requests, handle_time_sync_responses)
+                    .run_if(client_connected)
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(OnExit(AppState::InGame), exit);
+    }
+}
+
+fn exit(mut commands: Commands) {
+    commands.insert_resource(ServerClock::default());
+}
+
+fn send_time_sync_requests(
+    time: Res<Time<Real>>,
+    mut clock: ResMut<ServerClock>,
+    mut evw_request: EventWriter<TimeSyncRequestEvent>,
+) {
+    if !clock.sync_timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    evw_request.send(TimeSyncRequestEvent {
+        client_time: time.elapsed_secs_f64(),
+    });
+}
+
+fn handle_time_sync_responses(
+    time: Res<Time<Real>>,
+    mut fixed_time: ResMut<Time<Fixed>>,
+    mut clock: ResMut<ServerClock>,
+    mut evr_response: EventReader<TimeSyncResponseEvent>,
+) {
+    let now = time.elapsed_secs_f64();
+
+    for evt in evr_response.read() {
+        let rtt = now - evt.client_time;
+        if rtt < 0.0 {
+            continue;
+        }
+
+        // assume the trip there took as long as the trip back
+        let offset = evt.server_time + rtt * 0.5 - now;
+        clock.add_sample(TimeSample { rtt, offset }, now);
+
+        clock.tick = evt.tick;
+        clock.tick_time = evt.server_time;
+        clock.tick_secs = evt.tick_secs;
+
+        // input is sampled and predicted per tick, so we have to tick at the server's rate
+        let timestep = Duration::from_secs_f64(evt.tick_secs);
+        if evt.tick_secs > 0.0 && fixed_time.timestep() != timestep {
+            info!("matching server tick rate of {:.1}hz", 1.0 / evt.tick_secs);
+            fixed_time.set_timestep(timestep);
+        }
+    }
+}
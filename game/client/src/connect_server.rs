@@ -1,20 +1,20 @@
-use std::net::UdpSocket;
+use std::collections::VecDeque;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
 
 use bevy::prelude::*;
 use bevy_mod_reqwest::*;
 use bevy_replicon::prelude::*;
 use bevy_replicon_renet::{
-    netcode::{ClientAuthentication, NetcodeClientTransport},
+    netcode::{ClientAuthentication, ConnectToken, NetcodeClientTransport, NetcodeTransportError},
     renet::{ConnectionConfig, RenetClient},
     RenetChannelsExt,
 };
 
-use common::gameclient::*;
+use common::{gameclient::*, netcode, read_reqwest_error, ErrorCode};
 use game_common::{
     cleanup_state,
     network::{ConnectEvent, PlayerClientId},
     utils::current_timestamp,
-    PROTOCOL_ID,
 };
 
 use crate::{api, client, options::Options, ui, AppState};
@@ -25,13 +25,31 @@ struct Status;
 #[derive(Debug, Component)]
 struct OnConnectServer;
 
+// server addresses left to try, one at a time with a short timeout
+// NOTE: netcode servers reject a token that's already been used from a different
+// client address, so this only helps when earlier candidates are unreachable
+#[derive(Debug, Resource)]
+struct ConnectAttempts {
+    connect_token: ConnectToken,
+    candidates: VecDeque<SocketAddr>,
+    timer: Timer,
+}
+
 #[derive(Debug)]
 pub struct ConnectServerPlugin;
 
 impl Plugin for ConnectServerPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(OnEnter(AppState::ConnectToServer), enter)
-            .add_systems(Update, connected.run_if(client_just_connected))
+            .add_systems(
+                Update,
+                (
+                    update_connect_attempts
+                        .run_if(in_state(AppState::ConnectToServer))
+                        .run_if(resource_exists::<ConnectAttempts>),
+                    connected.run_if(client_just_connected),
+                ),
+            )
             .add_systems(
                 OnExit(AppState::ConnectToServer),
                 (
@@ -43,33 +61,65 @@ impl Plugin for ConnectServerPlugin {
     }
 }
 
-fn on_cancel(trigger: Trigger<Pointer<Click>>, mut app_state: ResMut<NextState<AppState>>) {
+fn on_cancel(
+    trigger: Trigger<Pointer<Click>>,
+    mut commands: Commands,
+    mut app_state: ResMut<NextState<AppState>>,
+) {
     if trigger.button == PointerButton::Primary {
+        // drop any in progress connection attempt
+        commands.remove_resource::<RenetClient>();
+        commands.remove_resource::<NetcodeClientTransport>();
+
         app_state.set(AppState::MainMenu);
     }
 }
 
+fn read_connect_token(connect_token: &str) -> anyhow::Result<ConnectToken> {
+    let connect_token = netcode::decode_connect_token(connect_token)?;
+    Ok(ConnectToken::read(&mut connect_token.as_slice())?)
+}
+
 fn on_find_server(
     req: Trigger<ReqwestResponseEvent>,
     mut commands: Commands,
+    options: Res<Options>,
     channels: Res<RepliconChannels>,
     mut status_query: Query<&mut Text, With<Status>>,
     mut app_state: ResMut<NextState<AppState>>,
 ) {
-    let resp = req.event().as_str().unwrap();
+    let resp = req.event();
+    if let Some(err) = read_reqwest_error(resp) {
+        error!("find server failed: {:?} {}", err.code, err.message);
 
-    let resp: FindServerResponseV1 = serde_json::from_str(resp).unwrap();
-    if resp.address.is_empty() {
-        error!("find server failed");
-        app_state.set(AppState::MainMenu);
+        // leave the user on this screen to cancel back out
+        status_query.single_mut().0 = match err.code {
+            ErrorCode::NoCapacity => "No servers available, try again later".to_owned(),
+            ErrorCode::Timeout => "Timed out waiting for a server, try again later".to_owned(),
+            ErrorCode::RateLimited | ErrorCode::Conflict => {
+                "Too many requests, try again later".to_owned()
+            }
+            _ => "Failed to find a server".to_owned(),
+        };
         return;
     }
 
+    let resp: FindServerResponseV1 = serde_json::from_str(resp.as_str().unwrap()).unwrap();
+    let connect_token = match read_connect_token(&resp.connect_token) {
+        Ok(connect_token) => connect_token,
+        Err(err) => {
+            error!("invalid connect token: {}", err);
+            app_state.set(AppState::MainMenu);
+            return;
+        }
+    };
+
     connect_to_server(
         &mut commands,
         &channels,
-        resp.address,
-        resp.port,
+        &options,
+        &resp,
+        connect_token,
         &mut status_query,
     );
 }
@@ -101,7 +151,7 @@ fn enter(
         ui::spawn_button(parent, &asset_server, "Cancel").observe(on_cancel);
     });
 
-    api::find_server(&mut client, options.user_id)
+    api::find_server(&mut client, &options.api_url, options.user_id)
         .unwrap()
         .on_response(on_find_server)
         .on_error(on_find_server_error);
@@ -111,30 +161,58 @@ fn exit(mut commands: Commands) {
     info!("exiting connect server ...");
 
     commands.remove_resource::<ClearColor>();
+    commands.remove_resource::<ConnectAttempts>();
 }
 
-fn connect_to_server(
+// interleave the address families, preferring v6 (RFC 8305)
+fn connect_candidates(resp: &FindServerResponseV1) -> VecDeque<SocketAddr> {
+    let parse = |addrs: &[String]| {
+        addrs
+            .iter()
+            .filter_map(|addr| addr.parse::<IpAddr>().ok())
+            .map(|ip| SocketAddr::new(ip, resp.port))
+            .collect::<Vec<_>>()
+    };
+
+    let mut v6addrs = parse(&resp.v6addrs).into_iter();
+    let mut v4addrs = parse(&resp.v4addrs).into_iter();
+
+    let mut candidates = VecDeque::new();
+    loop {
+        let v6addr = v6addrs.next();
+        let v4addr = v4addrs.next();
+        if v6addr.is_none() && v4addr.is_none() {
+            break;
+        }
+        candidates.extend(v6addr);
+        candidates.extend(v4addr);
+    }
+    candidates
+}
+
+fn try_connect(
     commands: &mut Commands,
     channels: &RepliconChannels,
-    address: impl AsRef<str>,
-    port: u16,
-    status_query: &mut Query<&mut Text, With<Status>>,
-) {
-    info!("connect to server ...");
-
-    status_query.single_mut().0 = "Connecting to server ...".to_owned();
+    connect_token: &ConnectToken,
+    server_addr: SocketAddr,
+) -> anyhow::Result<()> {
+    // the server validates against the addresses in the private part of the token
+    // so only the public list needs to be narrowed down to this candidate
+    let mut connect_token = connect_token.clone();
+    connect_token.server_addresses.fill(None);
+    connect_token.server_addresses[0] = Some(server_addr);
 
-    let address = address.as_ref();
-    let server_addr = format!("{}:{}", address, port).parse().unwrap();
-    let socket = UdpSocket::bind(format!("{}:0", address)).unwrap();
-    let current_time = current_timestamp();
-    let client_id = current_time.as_millis() as u64;
-    let authentication = ClientAuthentication::Unsecure {
-        client_id,
-        protocol_id: PROTOCOL_ID,
-        server_addr,
-        user_data: None,
+    let bind_addr = if server_addr.is_ipv6() {
+        SocketAddr::new(Ipv6Addr::UNSPECIFIED.into(), 0)
+    } else {
+        SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), 0)
     };
+    let socket = UdpSocket::bind(bind_addr)?;
+    let current_time = current_timestamp();
+    let client_id = connect_token.client_id;
+    let authentication = ClientAuthentication::Secure { connect_token };
+
+    let transport = NetcodeClientTransport::new(current_time, authentication, socket)?;
 
     info!("connecting to {} as {} ...", server_addr, client_id);
 
@@ -144,12 +222,100 @@ fn connect_to_server(
         ..Default::default()
     });
     commands.insert_resource(client);
-
-    let transport = NetcodeClientTransport::new(current_time, authentication, socket).unwrap();
     commands.insert_resource(transport);
 
-    commands.insert_resource(client::ClientState::new_remote(address));
+    commands.insert_resource(client::ClientState::new_remote(
+        server_addr.ip().to_string(),
+    ));
     commands.insert_resource(PlayerClientId::new(ClientId::new(client_id)));
+
+    Ok(())
+}
+
+// returns false once every candidate has been tried
+fn start_next_attempt(
+    commands: &mut Commands,
+    channels: &RepliconChannels,
+    attempts: &mut ConnectAttempts,
+) -> bool {
+    while let Some(server_addr) = attempts.candidates.pop_front() {
+        match try_connect(commands, channels, &attempts.connect_token, server_addr) {
+            Ok(()) => {
+                attempts.timer.reset();
+                return true;
+            }
+            Err(err) => warn!("failed to connect to {}: {}", server_addr, err),
+        }
+    }
+
+    false
+}
+
+fn connect_to_server(
+    commands: &mut Commands,
+    channels: &RepliconChannels,
+    options: &Options,
+    resp: &FindServerResponseV1,
+    connect_token: ConnectToken,
+    status_query: &mut Query<&mut Text, With<Status>>,
+) {
+    info!("connect to server ...");
+
+    status_query.single_mut().0 = "Connecting to server ...".to_owned();
+
+    let mut attempts = ConnectAttempts {
+        connect_token,
+        candidates: connect_candidates(resp),
+        timer: Timer::new(options.connect_attempt_timeout(), TimerMode::Once),
+    };
+    debug!("connect candidates: {:?}", attempts.candidates);
+
+    if !start_next_attempt(commands, channels, &mut attempts) {
+        error!("no server addresses to connect to");
+        status_query.single_mut().0 = "Failed to connect to server".to_owned();
+        return;
+    }
+
+    commands.insert_resource(attempts);
+}
+
+fn update_connect_attempts(
+    mut commands: Commands,
+    time: Res<Time>,
+    channels: Res<RepliconChannels>,
+    client: Option<Res<RenetClient>>,
+    mut attempts: ResMut<ConnectAttempts>,
+    mut evr_error: EventReader<NetcodeTransportError>,
+    mut status_query: Query<&mut Text, With<Status>>,
+) {
+    if client.as_ref().is_some_and(|client| client.is_connected()) {
+        return;
+    }
+
+    let mut failed = client.is_none_or(|client| client.is_disconnected());
+    for evt in evr_error.read() {
+        warn!("connect attempt failed: {}", evt);
+        failed = true;
+    }
+
+    // give the last candidate the full netcode timeout
+    let timed_out = attempts.timer.tick(time.delta()).finished() && !attempts.candidates.is_empty();
+    if !failed && !timed_out {
+        return;
+    }
+
+    if timed_out {
+        info!("connect attempt timed out, trying next address");
+    }
+
+    if !start_next_attempt(&mut commands, &channels, &mut attempts) {
+        error!("failed to connect to the server");
+        status_query.single_mut().0 = "Failed to connect to server".to_owned();
+
+        commands.remove_resource::<ConnectAttempts>();
+        commands.remove_resource::<RenetClient>();
+        commands.remove_resource::<NetcodeClientTransport>();
+    }
 }
 
 fn connected(
@@ -11,7 +11,7 @@ use bevy_replicon_renet::renet::RenetClient;
 
 use game_common::GameState;
 
-use crate::AppState;
+use crate::{clock::ServerClock, AppState};
 
 #[derive(Debug, Default, Reflect, Resource)]
 pub struct DebugSettings {
@@ -68,7 +68,9 @@ impl Plugin for DebugPlugin {
 fn debug_ui(
     time: Res<Time>,
     diagnostics: Res<DiagnosticsStore>,
+    real_time: Res<Time<Real>>,
     client: Option<Res<RenetClient>>,
+    server_clock: Res<ServerClock>,
     mut debug_settings: ResMut<DebugSettings>,
     mut contexts: EguiContexts,
     gamepads: Query<(&Name, &Gamepad)>,
@@ -127,6 +129,18 @@ fn debug_ui(
                         info.bytes_sent_per_second * 0.001,
                         info.bytes_received_per_second * 0.001,
                     ));
+
+                    let now = real_time.elapsed_secs_f64();
+                    if let Some(offset) = server_clock.offset() {
+                        ui.label(format!(
+                            "{:.2}ms clock offset, {:.1}ppm drift",
+                            offset * 1000.0,
+                            server_clock.drift() * 1_000_000.0,
+                        ));
+                    }
+                    if let Some(tick) = server_clock.server_tick(now) {
+                        ui.label(format!("server tick {}", tick));
+                    }
                 } else {
                     ui.label("Client disconnected");
                 }
@@ -34,10 +34,13 @@ impl Plugin for GamePlugin {
     }
 }
 
-fn enter(mut window_query: Query<&mut Window, With<PrimaryWindow>>) {
+fn enter(options: Res<Options>, mut window_query: Query<&mut Window, With<PrimaryWindow>>) {
     info!("entering client game ...");
 
-    show_cursor(window_query.get_single_mut().as_mut().ok(), false);
+    // bots don't need the cursor, leave it for the real player
+    if !options.bot {
+        show_cursor(window_query.get_single_mut().as_mut().ok(), false);
+    }
 }
 
 #[allow(clippy::type_complexity)]
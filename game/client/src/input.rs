@@ -43,19 +43,27 @@ fn should_update_input(
 
 impl Plugin for InputPlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<JumpPressedEvent>().add_systems(
-            Update,
-            (
-                handle_gamepad_events,
-                (update_mnk, (update_gamepad.after(handle_gamepad_events)))
-                    .run_if(should_update_input)
-                    .run_if(in_state(GameState::InGame)),
-            )
-                .in_set(InputSet),
-        );
+        app.add_event::<JumpPressedEvent>()
+            .add_systems(PreUpdate, reset_input)
+            .add_systems(
+                Update,
+                (
+                    handle_gamepad_events,
+                    (update_mnk, (update_gamepad.after(handle_gamepad_events)))
+                        .run_if(should_update_input)
+                        .run_if(in_state(GameState::InGame)),
+                )
+                    .in_set(InputSet),
+            );
     }
 }
 
+// input is gathered fresh each frame, crouch carries over since it can be toggled
+fn reset_input(mut input_state: ResMut<InputState>) {
+    input_state.look = Vec2::default();
+    input_state.r#move = Vec2::default();
+}
+
 fn handle_gamepad_events(
     mut commands: Commands,
     gamepad: Option<Res<ConnectedGamepad>>,
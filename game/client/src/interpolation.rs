@@ -0,0 +1,179 @@
+use std::collections::VecDeque;
+
+use bevy::{prelude::*, transform::TransformSystem};
+use bevy_replicon::prelude::*;
+
+use game_common::{dynamic::Dynamic, network::PlayerClientId, player::Player, TransformTick};
+
+use crate::{clock::ServerClock, options::Options};
+
+// anything that moves further than this between updates teleported
+const TELEPORT_DISTANCE: f32 = 5.0;
+
+#[derive(Debug, Copy, Clone)]
+struct Snapshot {
+    // server tick it was simulated on, when it arrived is just network jitter
+    tick: u32,
+    transform: Transform,
+}
+
+// replicated transforms are buffered here and rendered behind the server
+#[derive(Debug, Default, Component)]
+struct Interpolated {
+    snapshots: VecDeque<Snapshot>,
+}
+
+#[derive(Debug)]
+pub struct InterpolationPlugin;
+
+impl Plugin for InterpolationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PreUpdate,
+            (track_interpolated, receive_snapshots)
+                .chain()
+                .after(ClientSet::Receive)
+                .run_if(client_connected),
+        )
+        .add_systems(
+            PostUpdate,
+            interpolate
+                .before(TransformSystem::TransformPropagate)
+                .run_if(client_connected),
+        );
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn track_interpolated(
+    mut commands: Commands,
+    client_id: Res<PlayerClientId>,
+    query: Query<
+        (Entity, Option<&Player>),
+        (Or<(With<Player>, With<Dynamic>)>, Without<Interpolated>),
+    >,
+) {
+    for (entity, player) in &query {
+        // our own player is predicted
+        if player.is_some_and(|player| player.client_id == client_id.get_client_id()) {
+            continue;
+        }
+
+        commands.entity(entity).insert(Interpolated::default());
+    }
+}
+
+// the client ticks at the server's rate, so the delay can be measured in ticks
+fn delay_ticks(options: &Options, fixed_time: &Time<Fixed>) -> f64 {
+    options.interpolation_delay().as_secs_f64() / fixed_time.timestep().as_secs_f64()
+}
+
+fn receive_snapshots(
+    fixed_time: Res<Time<Fixed>>,
+    options: Res<Options>,
+    mut query: Query<(&Transform, &TransformTick, &mut Interpolated)>,
+) {
+    let delay_ticks = delay_ticks(&options, &fixed_time);
+
+    for (transform, tick, mut interpolated) in &mut query {
+        // Transform only comes from the server when the tick does
+        let last = interpolated.snapshots.back().copied();
+        if last.is_some_and(|last| tick.0 <= last.tick) {
+            continue;
+        }
+
+        match last {
+            Some(last)
+                if last.transform.translation.distance(transform.translation)
+                    > TELEPORT_DISTANCE =>
+            {
+                debug!("snapping teleported entity to {}", transform.translation);
+                interpolated.snapshots.clear();
+            }
+            // nothing was sent for longer than we buffer, so it sat still until just now
+            Some(last) if (tick.0 - last.tick) as f64 > delay_ticks => {
+                interpolated.snapshots.push_back(Snapshot {
+                    tick: tick.0 - 1,
+                    transform: last.transform,
+                });
+            }
+            _ => (),
+        }
+
+        interpolated.snapshots.push_back(Snapshot {
+            tick: tick.0,
+            transform: *transform,
+        });
+    }
+}
+
+fn interpolate(
+    time: Res<Time<Real>>,
+    fixed_time: Res<Time<Fixed>>,
+    clock: Res<ServerClock>,
+    options: Res<Options>,
+    mut query: Query<(&mut Transform, &mut Interpolated)>,
+) {
+    // nothing to render against until the clock is synced
+    let Some(server_ticks) = clock.server_ticks(time.elapsed_secs_f64()) else {
+        return;
+    };
+
+    let render_tick = server_ticks - delay_ticks(&options, &fixed_time);
+    let max_extrapolation =
+        options.max_extrapolation().as_secs_f64() / fixed_time.timestep().as_secs_f64();
+
+    for (mut transform, mut interpolated) in &mut query {
+        // drop what we've rendered past, but keep two around to interpolate or extrapolate from
+        while interpolated.snapshots.len() > 2
+            && interpolated.snapshots[1].tick as f64 <= render_tick
+        {
+            interpolated.snapshots.pop_front();
+        }
+
+        *transform = match (
+            interpolated.snapshots.front(),
+            interpolated.snapshots.get(1),
+        ) {
+            (Some(from), Some(to)) if render_tick <= to.tick as f64 => lerp(from, to, render_tick),
+            // out of updates, keep going the way it was for a little while
+            (Some(from), Some(to)) => extrapolate(
+                from,
+                to,
+                (render_tick - to.tick as f64).min(max_extrapolation),
+            ),
+            (Some(from), None) => from.transform,
+            (None, _) => continue,
+        };
+    }
+}
+
+fn lerp(from: &Snapshot, to: &Snapshot, tick: f64) -> Transform {
+    let span = (to.tick - from.tick) as f64;
+    let t = if span > 0.0 {
+        ((tick - from.tick as f64) / span).clamp(0.0, 1.0) as f32
+    } else {
+        1.0
+    };
+
+    Transform {
+        translation: from.transform.translation.lerp(to.transform.translation, t),
+        rotation: from.transform.rotation.slerp(to.transform.rotation, t),
+        scale: from.transform.scale.lerp(to.transform.scale, t),
+    }
+}
+
+fn extrapolate(from: &Snapshot, to: &Snapshot, ticks: f64) -> Transform {
+    let span = (to.tick - from.tick) as f32;
+    if span <= 0.0 {
+        return to.transform;
+    }
+
+    // per tick
+    let velocity = (to.transform.translation - from.transform.translation) / span;
+
+    Transform {
+        translation: to.transform.translation + velocity * ticks.max(0.0) as f32,
+        ..to.transform
+    }
+}
@@ -1,14 +1,18 @@
 mod api;
+mod bot;
 mod camera;
 mod client;
+mod clock;
 mod connect_server;
 mod debug;
 mod game;
 mod game_menu;
 mod input;
+mod interpolation;
 mod main_menu;
 mod notifs;
 mod options;
+mod prediction;
 mod settings;
 mod ui;
 
@@ -16,7 +20,7 @@ use bevy::{prelude::*, window::CursorGrabMode};
 use bevy_replicon::prelude::*;
 use bevy_replicon_renet::RepliconRenetPlugins;
 use bevy_tokio_tasks::TokioTasksPlugin;
-use clap::Parser;
+use common::config;
 
 use options::Options;
 use settings::Settings;
@@ -50,11 +54,17 @@ pub fn show_cursor(window: Option<&mut Mut<'_, Window>>, show: bool) {
     }
 }
 
-fn main() {
-    let options = Options::parse();
+fn main() -> anyhow::Result<()> {
+    let options: Options = config::load(options::ENV_PREFIX)?;
 
     println!("initializing client ...");
 
+    let title = if options.bot {
+        format!("Bevy Multiplayer Jam (bot {})", options.user_id)
+    } else {
+        "Bevy Multiplayer Jam".to_string()
+    };
+
     let mut app = App::new();
     app
         // bevy plugins
@@ -62,7 +72,7 @@ fn main() {
             DefaultPlugins
                 .set(WindowPlugin {
                     primary_window: Some(Window {
-                        title: "Bevy Multiplayer Jam".into(),
+                        title,
                         resolution: DEFAULT_RESOLUTION.into(),
                         ..default()
                     }),
@@ -83,9 +93,6 @@ fn main() {
             avian3d::debug_render::PhysicsDebugPlugin::default(),
             RepliconPlugins,
             RepliconRenetPlugins,
-            /*bevy_replicon_snap::SnapshotInterpolationPlugin {
-                max_tick_rate: game_common::SERVER_TICK_RATE,
-            },*/
             TokioTasksPlugin::default(),
             bevy_mod_reqwest::ReqwestPlugin::default(),
             bevy_mod_websocket::WebSocketPlugin,
@@ -106,4 +113,6 @@ fn main() {
 
     info!("running client ...");
     app.run();
+
+    Ok(())
 }
@@ -1,29 +1,44 @@
-use bevy::{prelude::*, utils::Duration};
+use bevy::prelude::*;
 use bevy_mod_websocket::*;
 use tokio_tungstenite::tungstenite::client::IntoClientRequest;
 
 use common::user::UserId;
 
-const HOST: &str = "ws://localhost:8001";
-const RETRY_INTERVAL: Duration = Duration::from_secs(10);
+use crate::options::Options;
 
 fn on_success(trigger: Trigger<WebSocketConnectSuccessEvent>) {
     let evt = trigger.event();
     info!("subscribe success: {:?}", evt);
 }
 
-fn on_error(trigger: Trigger<WebSocketErrorEvent>, mut ws_client: WebSocketClient) {
+fn on_error(
+    trigger: Trigger<WebSocketErrorEvent>,
+    options: Res<Options>,
+    mut ws_client: WebSocketClient,
+) {
     let evt = trigger.event();
     warn!("notifs error: {:?}", evt.error);
 
-    ws_client.retry(trigger.entity(), evt.request.clone(), RETRY_INTERVAL);
+    ws_client.retry(
+        trigger.entity(),
+        evt.request.clone(),
+        options.notifs_retry_interval(),
+    );
 }
 
-fn on_disconnect(trigger: Trigger<WebSocketDisconnectEvent>, mut ws_client: WebSocketClient) {
+fn on_disconnect(
+    trigger: Trigger<WebSocketDisconnectEvent>,
+    options: Res<Options>,
+    mut ws_client: WebSocketClient,
+) {
     let evt = trigger.event();
     warn!("notifs disconnect");
 
-    ws_client.retry(trigger.entity(), evt.request.clone(), RETRY_INTERVAL);
+    ws_client.retry(
+        trigger.entity(),
+        evt.request.clone(),
+        options.notifs_retry_interval(),
+    );
 }
 
 fn on_message(trigger: Trigger<WebSocketMessageEvent>) {
@@ -43,9 +58,13 @@ fn on_message(trigger: Trigger<WebSocketMessageEvent>) {
     }
 }
 
-pub fn subscribe<'a>(client: &'a mut WebSocketClient, user_id: UserId) -> WebSocketBuilder<'a> {
+pub fn subscribe<'a>(
+    client: &'a mut WebSocketClient,
+    notifs_url: &str,
+    user_id: UserId,
+) -> WebSocketBuilder<'a> {
     // TODO: get rid of the need to call into_client_request so we can drop the tungstenite dependency
-    let mut notifs_request = format!("{}/gameclient/notifs/v1", HOST)
+    let mut notifs_request = format!("{}/gameclient/notifs/v1", notifs_url)
         .into_client_request()
         .unwrap();
     let headers = notifs_request.headers_mut();
@@ -1,10 +1,75 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::Duration};
 use clap::Parser;
+use serde::{Deserialize, Serialize};
 
-use common::user::UserId;
+use common::{config::ConfigArgs, user::UserId};
 
-#[derive(Parser, Debug, Resource)]
+pub const ENV_PREFIX: &str = "BMP_CLIENT_";
+
+#[derive(Parser, Debug, Resource, Serialize, Deserialize)]
 pub struct Options {
+    #[command(flatten)]
+    #[serde(skip)]
+    pub config: ConfigArgs,
+
     #[arg(default_value_t = UserId::new_v4())]
     pub user_id: UserId,
+
+    #[arg(long, default_value = "http://localhost:8000")]
+    pub api_url: String,
+
+    #[arg(long, default_value = "ws://localhost:8001")]
+    pub notifs_url: String,
+
+    #[arg(long, default_value_t = 10)]
+    pub notifs_retry_interval_secs: u64,
+
+    // how long to wait on each server address before trying the next
+    #[arg(long, default_value_t = 2000)]
+    pub connect_attempt_timeout_ms: u64,
+
+    // play automatically, for load testing and the dev stack
+    #[arg(long)]
+    #[serde(default)]
+    pub bot: bool,
+
+    // how long a bot waits before finding a (new) server
+    #[arg(long, default_value_t = 5)]
+    pub bot_find_server_delay_secs: u64,
+
+    // how far behind the server's current tick other players and dynamics are rendered,
+    // enough to cover the trip from the server and ride out a little network jitter
+    #[arg(long, default_value_t = 100)]
+    pub interpolation_delay_ms: u64,
+
+    // how long to keep things moving when updates stop coming
+    #[arg(long, default_value_t = 250)]
+    pub max_extrapolation_ms: u64,
+}
+
+impl Options {
+    #[inline]
+    pub fn notifs_retry_interval(&self) -> Duration {
+        Duration::from_secs(self.notifs_retry_interval_secs)
+    }
+
+    #[inline]
+    pub fn connect_attempt_timeout(&self) -> Duration {
+        Duration::from_millis(self.connect_attempt_timeout_ms)
+    }
+
+    #[inline]
+    pub fn bot_find_server_delay(&self) -> Duration {
+        Duration::from_secs(self.bot_find_server_delay_secs)
+    }
+
+    #[inline]
+    pub fn interpolation_delay(&self) -> Duration {
+        Duration::from_millis(self.interpolation_delay_ms)
+    }
+
+    #[inline]
+    pub fn max_extrapolation(&self) -> Duration {
+        Duration::from_millis(self.max_extrapolation_ms)
+    }
 }
@@ -0,0 +1,310 @@
+use std::collections::VecDeque;
+
+use avian3d::prelude::*;
+use bevy::{prelude::*, utils::Duration};
+use bevy_replicon::prelude::*;
+use bevy_tnua::prelude::*;
+
+use game_common::{
+    network::InputUpdateEvent,
+    player::{self, LocalPlayer, PlayerAck, PredictedPlayer, PredictedPlayerModel},
+    GameState,
+};
+
+use crate::{client::SendInputSet, AppState};
+
+// how far off the server can be before we rewind
+const CORRECTION_THRESHOLD: f32 = 0.1;
+
+// anything bigger is a teleport and isn't worth smoothing
+const MAX_SMOOTHED_CORRECTION: f32 = 5.0;
+
+// how quickly the model catches up to a correction
+const CORRECTION_SMOOTHING: f32 = 10.0;
+
+// a couple seconds of ticks, anything older isn't getting acked
+const MAX_HISTORY: usize = 128;
+
+#[derive(Debug, Copy, Clone)]
+struct PredictedInput {
+    sequence: u32,
+    r#move: Vec2,
+    jump: bool,
+
+    // facing once the input's look was applied
+    rotation: Quat,
+
+    // where the input left us, None until it's been simulated
+    translation: Option<Vec3>,
+}
+
+// where the server had us after an input
+#[derive(Debug, Copy, Clone)]
+struct ServerState {
+    translation: Vec3,
+    velocity: Vec3,
+}
+
+#[derive(Debug, Default, Resource)]
+struct Prediction {
+    // inputs the server hasn't acked yet
+    history: VecDeque<PredictedInput>,
+
+    // server state to rewind to
+    correction: Option<ServerState>,
+
+    // how far the model is from where the simulation put us
+    smoothing_offset: Vec3,
+}
+
+#[derive(Debug)]
+pub struct PredictionPlugin;
+
+impl Plugin for PredictionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Prediction>()
+            .add_systems(
+                PreUpdate,
+                (check_prediction, replay_prediction)
+                    .chain()
+                    .after(ClientSet::Receive)
+                    .run_if(client_connected)
+                    .run_if(in_state(GameState::InGame)),
+            )
+            .add_systems(
+                Update,
+                smooth_corrections
+                    .run_if(client_connected)
+                    .run_if(in_state(GameState::InGame)),
+            )
+            .add_systems(
+                FixedUpdate,
+                predict_local_player
+                    .after(SendInputSet)
+                    .run_if(client_connected)
+                    .run_if(in_state(GameState::InGame)),
+            )
+            .add_systems(
+                FixedPostUpdate,
+                record_prediction
+                    .after(PhysicsSet::Sync)
+                    .run_if(client_connected)
+                    .run_if(in_state(GameState::InGame)),
+            )
+            .add_systems(OnExit(AppState::InGame), exit);
+    }
+}
+
+fn exit(mut commands: Commands) {
+    commands.insert_resource(Prediction::default());
+}
+
+// one physics step outside of the fixed loop, the same way avian runs it
+// this steps every body, so the predicted player has to be the only dynamic one on the client
+// (everything replicated only gets a collider)
+fn step_physics(world: &mut World, timestep: Duration) {
+    let old_clock = world.resource::<Time>().as_generic();
+
+    world.resource_mut::<Time<Physics>>().advance_by(timestep);
+    *world.resource_mut::<Time>() = world.resource::<Time<Physics>>().as_generic();
+
+    world.run_schedule(PhysicsSchedule);
+
+    *world.resource_mut::<Time>() = old_clock;
+}
+
+fn predict_local_player(
+    time: Res<Time>,
+    mut prediction: ResMut<Prediction>,
+    mut evr_input_update: EventReader<InputUpdateEvent>,
+    mut player_query: Query<(&mut Transform, &mut TnuaController), With<PredictedPlayer>>,
+) {
+    // the input we just sent is the newest one in the update
+    let Some(input) = evr_input_update
+        .read()
+        .last()
+        .and_then(|evt| evt.inputs.last().copied())
+    else {
+        return;
+    };
+
+    let Ok((mut transform, mut character_controller)) = player_query.get_single_mut() else {
+        return;
+    };
+
+    player::rotate(&mut transform, input.input_state.look, time.delta_secs());
+    player::walk(
+        &mut character_controller,
+        transform.rotation,
+        input.input_state.r#move,
+    );
+    if input.jump {
+        player::jump(&mut character_controller);
+    }
+
+    prediction.history.push_back(PredictedInput {
+        sequence: input.sequence,
+        r#move: input.input_state.r#move,
+        jump: input.jump,
+        rotation: transform.rotation,
+        translation: None,
+    });
+    if prediction.history.len() > MAX_HISTORY {
+        prediction.history.pop_front();
+    }
+}
+
+fn record_prediction(
+    mut prediction: ResMut<Prediction>,
+    player_query: Query<&Position, With<PredictedPlayer>>,
+) {
+    let Ok(position) = player_query.get_single() else {
+        return;
+    };
+
+    if let Some(predicted) = prediction.history.back_mut() {
+        if predicted.translation.is_none() {
+            predicted.translation = Some(position.0);
+        }
+    }
+}
+
+fn check_prediction(
+    mut prediction: ResMut<Prediction>,
+    server_query: Query<(&Transform, &PlayerAck), (With<LocalPlayer>, Changed<PlayerAck>)>,
+) {
+    let Ok((transform, ack)) = server_query.get_single() else {
+        return;
+    };
+
+    let Some(sequence) = ack.sequence else {
+        return;
+    };
+
+    // everything up to the ack is settled
+    let mut acked = None;
+    while prediction
+        .history
+        .front()
+        .is_some_and(|predicted| predicted.sequence <= sequence)
+    {
+        acked = prediction.history.pop_front();
+    }
+
+    let Some(predicted_translation) = acked
+        .filter(|acked| acked.sequence == sequence)
+        .and_then(|acked| acked.translation)
+    else {
+        return;
+    };
+
+    let error = predicted_translation.distance(transform.translation);
+    if error > CORRECTION_THRESHOLD {
+        debug!("misprediction at input {}, off by {}", sequence, error);
+
+        prediction.correction = Some(ServerState {
+            translation: transform.translation,
+            velocity: ack.velocity,
+        });
+    }
+}
+
+// rewind to the server state and replay everything it hasn't seen yet
+fn replay_prediction(world: &mut World) {
+    let Some(server_state) = world.resource_mut::<Prediction>().correction.take() else {
+        return;
+    };
+
+    let mut player_query = world.query_filtered::<(
+        &mut Transform,
+        &mut Position,
+        &mut LinearVelocity,
+        &mut TnuaController,
+    ), With<PredictedPlayer>>();
+
+    let Ok((_, mut position, mut velocity, _)) = player_query.get_single_mut(world) else {
+        return;
+    };
+    let predicted_translation = position.0;
+
+    position.0 = server_state.translation;
+    velocity.0 = server_state.velocity;
+
+    // the replay is a rerun of ticks already simulated, the clock shouldn't move for it
+    let physics_clock = *world.resource::<Time<Physics>>();
+
+    let timestep = world.resource::<Time<Fixed>>().timestep();
+    let replay = world
+        .resource::<Prediction>()
+        .history
+        .iter()
+        .copied()
+        .enumerate()
+        .filter(|(_, predicted)| predicted.translation.is_some())
+        .collect::<Vec<_>>();
+
+    for (idx, predicted) in replay {
+        if let Ok((_, _, _, mut character_controller)) = player_query.get_single_mut(world) {
+            player::walk(
+                &mut character_controller,
+                predicted.rotation,
+                predicted.r#move,
+            );
+            if predicted.jump {
+                player::jump(&mut character_controller);
+            }
+        }
+
+        step_physics(world, timestep);
+
+        let Ok((_, position, _, _)) = player_query.get_single(world) else {
+            break;
+        };
+        let translation = position.0;
+        world.resource_mut::<Prediction>().history[idx].translation = Some(translation);
+    }
+
+    *world.resource_mut::<Time<Physics>>() = physics_clock;
+
+    let Ok((mut transform, position, _, _)) = player_query.get_single_mut(world) else {
+        return;
+    };
+    transform.translation = position.0;
+    let corrected_translation = position.0;
+
+    debug!(
+        "corrected prediction by {}",
+        predicted_translation.distance(corrected_translation)
+    );
+
+    // keep the model where it was and ease it over
+    let mut prediction = world.resource_mut::<Prediction>();
+    prediction.smoothing_offset += predicted_translation - corrected_translation;
+    if prediction.smoothing_offset.length() > MAX_SMOOTHED_CORRECTION {
+        prediction.smoothing_offset = Vec3::ZERO;
+    }
+}
+
+fn smooth_corrections(
+    time: Res<Time>,
+    mut prediction: ResMut<Prediction>,
+    player_query: Query<&Transform, (With<PredictedPlayer>, Without<PredictedPlayerModel>)>,
+    mut model_query: Query<&mut Transform, With<PredictedPlayerModel>>,
+) {
+    let (Ok(transform), Ok(mut model_transform)) =
+        (player_query.get_single(), model_query.get_single_mut())
+    else {
+        return;
+    };
+
+    let smoothing_offset =
+        prediction.smoothing_offset * (-CORRECTION_SMOOTHING * time.delta_secs()).exp();
+    prediction.smoothing_offset = if smoothing_offset.length_squared() < 0.0001 {
+        Vec3::ZERO
+    } else {
+        smoothing_offset
+    };
+
+    // the model is a child, so undo the player's facing
+    model_transform.translation = transform.rotation.inverse() * prediction.smoothing_offset;
+}
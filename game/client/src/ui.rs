@@ -119,3 +119,26 @@ pub fn spawn_label<'a>(
         PickingBehavior::IGNORE,
     ))
 }
+
+// message across the top of the screen
+pub fn spawn_banner<'a>(
+    commands: &'a mut Commands,
+    asset_server: &AssetServer,
+    text: impl Into<String>,
+    top: f32,
+) -> EntityCommands<'a> {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            top: Val::Px(top),
+            ..default()
+        },
+        Text::new(text),
+        TextFont::from_font(asset_server.load(BUTTON_FONT)).with_font_size(BUTTON_FONT_SIZE),
+        TextColor(BUTTON_FONT_COLOR),
+        TextLayout::new_with_justify(JustifyText::Center),
+        Name::new("Banner"),
+        PickingBehavior::IGNORE,
+    ))
+}
@@ -3,7 +3,7 @@ use bevy::{color::palettes::css, prelude::*};
 use bevy_replicon::prelude::*;
 use serde::{Deserialize, Serialize};
 
-use crate::{game::OnInGame, GameAssetState};
+use crate::{game::OnInGame, GameAssetState, TransformTick};
 
 // TODO: if these moved to a resource
 // they'd be easier to fudge for testing
@@ -46,6 +46,7 @@ pub fn spawn_ball(commands: &mut Commands, position: Vec3, assets: &GameAssetSta
         Mesh3d(assets.ball_mesh.clone()),
         MeshMaterial3d(assets.ball_material.clone()),
         Transform::from_xyz(position.x, position.y, position.z),
+        TransformTick::default(),
         Name::new(name),
         Replicated,
         dynamic,
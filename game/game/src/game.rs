@@ -4,13 +4,22 @@ use bevy_replicon::prelude::*;
 
 use crate::{
     cleanup_state, dynamic,
-    network::{ConnectEvent, InputUpdateEvent, PlayerJumpEvent},
-    player, spawn, world, GameAssetState, GameState, InputState,
+    network::{
+        ConnectEvent, IdleWarningEvent, InputUpdateEvent, PingEvent, PongEvent,
+        ServerDisconnectEvent, ServerShutdownWarningEvent, TimeSyncRequestEvent,
+        TimeSyncResponseEvent,
+    },
+    player, spawn, world, GameAssetState, GameState, InputState, SimulationTick, TickRate,
+    TransformTick,
 };
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, SystemSet)]
 pub struct ServerSet;
 
+// the server checks client input in here before it's applied
+#[derive(Debug, Clone, PartialEq, Eq, Hash, SystemSet)]
+pub struct InputValidationSet;
+
 #[derive(Debug, Default, Component)]
 pub struct OnInGame;
 
@@ -19,6 +28,13 @@ pub struct GamePlugin;
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
+        let tick_rate = *app
+            .world_mut()
+            .get_resource_or_insert_with(TickRate::default);
+
+        // avian steps in FixedPostUpdate so this is also the physics rate
+        app.insert_resource(Time::<Fixed>::from_hz(tick_rate.0 as f64));
+
         app.add_plugins((
             // third-party plugins
             PhysicsPlugins::default(), // TODO: this doesn't work with tnua: .set(PhysicsInterpolationPlugin::interpolate_all()),
@@ -30,6 +46,7 @@ impl Plugin for GamePlugin {
         ))
         .init_state::<GameState>()
         .init_resource::<InputState>()
+        .init_resource::<SimulationTick>()
         .add_systems(OnEnter(GameState::LoadAssets), load_assets)
         .add_systems(
             Update,
@@ -50,9 +67,27 @@ impl Plugin for GamePlugin {
             ),
         )
         .add_systems(
+            FixedFirst,
+            advance_tick
+                .run_if(in_state(GameState::InGame))
+                .run_if(server_or_singleplayer),
+        )
+        // anything that moved since the last send, not just in the fixed loop
+        .add_systems(
+            PostUpdate,
+            stamp_transforms
+                .before(bevy_replicon::server::ServerSet::Send)
+                .run_if(in_state(GameState::InGame))
+                .run_if(server_running),
+        )
+        .configure_sets(
             PreUpdate,
-            (handle_input_update, handle_jump_event)
-                .after(bevy_replicon::server::ServerSet::Receive)
+            InputValidationSet.after(bevy_replicon::server::ServerSet::Receive),
+        )
+        .add_systems(
+            PreUpdate,
+            handle_input_update
+                .after(InputValidationSet)
                 .run_if(in_state(GameState::InGame))
                 .run_if(server_or_singleplayer),
         )
@@ -63,10 +98,21 @@ impl Plugin for GamePlugin {
 
         app.register_type::<InputState>();
 
+        app.replicate::<TransformTick>();
+
         // TOOD: move to a network plugin
         app.add_client_event::<ConnectEvent>(ChannelKind::Unordered)
-            .add_client_event::<InputUpdateEvent>(ChannelKind::Ordered)
-            .add_client_event::<PlayerJumpEvent>(ChannelKind::Unordered);
+            // inputs are resent until they're old, so there's no need to wait on lost packets
+            .add_client_event::<InputUpdateEvent>(ChannelKind::Unreliable)
+            // lost pings are just a missed sample, resending would skew the rtt
+            .add_client_event::<PongEvent>(ChannelKind::Unreliable)
+            .add_server_event::<PingEvent>(ChannelKind::Unreliable)
+            // same as pings, a lost sample is better than a late one
+            .add_client_event::<TimeSyncRequestEvent>(ChannelKind::Unreliable)
+            .add_server_event::<TimeSyncResponseEvent>(ChannelKind::Unreliable)
+            .add_server_event::<ServerShutdownWarningEvent>(ChannelKind::Ordered)
+            .add_server_event::<IdleWarningEvent>(ChannelKind::Ordered)
+            .add_server_event::<ServerDisconnectEvent>(ChannelKind::Ordered);
     }
 }
 
@@ -207,6 +253,9 @@ fn wait_for_world(mut game_state: ResMut<NextState<GameState>>) {
 fn enter_server(mut commands: Commands, assets: Res<GameAssetState>) {
     info!("entering game (server / singleplayer) ...");
 
+    commands.insert_resource(player::InputBufferMetrics::default());
+    commands.insert_resource(SimulationTick::default());
+
     dynamic::spawn_ball(&mut commands, Vec3::new(0.0, 20.0, -5.0), &assets);
 }
 
@@ -230,35 +279,38 @@ fn exit(mut commands: Commands) {
     commands.remove_resource::<GameAssetState>();
 }
 
+fn advance_tick(mut tick: ResMut<SimulationTick>) {
+    tick.0 = tick.0.wrapping_add(1);
+}
+
+fn stamp_transforms(
+    tick: Res<SimulationTick>,
+    mut query: Query<&mut TransformTick, Changed<Transform>>,
+) {
+    for mut transform_tick in &mut query {
+        transform_tick.0 = tick.0;
+    }
+}
+
 fn handle_input_update(
     mut evr_input_update: EventReader<FromClient<InputUpdateEvent>>,
-    mut player_query: Query<(&mut player::LastInput, &player::Player)>,
+    mut metrics: ResMut<player::InputBufferMetrics>,
+    mut player_query: Query<(&mut player::InputBuffer, &player::Player)>,
 ) {
     for FromClient { client_id, event } in evr_input_update.read() {
         // validation handled by server
 
-        for (mut last_input, player) in &mut player_query {
-            if player.client_id == *client_id {
-                last_input.input_state = event.0;
+        for (mut input_buffer, player) in &mut player_query {
+            if player.client_id != *client_id {
+                continue;
             }
-        }
-    }
-}
-
-fn handle_jump_event(
-    mut evr_jump: EventReader<FromClient<PlayerJumpEvent>>,
-    mut player_query: Query<(&mut player::LastInput, &player::Player)>,
-) {
-    for FromClient {
-        client_id,
-        event: _,
-    } in evr_jump.read()
-    {
-        // validation handled by server
 
-        for (mut last_input, player) in &mut player_query {
-            if player.client_id == *client_id {
-                last_input.jump = true;
+            // simulation picks these up one per tick
+            for input in &event.inputs {
+                if input_buffer.push(*input) {
+                    debug!("player {} input buffer overrun", player.user_id);
+                    metrics.overruns += 1;
+                }
             }
         }
     }
@@ -7,20 +7,37 @@ pub mod spawn;
 pub mod utils;
 mod world;
 
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::Duration};
 use serde::{Deserialize, Serialize};
 
-pub use game::{spawn_client_world, GamePlugin, OnInGame, ServerSet};
+pub use common::netcode::PROTOCOL_ID;
+pub use game::{spawn_client_world, GamePlugin, InputValidationSet, OnInGame, ServerSet};
 
-pub const PROTOCOL_ID: u64 = 0;
+pub const DEFAULT_TICK_RATE: u16 = 60;
 
-// TODO: this sets the server "frame rate"
-// bevy FixedUpdate tho runs at 64hz
-// and that might need to be adjusted as well?
-pub const SERVER_TICK_RATE: u16 = 60;
+// largest look delta the client sends in a single input,
+// the server's limit defaults to this but can be configured
+pub const MAX_LOOK_DELTA: f32 = 500.0;
 
-// TODO: the issue atm is that there's no way
-// to tell the app to go back to its initial state
+// simulation rate, drives FixedUpdate (and physics with it) and how often the server sends updates
+// insert this before GamePlugin to change it
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Resource)]
+pub struct TickRate(pub u16);
+
+impl Default for TickRate {
+    fn default() -> Self {
+        Self(DEFAULT_TICK_RATE)
+    }
+}
+
+impl TickRate {
+    #[inline]
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs_f64(1.0 / self.0 as f64)
+    }
+}
+
+// going back to WaitingForApp tears down the current game
 #[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, States, Reflect)]
 pub enum GameState {
     #[default]
@@ -37,6 +54,15 @@ pub struct InputState {
     pub crouch: bool,
 }
 
+// fixed updates the server has simulated this game
+#[derive(Debug, Default, Copy, Clone, Resource)]
+pub struct SimulationTick(pub u32);
+
+// simulation tick an entity's transform last changed on, replicated along with it
+// so clients can place updates on the server's timeline rather than when they arrived
+#[derive(Debug, Default, Copy, Clone, Component, Serialize, Deserialize)]
+pub struct TransformTick(pub u32);
+
 #[derive(Debug, Default, Resource)]
 pub struct GameAssetState {
     floor_mesh: Handle<Mesh>,
@@ -3,13 +3,15 @@ use std::net::{IpAddr, SocketAddr};
 
 use bevy::prelude::*;
 use bevy_replicon::prelude::*;
-use network_interface::{NetworkInterface, NetworkInterfaceConfig};
 use serde::{Deserialize, Serialize};
 
 use common::user::UserId;
 
 use crate::InputState;
 
+// inputs resent with each update so a lost packet doesn't lose input
+pub const INPUT_REDUNDANCY: usize = 4;
+
 #[derive(Debug, Clone, Default)]
 pub struct ConnectionInfo {
     pub v4addrs: BTreeSet<String>,
@@ -18,60 +20,30 @@ pub struct ConnectionInfo {
 }
 
 impl ConnectionInfo {
-    pub fn update(&mut self, addr: SocketAddr) {
-        let ip = addr.ip();
-        if ip.is_unspecified() {
-            self.v4addrs.clear();
-            self.v6addrs.clear();
-
-            let ifaces = NetworkInterface::show().unwrap();
-            for iface in ifaces {
-                // hack for now, I honestly don't know how to ignore this
-                if iface.name.contains("docker") {
-                    continue;
-                }
-
-                // also a hack, I don't know how to ignore bridge interfaces
-                if iface.name.starts_with("br-") {
-                    continue;
-                }
-
-                for ip in iface.addr {
-                    let ip = ip.ip();
-                    // TODO: copy paste
-                    match ip {
-                        IpAddr::V4(ip) => {
-                            if !ip.is_loopback() && !ip.is_link_local() {
-                                self.v4addrs.insert(ip.to_string());
-                            }
-                        }
-                        IpAddr::V6(ip) => {
-                            if !ip.is_loopback() {
-                                self.v6addrs.insert(ip.to_string());
-                            }
-                        }
-                    }
-                }
-            }
-        } else {
-            // TODO: copy paste
+    pub fn set(&mut self, addrs: impl IntoIterator<Item = IpAddr>, port: u16) {
+        self.v4addrs.clear();
+        self.v6addrs.clear();
+
+        for ip in addrs {
             match ip {
-                IpAddr::V4(ip) => {
-                    if !ip.is_loopback() && !ip.is_link_local() {
-                        self.v4addrs.insert(ip.to_string());
-                    }
-                }
-                IpAddr::V6(ip) => {
-                    if !ip.is_loopback() {
-                        self.v6addrs.insert(ip.to_string());
-                    }
-                }
-            }
+                IpAddr::V4(_) => self.v4addrs.insert(ip.to_string()),
+                IpAddr::V6(_) => self.v6addrs.insert(ip.to_string()),
+            };
         }
-        self.port = addr.port();
+        self.port = port;
 
         info!("updated connection info: {:?}", self);
     }
+
+    // addresses clients can reach the server at
+    pub fn addresses(&self) -> Vec<SocketAddr> {
+        self.v4addrs
+            .iter()
+            .chain(self.v6addrs.iter())
+            .filter_map(|addr| addr.parse::<IpAddr>().ok())
+            .map(|ip| SocketAddr::new(ip, self.port))
+            .collect()
+    }
 }
 
 #[derive(Debug, Copy, Clone, Resource)]
@@ -97,11 +69,61 @@ impl PlayerClientId {
 #[derive(Debug, Event, Serialize, Deserialize)]
 pub struct ConnectEvent(pub UserId);
 
-// TODO: add a ping event and have the client send it every 10-15 seconds
-// and then have the server check for timed out clients every 30-60 seconds
+// input sampled for a single fixed tick
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize)]
+pub struct SequencedInput {
+    pub sequence: u32,
+    pub input_state: InputState,
+
+    // pressed since the last input
+    pub jump: bool,
+}
+
+// the client's most recent inputs, oldest first
+#[derive(Debug, Clone, Event, Serialize, Deserialize)]
+pub struct InputUpdateEvent {
+    pub inputs: Vec<SequencedInput>,
+}
+
+// server pings each client and times how long the pong takes
+#[derive(Debug, Event, Serialize, Deserialize)]
+pub struct PingEvent(pub u32);
 
 #[derive(Debug, Event, Serialize, Deserialize)]
-pub struct InputUpdateEvent(pub InputState);
+pub struct PongEvent(pub u32);
+
+// client asks for the server clock, NTP style, to keep its own estimate in sync
+#[derive(Debug, Event, Serialize, Deserialize)]
+pub struct TimeSyncRequestEvent {
+    // echoed back so the client can time the round trip
+    pub client_time: f64,
+}
 
-#[derive(Debug, Default, Event, Serialize, Deserialize)]
-pub struct PlayerJumpEvent;
+#[derive(Debug, Event, Serialize, Deserialize)]
+pub struct TimeSyncResponseEvent {
+    pub client_time: f64,
+    pub server_time: f64,
+
+    // latest simulation tick at server_time and how long a tick is
+    pub tick: u32,
+    pub tick_secs: f64,
+}
+
+// the server is draining and will disconnect everyone when this hits 0
+#[derive(Debug, Event, Serialize, Deserialize)]
+pub struct ServerShutdownWarningEvent {
+    pub seconds_remaining: u32,
+}
+
+// we're about to be kicked for not doing anything,
+// None once we're active again
+#[derive(Debug, Event, Serialize, Deserialize)]
+pub struct IdleWarningEvent {
+    pub seconds_remaining: Option<u32>,
+}
+
+// sent right before the server disconnects us
+#[derive(Debug, Event, Serialize, Deserialize)]
+pub struct ServerDisconnectEvent {
+    pub reason: String,
+}
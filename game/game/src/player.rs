@@ -1,3 +1,5 @@
+use std::collections::VecDeque;
+
 use avian3d::prelude::*;
 use bevy::{color::palettes::css, ecs::entity::MapEntities, prelude::*};
 use bevy_replicon::prelude::*;
@@ -8,7 +10,11 @@ use serde::{Deserialize, Serialize};
 
 use common::user::UserId;
 
-use crate::{GameAssetState, GameState, InputState, game::OnInGame, network::PlayerClientId};
+use crate::{
+    GameAssetState, GameState, InputState, TransformTick,
+    game::OnInGame,
+    network::{PlayerClientId, SequencedInput},
+};
 
 // TODO: if these moved to a resource
 // they'd be easier to fudge for testing
@@ -19,6 +25,12 @@ const JUMP_HEIGHT: f32 = 8.0;
 const HEIGHT: f32 = 2.0; // includes capsule hemispheres
 const MASS: f32 = 75.0;
 
+// inputs held back to absorb jitter, so clients are simulated about this many ticks behind
+const TARGET_BUFFERED_INPUTS: usize = 2;
+
+// past this the client is too far ahead and the oldest input is folded into the next one
+const MAX_BUFFERED_INPUTS: usize = 8;
+
 #[derive(Debug, strum::Display)]
 pub enum PlayerAnimationState {
     Idle,
@@ -72,6 +84,131 @@ pub struct LastInput {
     pub jump: bool,
 }
 
+// client input waiting for its simulation tick
+// nothing is simulated until it fills to the target depth (and again whenever it runs dry)
+// so late packets are absorbed instead of guessing at input
+#[derive(Debug, Default, Component)]
+pub struct InputBuffer {
+    inputs: VecDeque<SequencedInput>,
+
+    // newest input received, anything at or before it is a resend
+    last_sequence: Option<u32>,
+
+    // filled to the target depth since it last ran dry
+    primed: bool,
+
+    // last input simulated, held while the buffer fills
+    last_input: InputState,
+}
+
+impl InputBuffer {
+    #[inline]
+    pub fn last_sequence(&self) -> Option<u32> {
+        self.last_sequence
+    }
+
+    #[inline]
+    pub fn is_primed(&self) -> bool {
+        self.primed
+    }
+
+    // returns true if the oldest input had to be folded into the next to make room
+    pub fn push(&mut self, input: SequencedInput) -> bool {
+        // resent or already simulated
+        if self
+            .last_sequence
+            .is_some_and(|last_sequence| input.sequence <= last_sequence)
+        {
+            return false;
+        }
+
+        self.last_sequence = Some(input.sequence);
+        self.inputs.push_back(input);
+
+        if self.inputs.len() > MAX_BUFFERED_INPUTS {
+            self.drop_oldest();
+            return true;
+        }
+
+        false
+    }
+
+    // None while the buffer is filling, simulate hold() instead
+    pub fn pop(&mut self, metrics: &mut InputBufferMetrics) -> Option<SequencedInput> {
+        if !self.primed {
+            if self.inputs.len() < TARGET_BUFFERED_INPUTS {
+                return None;
+            }
+            self.primed = true;
+        }
+
+        // the client got ahead (a burst after a stall), catch up a tick at a time
+        if self.inputs.len() > TARGET_BUFFERED_INPUTS * 2 {
+            self.drop_oldest();
+            metrics.overruns += 1;
+        }
+
+        let Some(input) = self.inputs.pop_front() else {
+            self.primed = false;
+            return None;
+        };
+
+        self.last_input = input.input_state;
+        Some(input)
+    }
+
+    // the last input without moving or turning, the client predicted from its own input
+    // so carrying on with the old one only puts us further from where it thinks it is
+    // None if the client hasn't sent anything yet
+    pub fn hold(&self) -> Option<InputState> {
+        self.last_sequence?;
+
+        Some(InputState {
+            look: Vec2::ZERO,
+            r#move: Vec2::ZERO,
+            ..self.last_input
+        })
+    }
+
+    // look and jump are one-off, so they carry over rather than being lost
+    fn drop_oldest(&mut self) {
+        let Some(dropped) = self.inputs.pop_front() else {
+            return;
+        };
+
+        if let Some(next) = self.inputs.front_mut() {
+            next.input_state.look += dropped.input_state.look;
+            next.jump |= dropped.jump;
+        }
+    }
+}
+
+// the last input the server simulated for a player and the velocity it left them with,
+// replicated with the player's transform so the owning client can check its prediction
+#[derive(Debug, Default, Copy, Clone, Component, Serialize, Deserialize)]
+pub struct PlayerAck {
+    pub sequence: Option<u32>,
+    pub velocity: Vec3,
+}
+
+// the local player simulated ahead of the server
+#[derive(Debug, Component)]
+pub struct PredictedPlayer;
+
+// predicted player model, offset to smooth out corrections
+#[derive(Debug, Component)]
+pub struct PredictedPlayerModel;
+
+// input buffer health across all players this session
+#[derive(Debug, Default, Resource)]
+pub struct InputBufferMetrics {
+    // times a player's input ran out
+    pub underruns: u32,
+
+    // inputs folded into the next because a player got too far ahead
+    pub overruns: u32,
+}
+
 #[derive(Debug, Event, Serialize, Deserialize)]
 pub struct PlayerCrouchEvent(pub Entity, pub bool);
 
@@ -158,27 +295,61 @@ pub fn spawn_player(
         AnimationGraphHandle(assets.player_animations.graph.clone()),
         AnimationPlayer::default(),
         Transform::from_xyz(position.x, position.y, position.z),
+        TransformTick::default(),
         Name::new(format!("Player {}: {:?}", user_id, client_id)),
         Replicated,
         LastInput::default(),
+        InputBuffer::default(),
+        PlayerAck::default(),
         Player::new(user_id, client_id),
         OnInGame,
     ));
 
-    commands.insert((
+    commands
+        .insert(physics_components())
+        .insert(TnuaAnimatingState::<PlayerAnimationState>::default())
+        .id()
+}
+
+// everything the server and client prediction need to simulate a player
+fn physics_components() -> impl Bundle {
+    (
         RigidBody::Dynamic,
         // TODO: can we infer this from the mesh?
         Collider::capsule(HEIGHT * 0.5, HEIGHT),
         Mass(MASS),
         LockedAxes::ROTATION_LOCKED.unlock_rotation_y(),
-    ));
+        TnuaController::default(),
+        bevy_tnua_avian3d::TnuaAvian3dSensorShape(Collider::cylinder(0.5, 0.0)),
+    )
+}
+
+pub fn spawn_predicted_player(
+    commands: &mut Commands,
+    assets: &GameAssetState,
+    transform: Transform,
+) -> Entity {
+    info!("spawning predicted player at {} ...", transform.translation);
 
     commands
-        .insert((
-            TnuaController::default(),
-            TnuaAnimatingState::<PlayerAnimationState>::default(),
-            bevy_tnua_avian3d::TnuaAvian3dSensorShape(Collider::cylinder(0.5, 0.0)),
+        .spawn((
+            transform,
+            Name::new("Predicted Player"),
+            PredictedPlayer,
+            OnInGame,
         ))
+        .insert(physics_components())
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Mesh3d(assets.player_mesh.clone()),
+                    MeshMaterial3d(assets.player_material.clone()),
+                    Transform::default(),
+                    Name::new("Predicted Player Model"),
+                    PredictedPlayerModel,
+                ))
+                .with_children(spawn_player_camera);
+        })
         .id()
 }
 
@@ -203,6 +374,22 @@ pub fn finish_client_player(
 
     let is_local = player.client_id == local_client_id;
 
+    // we see our own predicted player instead,
+    // this one is just where the server says we are
+    if is_local {
+        commands.entity(entity).insert((
+            Name::new(format!(
+                "Replicated Player ({}) Local: {:?}",
+                player.user_id, player.client_id
+            )),
+            LocalPlayer,
+            OnInGame,
+        ));
+
+        spawn_predicted_player(commands, assets, *transform);
+        return;
+    }
+
     let mut ec = commands.entity(entity);
     ec.insert((
         Mesh3d(assets.player_mesh.clone()),
@@ -211,39 +398,34 @@ pub fn finish_client_player(
         AnimationPlayer::default(),
         Collider::capsule(HEIGHT * 0.5, HEIGHT),
         Name::new(format!(
-            "Replicated Player ({}) {}: {:?}",
-            player.user_id,
-            if is_local { " Local" } else { "Remote" },
-            player.client_id
+            "Replicated Player ({}) Remote: {:?}",
+            player.user_id, player.client_id
         )),
         OnInGame,
     ));
-
-    if is_local {
-        finish_local_player(commands, entity);
-    }
 }
 
 pub fn finish_local_player(commands: &mut Commands, entity: Entity) {
     info!("finishing local player {} ...", entity);
 
-    let mut ec = commands.entity(entity);
-    ec.insert(LocalPlayer);
-
-    // spawn the player camera
-    ec.with_children(|parent| {
-        parent.spawn((
-            Transform::from_xyz(0.0, 1.9, -0.9),
-            Camera3d::default(),
-            PerspectiveProjection {
-                fov: 90.0_f32.to_radians(), // TODO: this should move to settings
-                ..default()
-            },
-            Name::new("Player Camera"),
-            PlayerCamera,
-            OnInGame,
-        ));
-    });
+    commands
+        .entity(entity)
+        .insert(LocalPlayer)
+        .with_children(spawn_player_camera);
+}
+
+fn spawn_player_camera(parent: &mut ChildBuilder) {
+    parent.spawn((
+        Transform::from_xyz(0.0, 1.9, -0.9),
+        Camera3d::default(),
+        PerspectiveProjection {
+            fov: 90.0_f32.to_radians(), // TODO: this should move to settings
+            ..default()
+        },
+        Name::new("Player Camera"),
+        PlayerCamera,
+        OnInGame,
+    ));
 }
 
 #[derive(Debug)]
@@ -251,29 +433,39 @@ pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            PreUpdate,
-            finish_client_players
-                .after(ClientSet::Receive)
-                .run_if(client_connected),
-        )
-        .add_systems(
-            Update,
-            (rotate_player, animate_player)
-                .run_if(server_or_singleplayer)
-                .run_if(in_state(GameState::InGame)),
-        )
-        .add_systems(
-            FixedUpdate,
-            update_player_physics
-                .run_if(server_or_singleplayer)
-                .run_if(in_state(GameState::InGame)),
-        );
+        app.init_resource::<InputBufferMetrics>()
+            .add_systems(
+                PreUpdate,
+                finish_client_players
+                    .after(ClientSet::Receive)
+                    .run_if(client_connected),
+            )
+            .add_systems(
+                Update,
+                animate_player
+                    .run_if(server_or_singleplayer)
+                    .run_if(in_state(GameState::InGame)),
+            )
+            .add_systems(
+                FixedUpdate,
+                // input is simulated one tick at a time
+                (consume_input, rotate_player, update_player_physics)
+                    .chain()
+                    .run_if(server_or_singleplayer)
+                    .run_if(in_state(GameState::InGame)),
+            )
+            .add_systems(
+                PostUpdate,
+                update_player_acks
+                    .before(ServerSet::Send)
+                    .run_if(server_running),
+            );
 
         app.register_type::<Player>();
 
         app.add_mapped_server_event::<PlayerCrouchEvent>(ChannelKind::Ordered)
-            .replicate_group::<(Transform, Player)>();
+            .replicate_group::<(Transform, Player)>()
+            .replicate::<PlayerAck>();
     }
 }
 
@@ -299,15 +491,73 @@ fn finish_client_players(
     }
 }
 
+fn consume_input(
+    mut metrics: ResMut<InputBufferMetrics>,
+    mut player_query: Query<(&Player, &mut InputBuffer, &mut LastInput, &mut PlayerAck)>,
+) {
+    for (player, mut input_buffer, mut last_input, mut ack) in &mut player_query {
+        let primed = input_buffer.is_primed();
+
+        if let Some(input) = input_buffer.pop(&mut metrics) {
+            last_input.input_state = input.input_state;
+            last_input.jump = input.jump;
+            ack.sequence = Some(input.sequence);
+            continue;
+        }
+
+        // nothing new to ack, the late inputs are still simulated once they show up
+        let Some(input_state) = input_buffer.hold() else {
+            continue;
+        };
+
+        if primed {
+            debug!("player {} input buffer underrun", player.user_id);
+            metrics.underruns += 1;
+        }
+
+        last_input.input_state = input_state;
+        last_input.jump = false;
+    }
+}
+
+// shared with client prediction so both simulate the same way
+pub fn rotate(transform: &mut Transform, look: Vec2, delta_secs: f32) {
+    // TODO: should the rate of change here be maxed?
+    let delta_yaw = -look.x * delta_secs;
+
+    transform.rotate_y(delta_yaw);
+}
+
+pub fn walk(character_controller: &mut TnuaController, rotation: Quat, r#move: Vec2) {
+    let direction = rotation * Vec3::new(r#move.x, 0.0, -r#move.y);
+
+    character_controller.basis(TnuaBuiltinWalk {
+        desired_velocity: direction.normalize_or_zero() * MOVE_SPEED,
+        // TODO: this isn't right, but we should probably do this instead of rotate_player()
+        //desired_forward: Dir3::new(Vec3::new(look.x, 0.0, 0.0)).ok(),
+        // TODO: this doesn't seem right by the docs, but anything less doesn't work
+        float_height: HEIGHT,
+        ..Default::default()
+    });
+}
+
+pub fn jump(character_controller: &mut TnuaController) {
+    character_controller.action(TnuaBuiltinJump {
+        height: JUMP_HEIGHT,
+        ..Default::default()
+    });
+}
+
 fn rotate_player(
     time: Res<Time>,
     mut player_query: Query<(&mut LastInput, &mut Transform), With<Player>>,
 ) {
     for (mut last_input, mut transform) in &mut player_query {
-        // TODO: should the rate of change here be maxed?
-        let delta_yaw = -last_input.input_state.look.x * time.delta_secs();
-
-        transform.rotate_y(delta_yaw);
+        rotate(
+            &mut transform,
+            last_input.input_state.look,
+            time.delta_secs(),
+        );
 
         last_input.input_state.look = Vec2::default();
     }
@@ -424,37 +674,23 @@ fn update_player_physics(
         &mut Player,
         &mut LastInput,
         &mut TnuaController,
-        &GlobalTransform,
+        &Transform,
     )>,
     mut evw_crouch: EventWriter<ToClients<PlayerCrouchEvent>>,
 ) {
-    for (entity, mut player, mut last_input, mut character_controller, global_transform) in
+    for (entity, mut player, mut last_input, mut character_controller, transform) in
         &mut player_query
     {
-        let global_transform = global_transform.compute_transform();
-
-        let direction = global_transform.rotation
-            * Vec3::new(
-                last_input.input_state.r#move.x,
-                0.0,
-                -last_input.input_state.r#move.y,
-            );
+        // rotate_player already ran this tick, so go off the local transform
+        walk(
+            &mut character_controller,
+            transform.rotation,
+            last_input.input_state.r#move,
+        );
         last_input.input_state.r#move = Vec2::default();
 
-        character_controller.basis(TnuaBuiltinWalk {
-            desired_velocity: direction.normalize_or_zero() * MOVE_SPEED,
-            // TODO: this isn't right, but we should probably do this instead of rotate_player()
-            //desired_forward: Dir3::new(Vec3::new(last_input.input_state.look.x, 0.0, 0.0)).ok(),
-            // TODO: this doesn't seem right by the docs, but anything less doesn't work
-            float_height: HEIGHT,
-            ..Default::default()
-        });
-
         if last_input.jump {
-            character_controller.action(TnuaBuiltinJump {
-                height: JUMP_HEIGHT,
-                ..Default::default()
-            });
+            jump(&mut character_controller);
             last_input.jump = false;
         }
 
@@ -479,3 +715,157 @@ fn update_player_physics(
         }
     }
 }
+
+fn update_player_acks(mut player_query: Query<(&mut PlayerAck, &LinearVelocity)>) {
+    for (mut ack, velocity) in &mut player_query {
+        if ack.velocity != velocity.0 {
+            ack.velocity = velocity.0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(sequence: u32) -> SequencedInput {
+        SequencedInput {
+            sequence,
+            ..default()
+        }
+    }
+
+    #[test]
+    fn push_ignores_resent_inputs() {
+        let mut input_buffer = InputBuffer::default();
+
+        assert!(!input_buffer.push(input(0)));
+        assert!(!input_buffer.push(input(1)));
+        assert!(!input_buffer.push(input(1)));
+        assert!(!input_buffer.push(input(0)));
+
+        assert_eq!(input_buffer.last_sequence(), Some(1));
+        assert_eq!(input_buffer.inputs.len(), 2);
+    }
+
+    #[test]
+    fn push_folds_when_full() {
+        let mut input_buffer = InputBuffer::default();
+
+        for sequence in 0..MAX_BUFFERED_INPUTS as u32 {
+            assert!(!input_buffer.push(input(sequence)));
+        }
+        assert!(input_buffer.push(input(MAX_BUFFERED_INPUTS as u32)));
+
+        assert_eq!(input_buffer.inputs.len(), MAX_BUFFERED_INPUTS);
+        assert_eq!(input_buffer.inputs.front().unwrap().sequence, 1);
+    }
+
+    #[test]
+    fn pop_waits_for_target_depth() {
+        let mut input_buffer = InputBuffer::default();
+        let mut metrics = InputBufferMetrics::default();
+
+        assert!(input_buffer.pop(&mut metrics).is_none());
+        assert!(input_buffer.hold().is_none());
+
+        for sequence in 0..TARGET_BUFFERED_INPUTS as u32 - 1 {
+            input_buffer.push(input(sequence));
+            assert!(input_buffer.pop(&mut metrics).is_none());
+            assert!(!input_buffer.is_primed());
+        }
+
+        input_buffer.push(input(TARGET_BUFFERED_INPUTS as u32 - 1));
+        assert_eq!(input_buffer.pop(&mut metrics).unwrap().sequence, 0);
+        assert!(input_buffer.is_primed());
+    }
+
+    #[test]
+    fn pop_refills_after_running_dry() {
+        let mut input_buffer = InputBuffer::default();
+        let mut metrics = InputBufferMetrics::default();
+
+        let mut sequence = 0;
+        for _ in 0..TARGET_BUFFERED_INPUTS {
+            input_buffer.push(input(sequence));
+            sequence += 1;
+        }
+
+        for expected in 0..TARGET_BUFFERED_INPUTS as u32 {
+            assert_eq!(input_buffer.pop(&mut metrics).unwrap().sequence, expected);
+        }
+
+        // ran dry
+        assert!(input_buffer.pop(&mut metrics).is_none());
+        assert!(!input_buffer.is_primed());
+
+        // late inputs aren't skipped, they wait for the target depth again
+        input_buffer.push(input(sequence));
+        assert!(input_buffer.pop(&mut metrics).is_none());
+
+        input_buffer.push(input(sequence + 1));
+        assert_eq!(input_buffer.pop(&mut metrics).unwrap().sequence, sequence);
+        assert_eq!(metrics.overruns, 0);
+    }
+
+    #[test]
+    fn hold_stops_moving() {
+        let mut input_buffer = InputBuffer::default();
+        let mut metrics = InputBufferMetrics::default();
+
+        for sequence in 0..TARGET_BUFFERED_INPUTS as u32 {
+            input_buffer.push(SequencedInput {
+                sequence,
+                input_state: InputState {
+                    look: Vec2::ONE,
+                    r#move: Vec2::X,
+                    crouch: true,
+                },
+                jump: true,
+            });
+        }
+        input_buffer.pop(&mut metrics).unwrap();
+
+        let held = input_buffer.hold().unwrap();
+        assert_eq!(held.look, Vec2::ZERO);
+        assert_eq!(held.r#move, Vec2::ZERO);
+        assert!(held.crouch);
+    }
+
+    #[test]
+    fn pop_folds_to_catch_up() {
+        let mut input_buffer = InputBuffer::default();
+        let mut metrics = InputBufferMetrics::default();
+
+        input_buffer.push(SequencedInput {
+            sequence: 0,
+            input_state: InputState {
+                look: Vec2::ONE,
+                ..default()
+            },
+            jump: true,
+        });
+        input_buffer.push(SequencedInput {
+            sequence: 1,
+            input_state: InputState {
+                look: Vec2::ONE,
+                ..default()
+            },
+            jump: false,
+        });
+        for sequence in 2..=TARGET_BUFFERED_INPUTS as u32 * 2 {
+            input_buffer.push(input(sequence));
+        }
+
+        // the oldest is folded into the next, so nothing one-off is lost
+        let popped = input_buffer.pop(&mut metrics).unwrap();
+        assert_eq!(popped.sequence, 1);
+        assert_eq!(popped.input_state.look, Vec2::splat(2.0));
+        assert!(popped.jump);
+        assert_eq!(metrics.overruns, 1);
+
+        // back at the target depth, no more folding
+        assert_eq!(input_buffer.pop(&mut metrics).unwrap().sequence, 2);
+        assert_eq!(metrics.overruns, 1);
+    }
+}
@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 use std::time::Duration;
 
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::Instant};
 use bevy_replicon::prelude::*;
 use uuid::Uuid;
 
@@ -33,6 +33,10 @@ pub struct GameSessionInfo {
     pub session_id: Uuid,
     pub max_players: u16,
 
+    // idle players are warned this long before being kicked
+    pub idle_kick_timeout: Option<Duration>,
+    pub idle_warning: Duration,
+
     pending_player_count: usize,
     active_player_count: usize,
 
@@ -51,6 +55,8 @@ impl GameSessionInfo {
         let mut this = Self {
             session_id,
             max_players: settings.max_players,
+            idle_kick_timeout: settings.idle_kick_timeout(),
+            idle_warning: settings.idle_warning(),
             pending_player_count: 0,
             active_player_count: 0,
             clients: HashMap::with_capacity(settings.max_players as usize),
@@ -75,6 +81,13 @@ impl GameSessionInfo {
         self.clients.contains_key(client_id)
     }
 
+    #[inline]
+    pub fn get_client_id(&self, user_id: UserId) -> Option<ClientId> {
+        self.clients
+            .iter()
+            .find_map(|(client_id, v)| (*v == user_id).then_some(*client_id))
+    }
+
     #[inline]
     pub fn update_shutdown_timer(&mut self, delta: Duration) -> bool {
         self.shutdown_timer.tick(delta);
@@ -106,6 +119,21 @@ impl GameSessionInfo {
     ) {
         info!("pending player {} timeout", pending_player_id);
 
+        self.remove_pending_player(commands, pending_player);
+    }
+
+    pub fn cancel_reservation(
+        &mut self,
+        commands: &mut Commands,
+        pending_player: Entity,
+        pending_player_id: UserId,
+    ) {
+        info!("cancelling player slot {}", pending_player_id);
+
+        self.remove_pending_player(commands, pending_player);
+    }
+
+    fn remove_pending_player(&mut self, commands: &mut Commands, pending_player: Entity) {
         commands.entity(pending_player).despawn_recursive();
         self.pending_player_count -= 1;
 
@@ -134,7 +162,7 @@ impl GameSessionInfo {
             commands.entity(pending_player).despawn_recursive();
             self.pending_player_count -= 1;
 
-            commands.spawn(ActivePlayer::new(user_id));
+            commands.spawn(ActivePlayer::new(user_id, client_id));
             self.active_player_count += 1;
 
             self.clients.insert(client_id, user_id);
@@ -213,10 +241,57 @@ impl PendingPlayer {
 #[derive(Debug, Component)]
 pub struct ActivePlayer {
     pub user_id: UserId,
+    pub client_id: ClientId,
+
+    // round trip time of the last answered ping
+    pub rtt: Option<Duration>,
+
+    ping_sequence: u32,
+    ping_sent: Option<Instant>,
+
+    // last time we heard anything from the client
+    last_seen: Instant,
 }
 
 impl ActivePlayer {
-    pub fn new(user_id: UserId) -> Self {
-        Self { user_id }
+    pub fn new(user_id: UserId, client_id: ClientId) -> Self {
+        Self {
+            user_id,
+            client_id,
+            rtt: None,
+            ping_sequence: 0,
+            ping_sent: None,
+            last_seen: Instant::now(),
+        }
+    }
+
+    // starts a new ping, any unanswered one is dropped
+    pub fn start_ping(&mut self) -> u32 {
+        self.ping_sequence = self.ping_sequence.wrapping_add(1);
+        self.ping_sent = Some(Instant::now());
+        self.ping_sequence
+    }
+
+    pub fn pong(&mut self, sequence: u32) {
+        self.seen();
+
+        // late pongs still count as activity but would skew the rtt
+        if sequence != self.ping_sequence {
+            return;
+        }
+
+        if let Some(ping_sent) = self.ping_sent.take() {
+            self.rtt = Some(ping_sent.elapsed());
+        }
+    }
+
+    #[inline]
+    pub fn seen(&mut self) {
+        self.last_seen = Instant::now();
+    }
+
+    #[inline]
+    pub fn is_unresponsive(&self, timeout: Duration) -> bool {
+        self.last_seen.elapsed() >= timeout
     }
 }
@@ -0,0 +1,142 @@
+use std::net::{IpAddr, SocketAddr};
+
+use bevy::prelude::*;
+use network_interface::{NetworkInterface, NetworkInterfaceConfig};
+use serde::{Deserialize, Serialize};
+
+use crate::{options::Options, orchestration::PublicEndpoint};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, clap::ValueEnum, Serialize, Deserialize)]
+#[clap(rename_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
+pub enum AddressClass {
+    Loopback,
+    LinkLocal,
+    Private,
+    Public,
+}
+
+impl AddressClass {
+    pub fn of(ip: IpAddr) -> Self {
+        match ip {
+            IpAddr::V4(ip) => {
+                // 100.64.0.0/10 is carrier-grade NAT
+                let octets = ip.octets();
+                let shared = octets[0] == 100 && (octets[1] & 0xc0) == 64;
+
+                if ip.is_loopback() {
+                    Self::Loopback
+                } else if ip.is_link_local() {
+                    Self::LinkLocal
+                } else if ip.is_private() || shared {
+                    Self::Private
+                } else {
+                    Self::Public
+                }
+            }
+            IpAddr::V6(ip) => {
+                // fe80::/10 link local, fc00::/7 unique local
+                let segment = ip.segments()[0];
+                let link_local = (segment & 0xffc0) == 0xfe80;
+                let unique_local = (segment & 0xfe00) == 0xfc00;
+
+                if ip.is_loopback() {
+                    Self::Loopback
+                } else if link_local {
+                    Self::LinkLocal
+                } else if unique_local {
+                    Self::Private
+                } else {
+                    Self::Public
+                }
+            }
+        }
+    }
+}
+
+// trailing * matches a prefix, otherwise the name must match exactly
+fn matches_pattern(name: &str, pattern: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => name.starts_with(prefix),
+        None => name == pattern,
+    }
+}
+
+fn include_interface(options: &Options, name: &str) -> bool {
+    let included = options.interface_include.is_empty()
+        || options
+            .interface_include
+            .iter()
+            .any(|pattern| matches_pattern(name, pattern));
+
+    included
+        && !options
+            .interface_exclude
+            .iter()
+            .any(|pattern| matches_pattern(name, pattern))
+}
+
+fn include_address(options: &Options, ip: IpAddr) -> bool {
+    options.address_classes.contains(&AddressClass::of(ip))
+}
+
+// addresses of the local interfaces we're listening on
+fn discover_addresses(options: &Options, bind_ip: IpAddr) -> anyhow::Result<Vec<IpAddr>> {
+    if !bind_ip.is_unspecified() {
+        return Ok(if include_address(options, bind_ip) {
+            vec![bind_ip]
+        } else {
+            vec![]
+        });
+    }
+
+    // a v4 unspecified socket doesn't accept v6
+    let v6 = bind_ip.is_ipv6();
+
+    let mut addrs = vec![];
+    for iface in NetworkInterface::show()? {
+        if !include_interface(options, &iface.name) {
+            debug!("skipping interface {}", iface.name);
+            continue;
+        }
+
+        for addr in iface.addr {
+            let ip = addr.ip();
+            if (ip.is_ipv4() || v6) && include_address(options, ip) && !addrs.contains(&ip) {
+                addrs.push(ip);
+            }
+        }
+    }
+
+    Ok(addrs)
+}
+
+// explicit options win, then whatever the orchestrator tells us,
+// and the local interfaces are only a fallback
+pub fn advertised_endpoint(
+    options: &Options,
+    public_endpoint: Option<&PublicEndpoint>,
+    bind_addr: SocketAddr,
+) -> anyhow::Result<(Vec<IpAddr>, u16)> {
+    let port = options
+        .advertise_port
+        .or(public_endpoint.and_then(|endpoint| endpoint.port))
+        .unwrap_or(bind_addr.port());
+
+    if !options.advertise_addresses.is_empty() {
+        return Ok((options.advertise_addresses.clone(), port));
+    }
+
+    if let Some(public_endpoint) = public_endpoint {
+        if !public_endpoint.addresses.is_empty() {
+            return Ok((public_endpoint.addresses.clone(), port));
+        }
+    }
+
+    let addrs = discover_addresses(options, bind_addr.ip())?;
+    if addrs.is_empty() {
+        warn!("no advertisable addresses found, clients won't be able to connect!");
+    }
+
+    Ok((addrs, port))
+}
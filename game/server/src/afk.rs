@@ -0,0 +1,163 @@
+use bevy::{prelude::*, utils::Duration};
+use bevy_replicon::prelude::*;
+
+use game_common::{
+    network::{IdleWarningEvent, InputUpdateEvent, SequencedInput, ServerDisconnectEvent},
+    player::Player,
+    server::GameSessionInfo,
+    GameState, InputValidationSet,
+};
+
+use crate::{server, AppState};
+
+const KICK_REASON: &str = "Kicked for inactivity";
+
+// time since the player last did anything
+#[derive(Debug, Default, Component)]
+struct Idle {
+    duration: Duration,
+
+    // last countdown the player was warned with
+    warning: Option<u32>,
+
+    kicked: bool,
+}
+
+#[derive(Debug)]
+pub struct AfkPlugin;
+
+impl Plugin for AfkPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PreUpdate,
+            handle_player_activity
+                // only count input that made it through validation
+                .after(InputValidationSet)
+                .run_if(in_state(GameState::InGame))
+                .run_if(in_state(AppState::InGame))
+                .run_if(server_running),
+        )
+        .add_systems(
+            Update,
+            (track_players, update_idle_players)
+                .chain()
+                .run_if(in_state(GameState::InGame))
+                .run_if(in_state(AppState::InGame))
+                .run_if(server_running),
+        );
+    }
+}
+
+// input updates are sent every fixed tick whether or not anything is pressed,
+// so only count ones that do something
+#[inline]
+fn is_active_input(input: &SequencedInput) -> bool {
+    input.jump || input.input_state.r#move != Vec2::ZERO || input.input_state.look != Vec2::ZERO
+}
+
+fn track_players(mut commands: Commands, players: Query<Entity, (With<Player>, Without<Idle>)>) {
+    for entity in &players {
+        commands.entity(entity).insert(Idle::default());
+    }
+}
+
+fn handle_player_activity(
+    mut evr_input_update: EventReader<FromClient<InputUpdateEvent>>,
+    mut players: Query<(&Player, &mut Idle)>,
+    mut evw_warning: EventWriter<ToClients<IdleWarningEvent>>,
+) {
+    let client_ids = evr_input_update
+        .read()
+        .filter(|evt| evt.event.inputs.iter().any(is_active_input))
+        .map(|evt| evt.client_id);
+
+    for client_id in client_ids {
+        let Some((player, mut idle)) = players
+            .iter_mut()
+            .find(|(player, _)| player.client_id == client_id)
+        else {
+            continue;
+        };
+
+        if idle.kicked {
+            continue;
+        }
+
+        // let them know they're safe
+        if idle.warning.take().is_some() {
+            info!("player {} is no longer idle", player.user_id);
+
+            evw_warning.send(ToClients {
+                mode: SendMode::Direct(client_id),
+                event: IdleWarningEvent {
+                    seconds_remaining: None,
+                },
+            });
+        }
+
+        idle.duration = Duration::ZERO;
+    }
+}
+
+fn update_idle_players(
+    mut commands: Commands,
+    time: Res<Time>,
+    session_info: Res<GameSessionInfo>,
+    mut players: Query<(&Player, &mut Idle)>,
+    mut evw_warning: EventWriter<ToClients<IdleWarningEvent>>,
+    mut evw_disconnect: EventWriter<ToClients<ServerDisconnectEvent>>,
+) {
+    let Some(kick_timeout) = session_info.idle_kick_timeout else {
+        return;
+    };
+
+    for (player, mut idle) in &mut players {
+        if idle.kicked {
+            continue;
+        }
+
+        idle.duration += time.delta();
+
+        // disconnect handling frees the slot and heartbeats it for backfill
+        if idle.duration >= kick_timeout {
+            info!(
+                "kicking player {} after being idle for {:?}",
+                player.user_id, idle.duration
+            );
+
+            idle.kicked = true;
+            server::disconnect_with_reason(
+                &mut commands,
+                &mut evw_disconnect,
+                player.client_id,
+                KICK_REASON,
+            );
+            continue;
+        }
+
+        let remaining = kick_timeout - idle.duration;
+        if remaining > session_info.idle_warning {
+            continue;
+        }
+
+        let seconds_remaining = remaining.as_secs_f32().ceil() as u32;
+        if idle.warning == Some(seconds_remaining) {
+            continue;
+        }
+
+        if idle.warning.is_none() {
+            info!(
+                "player {} is idle, kicking in {:?}",
+                player.user_id, remaining
+            );
+        }
+
+        evw_warning.send(ToClients {
+            mode: SendMode::Direct(player.client_id),
+            event: IdleWarningEvent {
+                seconds_remaining: Some(seconds_remaining),
+            },
+        });
+        idle.warning = Some(seconds_remaining);
+    }
+}
@@ -5,30 +5,44 @@ use uuid::Uuid;
 use common::{check_reqwest_error, gameserver};
 use game_common::{
     network::ConnectionInfo,
+    player::InputBufferMetrics,
     server::{ActivePlayer, GameSessionInfo, PendingPlayer},
 };
 
-const HOST: &str = "http://localhost:8000";
+use crate::tick::TickMetrics;
 
 #[allow(clippy::too_many_arguments)]
 pub fn heartbeat<'a>(
     client: &'a mut BevyReqwest,
+    api_url: &str,
     server_id: Uuid,
     connection_info: ConnectionInfo,
     state: gameserver::GameServerState,
     orchestration: gameserver::GameServerOrchestration,
+    region: Option<String>,
     session_info: Option<&GameSessionInfo>,
     pending_players: impl Iterator<Item = &'a PendingPlayer>,
     active_players: impl Iterator<Item = &'a ActivePlayer>,
+    input_metrics: &InputBufferMetrics,
+    tick_metrics: &TickMetrics,
+    request_id: Option<String>,
 ) -> anyhow::Result<BevyReqwestBuilder<'a>> {
     debug!("heartbeat");
 
-    let url = format!("{}/gameserver/heartbeat/v1", HOST);
+    let active_players = active_players.collect::<Vec<_>>();
 
-    let req = client
+    let url = format!("{}/gameserver/heartbeat/v1", api_url);
+
+    let mut req = client
         .post(url)
         // TODO: should be auth JWT token
-        .bearer_auth(server_id.to_string())
+        .bearer_auth(server_id.to_string());
+    if let Some(request_id) = &request_id {
+        debug!("acknowledging request {}", request_id);
+        req = req.header(common::REQUEST_ID_HEADER, request_id);
+    }
+
+    let req = req
         .json(&gameserver::PostHeartbeatRequestV1 {
             server_info: gameserver::GameServerInfo {
                 v4addrs: connection_info.v4addrs.iter().cloned().collect(),
@@ -36,17 +50,31 @@ pub fn heartbeat<'a>(
                 port: connection_info.port,
                 state,
                 orchestration,
+                region,
                 game_session_info: session_info.map(|session_info| gameserver::GameSessionInfo {
                     max_players: session_info.max_players,
                     game_session_id: session_info.session_id,
                     active_player_ids: active_players
+                        .iter()
                         .map(|active_player| active_player.user_id)
                         .collect(),
                     pending_player_ids: pending_players
                         .map(|pending_player| pending_player.user_id)
                         .collect(),
+                    player_rtt_ms: active_players
+                        .iter()
+                        .filter_map(|active_player| {
+                            active_player
+                                .rtt
+                                .map(|rtt| (active_player.user_id, rtt.as_millis() as u32))
+                        })
+                        .collect(),
+                    input_buffer_underruns: input_metrics.underruns,
+                    input_buffer_overruns: input_metrics.overruns,
+                    tick_overruns: tick_metrics.overruns,
                 }),
             },
+            request_id,
         })
         .build()?;
 
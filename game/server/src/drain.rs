@@ -0,0 +1,240 @@
+use bevy::{prelude::*, utils::Duration};
+use bevy_replicon::prelude::*;
+use bevy_tokio_tasks::TokioTasksRuntime;
+use tokio::sync::mpsc;
+
+use game_common::{
+    network::{ServerDisconnectEvent, ServerShutdownWarningEvent},
+    server::GameSessionInfo,
+};
+
+use crate::{
+    options::Options,
+    server::{EndSessionEvent, HeartbeatEvent},
+    AppState,
+};
+
+const DISCONNECT_REASON: &str = "Server is shutting down";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DrainReason {
+    Signal,
+    Admin,
+    Orchestrator,
+}
+
+// lets things outside of the app (signals, orchestrator callbacks) request a drain
+#[derive(Debug, Clone, Resource)]
+pub struct DrainSender(mpsc::UnboundedSender<DrainReason>);
+
+impl DrainSender {
+    pub fn request(&self, reason: DrainReason) {
+        // the receiver only goes away with the app
+        let _ = self.0.send(reason);
+    }
+}
+
+#[derive(Debug, Resource)]
+struct DrainReceiver(mpsc::UnboundedReceiver<DrainReason>);
+
+#[derive(Debug, Event)]
+pub struct DrainEvent(pub DrainReason);
+
+// server is finishing its current session and not accepting new players
+#[derive(Debug, Resource)]
+pub struct Draining {
+    timer: Timer,
+    last_warning: Option<u32>,
+    ended: bool,
+}
+
+impl Draining {
+    fn new(timeout: Duration) -> Self {
+        Self {
+            timer: Timer::new(timeout, TimerMode::Once),
+            last_warning: None,
+            ended: false,
+        }
+    }
+
+    #[inline]
+    fn seconds_remaining(&self) -> u32 {
+        self.timer.remaining_secs().ceil() as u32
+    }
+
+    // skip whatever is left of the countdown
+    #[inline]
+    fn finish(&mut self) {
+        let duration = self.timer.duration();
+        self.timer.set_elapsed(duration);
+    }
+}
+
+#[derive(Debug)]
+pub struct DrainPlugin;
+
+impl Plugin for DrainPlugin {
+    fn build(&self, app: &mut App) {
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        app.add_event::<DrainEvent>()
+            .insert_resource(DrainSender(tx))
+            .insert_resource(DrainReceiver(rx))
+            .add_systems(Startup, listen_for_signals)
+            // run early so Draining is in place before anything reports it
+            .add_systems(
+                PreUpdate,
+                (receive_drain_requests, handle_drain_events).chain(),
+            )
+            .add_systems(Update, update_drain.run_if(in_state(AppState::InGame)));
+    }
+}
+
+#[cfg(unix)]
+async fn wait_for_signal(sigterm: &mut tokio::signal::unix::Signal) -> std::io::Result<()> {
+    tokio::select! {
+        res = tokio::signal::ctrl_c() => res,
+        _ = sigterm.recv() => Ok(()),
+    }
+}
+
+fn listen_for_signals(runtime: Res<TokioTasksRuntime>, drain: Res<DrainSender>) {
+    let drain = drain.clone();
+    runtime.spawn_background_task(move |_ctx| async move {
+        #[cfg(unix)]
+        let mut sigterm =
+            match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+                Ok(sigterm) => sigterm,
+                Err(err) => {
+                    error!("failed to listen for SIGTERM: {}", err);
+                    return;
+                }
+            };
+
+        // a second signal skips the rest of the drain
+        loop {
+            #[cfg(unix)]
+            let res = wait_for_signal(&mut sigterm).await;
+
+            #[cfg(not(unix))]
+            let res = tokio::signal::ctrl_c().await;
+
+            if let Err(err) = res {
+                error!("failed to listen for shutdown signals: {}", err);
+                return;
+            }
+
+            info!("received shutdown signal");
+            drain.request(DrainReason::Signal);
+        }
+    });
+}
+
+fn receive_drain_requests(
+    mut receiver: ResMut<DrainReceiver>,
+    mut evw_drain: EventWriter<DrainEvent>,
+) {
+    while let Ok(reason) = receiver.0.try_recv() {
+        evw_drain.send(DrainEvent(reason));
+    }
+}
+
+fn handle_drain_events(
+    mut commands: Commands,
+    options: Res<Options>,
+    current_state: Res<State<AppState>>,
+    mut draining: Option<ResMut<Draining>>,
+    mut app_state: ResMut<NextState<AppState>>,
+    mut evr_drain: EventReader<DrainEvent>,
+    mut evw_heartbeat: EventWriter<HeartbeatEvent>,
+) {
+    for DrainEvent(reason) in evr_drain.read() {
+        match current_state.get() {
+            AppState::InitServer | AppState::InGame => {
+                if let Some(draining) = draining.as_mut() {
+                    warn!(
+                        "drain requested ({:?}) while draining, ending session now ...",
+                        reason
+                    );
+                    draining.finish();
+                    continue;
+                }
+
+                // finish the current session before shutting down
+                info!(
+                    "draining server ({:?}), players have {:?} ...",
+                    reason,
+                    options.drain_timeout()
+                );
+                commands.insert_resource(Draining::new(options.drain_timeout()));
+
+                // let the backend know to stop sending us players
+                evw_heartbeat.send_default();
+
+                // anything else this frame is the same request
+                break;
+            }
+            AppState::Shutdown => {
+                warn!(
+                    "ignoring drain request ({:?}), already shutting down!",
+                    reason
+                );
+            }
+            _ => {
+                info!("draining idle server ({:?}), shutting down ...", reason);
+                app_state.set(AppState::Shutdown);
+            }
+        }
+    }
+}
+
+fn update_drain(
+    time: Res<Time>,
+    draining: Option<ResMut<Draining>>,
+    session_info: Option<Res<GameSessionInfo>>,
+    mut evw_warning: EventWriter<ToClients<ServerShutdownWarningEvent>>,
+    mut evw_disconnect: EventWriter<ToClients<ServerDisconnectEvent>>,
+    mut evw_end_session: EventWriter<EndSessionEvent>,
+) {
+    let Some(mut draining) = draining else {
+        return;
+    };
+
+    if draining.ended {
+        return;
+    }
+
+    if session_info.is_none_or(|session_info| session_info.player_count() == 0) {
+        info!("drained, ending session");
+        draining.ended = true;
+        evw_end_session.send_default();
+        return;
+    }
+
+    draining.timer.tick(time.delta());
+
+    if draining.timer.finished() {
+        info!("drain timeout, disconnecting players");
+
+        // the disconnect goes out when the session is torn down next frame
+        evw_disconnect.send(ToClients {
+            mode: SendMode::Broadcast,
+            event: ServerDisconnectEvent {
+                reason: DISCONNECT_REASON.into(),
+            },
+        });
+
+        draining.ended = true;
+        evw_end_session.send_default();
+        return;
+    }
+
+    let seconds_remaining = draining.seconds_remaining();
+    if draining.last_warning != Some(seconds_remaining) {
+        evw_warning.send(ToClients {
+            mode: SendMode::Broadcast,
+            event: ServerShutdownWarningEvent { seconds_remaining },
+        });
+        draining.last_warning = Some(seconds_remaining);
+    }
+}
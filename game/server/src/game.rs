@@ -33,6 +33,13 @@ impl Plugin for GamePlugin {
     }
 }
 
+fn format_active_player(active_player: &ActivePlayer) -> String {
+    match active_player.rtt {
+        Some(rtt) => format!("  {} ({}ms)", active_player.user_id, rtt.as_millis()),
+        None => format!("  {} (-)", active_player.user_id),
+    }
+}
+
 fn enter_spectate(
     mut commands: Commands,
     server_info: Res<GameServerInfo>,
@@ -122,7 +129,7 @@ fn enter_spectate(
 
             let mut active_players_str = String::new();
             for active_player in &active_players {
-                active_players_str.push_str(&format!("  {}", active_player.user_id));
+                active_players_str.push_str(&format_active_player(active_player));
             }
 
             parent.spawn((
@@ -150,7 +157,7 @@ fn update_spectate_ui(
 
     let mut active_players_str = String::new();
     for active_player in &active_players {
-        active_players_str.push_str(&format!("  {}", active_player.user_id));
+        active_players_str.push_str(&format_active_player(active_player));
     }
 
     active_player_list.single_mut().0 = active_players_str;
@@ -1,20 +1,24 @@
+mod advertise;
+mod afk;
 mod api;
+mod drain;
 mod game;
 mod notifs;
 mod options;
 mod orchestration;
+mod ping;
 mod placement;
 mod server;
 mod tasks;
+mod tick;
+mod validation;
 
 use bevy::prelude::*;
 use bevy_replicon::prelude::*;
 use bevy_replicon_renet::RepliconRenetPlugins;
 use bevy_tokio_tasks::TokioTasksPlugin;
-use clap::Parser;
-
-use common::gameserver::GameServerState;
-use game_common::SERVER_TICK_RATE;
+use common::{config, gameserver::GameServerState};
+use game_common::TickRate;
 
 use options::Options;
 
@@ -51,8 +55,18 @@ pub fn is_not_headless(options: Res<Options>) -> bool {
     !options.headless
 }
 
-fn main() {
-    let options = Options::parse();
+fn main() -> anyhow::Result<()> {
+    let options: Options = config::load(options::ENV_PREFIX)?;
+    anyhow::ensure!(options.tick_rate > 0, "tick rate must be at least 1");
+    anyhow::ensure!(
+        options.max_input_rate() > options.tick_rate as u32,
+        "max input rate must be above the tick rate, clients send input every tick"
+    );
+
+    // fail now rather than when the first session starts
+    options.connect_token_key()?;
+
+    let tick_rate = TickRate(options.tick_rate);
 
     println!("initializing server ...");
 
@@ -64,7 +78,7 @@ fn main() {
 
         app.add_plugins((
             MinimalPlugins.set(bevy::app::ScheduleRunnerPlugin::run_loop(
-                bevy::utils::Duration::from_secs_f64(1.0 / SERVER_TICK_RATE as f64),
+                tick_rate.interval(),
             )),
             // not sure why MinimalPlugins doesn't include any of this
             // or why the HeadlessPlugins that was supposed to be in 0.15 was removed
@@ -109,7 +123,9 @@ fn main() {
                         bevy::log::DEFAULT_FILTER
                     ),
                     ..default()
-                }),
+                })
+                // signals start a drain instead of exiting
+                .disable::<bevy::app::TerminalCtrlCHandlerPlugin>(),
         );
     }
 
@@ -117,20 +133,25 @@ fn main() {
         // bevy plugins
         // third-party plugins
         .add_plugins((
-            RepliconPlugins, /*.set(ServerPlugin {
-                                 tick_policy: TickPolicy::MaxTickRate(SERVER_TICK_RATE),
-                                 ..default()
-                             })*/
+            RepliconPlugins.set(ServerPlugin {
+                tick_policy: TickPolicy::MaxTickRate(tick_rate.0),
+                ..default()
+            }),
             RepliconRenetPlugins,
             TokioTasksPlugin::default(),
             bevy_mod_reqwest::ReqwestPlugin::default(),
             bevy_mod_websocket::WebSocketPlugin,
         ))
+        // the server plugin reads its config at build time
+        .insert_resource(options)
+        // the game plugin sets up FixedUpdate from this
+        .insert_resource(tick_rate)
         // server / game plugins
         .add_plugins((server::ServerPlugin, game_common::GamePlugin))
-        .insert_resource(options)
         .init_state::<AppState>();
 
     info!("running server ...");
     app.run();
+
+    Ok(())
 }
@@ -0,0 +1,12 @@
+use bevy::prelude::*;
+
+use internal::notifs;
+
+use crate::drain::{DrainEvent, DrainReason};
+
+pub fn handle_v1(_request: notifs::DrainRequestV1, evw_drain: &mut EventWriter<DrainEvent>) {
+    info!("drain requested");
+
+    // shares the drain flow with signals and the orchestrator
+    evw_drain.send(DrainEvent(DrainReason::Admin));
+}
@@ -0,0 +1,34 @@
+use bevy::prelude::*;
+
+use game_common::server::GameSessionInfo;
+use internal::notifs;
+
+use crate::{server::EndSessionEvent, AppState};
+
+pub fn handle_v1(
+    current_state: &AppState,
+    session_info: Option<&GameSessionInfo>,
+    request: notifs::EndSessionRequestV1,
+    evw_end_session: &mut EventWriter<EndSessionEvent>,
+) {
+    if *current_state != AppState::InGame {
+        warn!("ignoring unexpected end session request!");
+        return;
+    }
+
+    let Some(session_info) = session_info else {
+        warn!("ignoring end session request without a session!");
+        return;
+    };
+
+    if session_info.session_id != request.game_session_id {
+        warn!(
+            "ignoring end session request for {}, current session is {}",
+            request.game_session_id, session_info.session_id
+        );
+        return;
+    }
+
+    info!("ending session {} ...", session_info.session_id);
+    evw_end_session.send_default();
+}
@@ -0,0 +1,56 @@
+use bevy::prelude::*;
+use bevy_replicon_renet::renet::RenetServer;
+
+use game_common::server::{GameSessionInfo, PendingPlayer};
+use internal::notifs;
+
+use crate::{server::HeartbeatEvent, AppState};
+
+pub fn handle_v1<'a>(
+    commands: &mut Commands,
+    current_state: &AppState,
+    session_info: &mut GameSessionInfo,
+    server: Option<&mut RenetServer>,
+    mut pending_players: impl Iterator<Item = (Entity, &'a PendingPlayer)>,
+    request: notifs::KickPlayerRequestV1,
+    request_id: Option<String>,
+    evw_heartbeat: &mut EventWriter<HeartbeatEvent>,
+) {
+    if *current_state != AppState::InGame {
+        warn!("ignoring unexpected kick player request!");
+        return;
+    }
+
+    if session_info.session_id != request.game_session_id {
+        warn!(
+            "ignoring kick player request for {}, current session is {}",
+            request.game_session_id, session_info.session_id
+        );
+        return;
+    }
+
+    if let Some(client_id) = session_info.get_client_id(request.player_id) {
+        info!("kicking player {} ({:?})", request.player_id, client_id);
+
+        // disconnect handling will clean up the player
+        if let Some(server) = server {
+            server.disconnect(client_id.get());
+        }
+        return;
+    }
+
+    if let Some((entity, _)) =
+        pending_players.find(|(_, pending_player)| pending_player.user_id == request.player_id)
+    {
+        info!("kicking pending player {}", request.player_id);
+
+        session_info.cancel_reservation(commands, entity, request.player_id);
+        evw_heartbeat.send(HeartbeatEvent::for_request(request_id));
+        return;
+    }
+
+    warn!(
+        "ignoring kick request for unknown player {}",
+        request.player_id
+    );
+}
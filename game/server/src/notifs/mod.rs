@@ -1,36 +1,59 @@
+mod drain;
+mod endsession;
+mod kick;
 mod placement;
 mod reservation;
+mod shutdown;
 
-use bevy::{prelude::*, utils::Duration};
+use bevy::prelude::*;
 use bevy_mod_websocket::*;
+use bevy_replicon_renet::renet::RenetServer;
 use tokio_tungstenite::tungstenite::client::IntoClientRequest;
 use uuid::Uuid;
 
-use game_common::server::GameSessionInfo;
+use game_common::server::{GameSessionInfo, PendingPlayer};
 use internal::notifs;
 
-use crate::{server::HeartbeatEvent, AppState};
-
-const HOST: &str = "ws://localhost:8001";
-const RETRY_INTERVAL: Duration = Duration::from_secs(10);
+use crate::{
+    drain::{DrainEvent, Draining},
+    options::Options,
+    server::{EndSessionEvent, HeartbeatEvent},
+    AppState,
+};
 
 fn on_success(trigger: Trigger<WebSocketConnectSuccessEvent>) {
     let evt = trigger.event();
     info!("subscribe success: {:?}", evt);
 }
 
-fn on_error(trigger: Trigger<WebSocketErrorEvent>, mut ws_client: WebSocketClient) {
+fn on_error(
+    trigger: Trigger<WebSocketErrorEvent>,
+    options: Res<Options>,
+    mut ws_client: WebSocketClient,
+) {
     let evt = trigger.event();
     warn!("notifs error: {:?}", evt.error);
 
-    ws_client.retry(trigger.entity(), evt.request.clone(), RETRY_INTERVAL);
+    ws_client.retry(
+        trigger.entity(),
+        evt.request.clone(),
+        options.notifs_retry_interval(),
+    );
 }
 
-fn on_disconnect(trigger: Trigger<WebSocketDisconnectEvent>, mut ws_client: WebSocketClient) {
+fn on_disconnect(
+    trigger: Trigger<WebSocketDisconnectEvent>,
+    options: Res<Options>,
+    mut ws_client: WebSocketClient,
+) {
     let evt = trigger.event();
     warn!("notifs disconnect");
 
-    ws_client.retry(trigger.entity(), evt.request.clone(), RETRY_INTERVAL);
+    ws_client.retry(
+        trigger.entity(),
+        evt.request.clone(),
+        options.notifs_retry_interval(),
+    );
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -40,7 +63,12 @@ fn on_message(
     current_state: Res<State<AppState>>,
     mut app_state: ResMut<NextState<AppState>>,
     mut session_info: Option<ResMut<GameSessionInfo>>,
+    draining: Option<Res<Draining>>,
+    mut server: Option<ResMut<RenetServer>>,
+    pending_players: Query<(Entity, &PendingPlayer)>,
     mut evw_heartbeat: EventWriter<HeartbeatEvent>,
+    mut evw_end_session: EventWriter<EndSessionEvent>,
+    mut evw_drain: EventWriter<DrainEvent>,
 ) {
     let evt = trigger.event();
 
@@ -50,6 +78,14 @@ fn on_message(
 
             // TODO: error handling
             let notif = serde_json::from_str::<notifs::Notification>(value).unwrap();
+            info!(
+                "handling {:?} notif {} (request {})",
+                notif.r#type,
+                notif.id,
+                notif.request_id.as_deref().unwrap_or("-")
+            );
+
+            let request_id = notif.request_id.clone();
             match notif.r#type {
                 notifs::NotifType::PlacementRequestV1 => {
                     placement::handle_v1(
@@ -58,15 +94,66 @@ fn on_message(
                         &mut app_state,
                         // TODO: error handling
                         notif.to_message::<notifs::PlacementRequestV1>().unwrap(),
+                        request_id,
                     );
                 }
                 notifs::NotifType::ReservationRequestV1 => {
+                    // the api can still think we're hosting a session that's ended
+                    let Some(session_info) = session_info.as_mut() else {
+                        warn!("ignoring reservation request without a session!");
+                        return;
+                    };
+
                     reservation::handle_v1(
                         &mut commands,
                         &current_state,
-                        session_info.as_mut().unwrap(),
+                        draining.is_some(),
+                        session_info,
                         // TODO: error handling
                         notif.to_message::<notifs::ReservationRequestV1>().unwrap(),
+                        request_id,
+                        &mut evw_heartbeat,
+                    );
+                }
+                notifs::NotifType::DrainRequestV1 => {
+                    drain::handle_v1(
+                        // TODO: error handling
+                        notif.to_message::<notifs::DrainRequestV1>().unwrap(),
+                        &mut evw_drain,
+                    );
+                }
+                notifs::NotifType::ShutdownRequestV1 => {
+                    shutdown::handle_v1(
+                        &current_state,
+                        &mut app_state,
+                        // TODO: error handling
+                        notif.to_message::<notifs::ShutdownRequestV1>().unwrap(),
+                    );
+                }
+                notifs::NotifType::EndSessionRequestV1 => {
+                    endsession::handle_v1(
+                        &current_state,
+                        session_info.as_deref(),
+                        // TODO: error handling
+                        notif.to_message::<notifs::EndSessionRequestV1>().unwrap(),
+                        &mut evw_end_session,
+                    );
+                }
+                notifs::NotifType::KickPlayerRequestV1 => {
+                    let Some(session_info) = session_info.as_mut() else {
+                        warn!("ignoring kick player request without a session!");
+                        return;
+                    };
+
+                    kick::handle_v1(
+                        &mut commands,
+                        &current_state,
+                        session_info,
+                        server.as_deref_mut(),
+                        pending_players.iter(),
+                        // TODO: error handling
+                        notif.to_message::<notifs::KickPlayerRequestV1>().unwrap(),
+                        request_id,
                         &mut evw_heartbeat,
                     );
                 }
@@ -78,9 +165,13 @@ fn on_message(
     }
 }
 
-pub fn subscribe<'a>(client: &'a mut WebSocketClient, server_id: Uuid) -> WebSocketBuilder<'a> {
+pub fn subscribe<'a>(
+    client: &'a mut WebSocketClient,
+    notifs_url: &str,
+    server_id: Uuid,
+) -> WebSocketBuilder<'a> {
     // TODO: get rid of the need to call into_client_request so we can drop the tungstenite dependency
-    let mut notifs_request = format!("{}/gameserver/notifs/v1", HOST)
+    let mut notifs_request = format!("{}/gameserver/notifs/v1", notifs_url)
         .into_client_request()
         .unwrap();
     let headers = notifs_request.headers_mut();
@@ -1,25 +1,23 @@
 use bevy::prelude::*;
 
-use common::GameSettings;
 use game_common::server::GameSessionInfo;
 use internal::notifs;
 
-use crate::AppState;
+use crate::{server::PlacementRequestId, AppState};
 
 pub fn handle_v1(
     commands: &mut Commands,
     current_state: &AppState,
     app_state: &mut NextState<AppState>,
     request: notifs::PlacementRequestV1,
+    request_id: Option<String>,
 ) {
     if *current_state != AppState::WaitForPlacement {
         warn!("ignoring unexpected placement request!");
         return;
     }
 
-    // TODO: should come from the placement request
-    // (as matchtype or something we can look up settings for)
-    let game_settings = GameSettings::default();
+    let game_settings = request.game_settings;
 
     if request.player_ids.len() > game_settings.max_players as usize {
         warn!(
@@ -42,6 +40,9 @@ pub fn handle_v1(
     );
 
     commands.insert_resource(session_info);
+    if let Some(request_id) = request_id {
+        commands.insert_resource(PlacementRequestId(request_id));
+    }
 
     app_state.set(AppState::InitServer);
 }
@@ -8,8 +8,10 @@ use crate::{server::HeartbeatEvent, AppState};
 pub fn handle_v1(
     commands: &mut Commands,
     current_state: &AppState,
+    draining: bool,
     session_info: &mut GameSessionInfo,
     request: notifs::ReservationRequestV1,
+    request_id: Option<String>,
     evw_heartbeat: &mut EventWriter<HeartbeatEvent>,
 ) {
     if *current_state != AppState::InGame {
@@ -17,6 +19,20 @@ pub fn handle_v1(
         return;
     }
 
+    if draining {
+        warn!("ignoring reservation request while draining!");
+        return;
+    }
+
+    // left over from a session we've since ended
+    if session_info.session_id != request.game_session_id {
+        warn!(
+            "ignoring reservation request for {}, current session is {}",
+            request.game_session_id, session_info.session_id
+        );
+        return;
+    }
+
     if session_info.player_count() + request.player_ids.len() > session_info.max_players as usize {
         warn!(
             "ignoring reservation request with too many players: {}",
@@ -30,5 +46,5 @@ pub fn handle_v1(
         session_info.reserve_player(commands, player_id);
     }
 
-    evw_heartbeat.send_default();
+    evw_heartbeat.send(HeartbeatEvent::for_request(request_id));
 }
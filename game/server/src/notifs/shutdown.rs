@@ -0,0 +1,19 @@
+use bevy::prelude::*;
+
+use internal::notifs;
+
+use crate::AppState;
+
+pub fn handle_v1(
+    current_state: &AppState,
+    app_state: &mut NextState<AppState>,
+    _request: notifs::ShutdownRequestV1,
+) {
+    if *current_state == AppState::Shutdown {
+        warn!("ignoring shutdown request, already shutting down!");
+        return;
+    }
+
+    info!("shutdown requested, shutting down ...");
+    app_state.set(AppState::Shutdown);
+}
@@ -1,8 +1,24 @@
-use bevy::prelude::*;
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use bevy::{prelude::*, utils::Duration};
 use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+use common::{
+    config::ConfigArgs,
+    netcode::{self, CONNECT_TOKEN_KEY_BYTES},
+};
+use game_common::{DEFAULT_TICK_RATE, MAX_LOOK_DELTA};
+
+use crate::advertise::AddressClass;
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, clap::ValueEnum)]
+pub const ENV_PREFIX: &str = "BMP_SERVER_";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, clap::ValueEnum, Serialize, Deserialize)]
 #[clap(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
 pub enum OrchestrationType {
     Local,
 
@@ -13,9 +29,75 @@ pub enum OrchestrationType {
     GameLift,
 }
 
-#[derive(Parser, Debug, Resource)]
+// inclusive range of ports, either start-end or a single port
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct PortRange {
+    pub start: u16,
+    pub end: u16,
+}
+
+impl PortRange {
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = u16> {
+        self.start..=self.end
+    }
+}
+
+impl FromStr for PortRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |port: &str| {
+            port.trim()
+                .parse::<u16>()
+                .map_err(|err| format!("invalid port {:?}: {}", port, err))
+        };
+
+        let (start, end) = match s.split_once('-') {
+            Some((start, end)) => (parse(start)?, parse(end)?),
+            None => {
+                let port = parse(s)?;
+                (port, port)
+            }
+        };
+
+        if start == 0 || start > end {
+            return Err(format!("invalid port range {:?}", s));
+        }
+
+        Ok(Self { start, end })
+    }
+}
+
+impl TryFrom<String> for PortRange {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl fmt::Display for PortRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+impl From<PortRange> for String {
+    fn from(range: PortRange) -> Self {
+        range.to_string()
+    }
+}
+
+#[derive(Parser, Debug, Resource, Serialize, Deserialize)]
 pub struct Options {
+    #[command(flatten)]
+    #[serde(skip)]
+    pub config: ConfigArgs,
+
     #[arg(long)]
+    #[serde(default)]
     pub headless: bool,
 
     #[arg(value_enum, default_value_t = OrchestrationType::Local)]
@@ -24,15 +106,153 @@ pub struct Options {
     #[arg(long, default_value = "0.0.0.0")]
     pub host: String,
 
+    // 0 lets the OS pick a free port
     #[arg(short, long, default_value_t = 5576)]
     pub port: u16,
 
+    // bind the first free port in this range instead of --port
+    #[arg(long)]
+    pub port_range: Option<PortRange>,
+
+    #[arg(long)]
+    pub region: Option<String>,
+
     #[arg(short, long, default_value = "vec![\"logs\"]")]
     pub log_paths: Vec<String>,
+
+    // addresses to advertise instead of asking the orchestrator / discovering them
+    #[arg(long, value_delimiter = ',')]
+    #[serde(default)]
+    pub advertise_addresses: Vec<IpAddr>,
+
+    // port to advertise if it differs from the bound port (NAT, host ports)
+    #[arg(long)]
+    pub advertise_port: Option<u16>,
+
+    // interfaces to discover addresses from, a trailing * matches a prefix
+    #[arg(long, value_delimiter = ',')]
+    #[serde(default)]
+    pub interface_include: Vec<String>,
+
+    #[arg(long, value_delimiter = ',', default_value = "docker*,br-*")]
+    pub interface_exclude: Vec<String>,
+
+    // classes of discovered addresses to advertise
+    #[arg(
+        long,
+        value_delimiter = ',',
+        value_enum,
+        default_value = "private,public"
+    )]
+    pub address_classes: Vec<AddressClass>,
+
+    // base64 encoded key shared with the api to validate connect tokens
+    #[arg(long, env = "CONNECT_TOKEN_KEY")]
+    pub connect_token_key: Option<String>,
+
+    // use the development key checked into the repo if no key is set, local development only!
+    #[arg(long)]
+    #[serde(default)]
+    pub insecure_dev_key: bool,
+
+    #[arg(long, default_value = "http://localhost:8000")]
+    pub api_url: String,
+
+    #[arg(long, default_value = "ws://localhost:8001")]
+    pub notifs_url: String,
+
+    // simulation ticks per second, also how often updates are sent to clients
+    #[arg(long, default_value_t = DEFAULT_TICK_RATE)]
+    pub tick_rate: u16,
+
+    #[arg(long, default_value_t = 5)]
+    pub heartbeat_interval_secs: u64,
+
+    #[arg(long, default_value_t = 10)]
+    pub notifs_retry_interval_secs: u64,
+
+    // sessions to host before exiting so the process is recycled, 0 for no limit
+    #[arg(long, default_value_t = 0)]
+    pub max_sessions: u32,
+
+    // how long players get to finish up before a draining server disconnects them
+    #[arg(long, default_value_t = 30)]
+    pub drain_timeout_secs: u64,
+
+    #[arg(long, default_value_t = 5)]
+    pub ping_interval_secs: u64,
+
+    // clients that send nothing (input or pongs) for this long are disconnected
+    #[arg(long, default_value_t = 15)]
+    pub client_timeout_secs: u64,
+
+    // largest look delta accepted in a single input update
+    #[arg(long, default_value_t = MAX_LOOK_DELTA)]
+    pub max_look_delta: f32,
+
+    // input updates accepted from a client per second, 0 for twice the tick rate
+    // clients send one per fixed tick, so this has to stay above the tick rate
+    #[arg(long, default_value_t = 0)]
+    pub max_input_rate: u32,
+
+    // jumps accepted from a client per second
+    #[arg(long, default_value_t = 10)]
+    pub max_jump_rate: u32,
+
+    // input violations in a session before a player is kicked, 0 to never kick
+    #[arg(long, default_value_t = 10)]
+    pub input_violation_kick_threshold: u32,
+
+    // input violations in a session before a player is banned from this server, 0 to never ban
+    #[arg(long, default_value_t = 50)]
+    pub input_violation_ban_threshold: u32,
 }
 
 impl Options {
-    pub fn address(&self) -> String {
-        format!("{}:{}", self.host, self.port)
+    pub fn connect_token_key(&self) -> anyhow::Result<[u8; CONNECT_TOKEN_KEY_BYTES]> {
+        match &self.connect_token_key {
+            Some(key) => netcode::decode_connect_token_key(key),
+            None if self.insecure_dev_key => {
+                warn!("no connect token key set, using the insecure development key!");
+                netcode::decode_connect_token_key(netcode::DEV_CONNECT_TOKEN_KEY)
+            }
+            None => anyhow::bail!(
+                "no connect token key set (use --insecure-dev-key for local development)"
+            ),
+        }
+    }
+
+    #[inline]
+    pub fn heartbeat_interval(&self) -> Duration {
+        Duration::from_secs(self.heartbeat_interval_secs)
+    }
+
+    #[inline]
+    pub fn notifs_retry_interval(&self) -> Duration {
+        Duration::from_secs(self.notifs_retry_interval_secs)
+    }
+
+    #[inline]
+    pub fn drain_timeout(&self) -> Duration {
+        Duration::from_secs(self.drain_timeout_secs)
+    }
+
+    #[inline]
+    pub fn ping_interval(&self) -> Duration {
+        Duration::from_secs(self.ping_interval_secs)
+    }
+
+    #[inline]
+    pub fn client_timeout(&self) -> Duration {
+        Duration::from_secs(self.client_timeout_secs)
+    }
+
+    #[inline]
+    pub fn max_input_rate(&self) -> u32 {
+        if self.max_input_rate > 0 {
+            self.max_input_rate
+        } else {
+            self.tick_rate as u32 * 2
+        }
     }
 }
@@ -1,30 +1,43 @@
 #![cfg(feature = "agones")]
 
+use std::net::IpAddr;
 use std::sync::{Arc, Mutex};
 
 use bevy::prelude::*;
 use bevy_tokio_tasks::TokioTasksRuntime;
 use tokio::sync::{mpsc, oneshot};
 
-use crate::tasks;
+use super::PublicEndpoint;
+use crate::{
+    drain::{DrainReason, DrainSender},
+    tasks,
+};
+
+// name of the GameServer port clients connect to
+const PORT_NAME: &str = "default";
+
+// GameServer state when agones wants us gone
+const SHUTDOWN_STATE: &str = "Shutdown";
 
 #[derive(Clone)]
 pub struct AgonesState {
     sdk: agones_api::Sdk,
     health: mpsc::Sender<()>,
+    drain: DrainSender,
 
     // TODO: it's dumb this needs a mutex
     // this should probably be a parking_lot mutex at least
     watcher: Arc<Mutex<Option<oneshot::Sender<()>>>>,
 }
 
-pub(super) async fn new_sdk() -> anyhow::Result<AgonesState> {
+pub(super) async fn new_sdk(drain: DrainSender) -> anyhow::Result<AgonesState> {
     let sdk = agones_api::Sdk::new(None, None).await?;
     let health = sdk.health_check();
 
     Ok(AgonesState {
         sdk,
         health,
+        drain,
         watcher: Arc::new(Mutex::new(None)),
     })
 }
@@ -37,8 +50,42 @@ pub(super) async fn ready(mut agones: AgonesState) -> anyhow::Result<()> {
     Ok(())
 }
 
+pub(super) async fn public_endpoint(
+    mut agones: AgonesState,
+) -> anyhow::Result<Option<PublicEndpoint>> {
+    let gs = agones.sdk.get_gameserver().await?;
+    let Some(status) = gs.status else {
+        return Ok(None);
+    };
+
+    let addresses = match status.address.parse::<IpAddr>() {
+        Ok(ip) => vec![ip],
+        Err(_) => {
+            warn!("ignoring non-ip GameServer address {:?}", status.address);
+            vec![]
+        }
+    };
+
+    // fall back to the first port if the default isn't there
+    let port = status
+        .ports
+        .iter()
+        .find(|port| port.name == PORT_NAME)
+        .or_else(|| status.ports.first())
+        .map(|port| port.port as u16);
+
+    Ok(Some(PublicEndpoint { addresses, port }))
+}
+
 pub(super) fn start_watcher(agones: AgonesState, runtime: &TokioTasksRuntime) {
+    // we go back through ready for every session
+    if agones.watcher.lock().unwrap().is_some() {
+        debug!("GameServer watch loop already running");
+        return;
+    }
+
     let mut watch_client = agones.sdk.clone();
+    let drain = agones.drain.clone();
     let (tx, mut rx) = oneshot::channel::<()>();
     tasks::spawn_task(
         runtime,
@@ -51,8 +98,16 @@ pub(super) fn start_watcher(agones: AgonesState, runtime: &TokioTasksRuntime) {
                     gs = stream.message() => {
                         match gs {
                             Ok(Some(gs)) => {
+                                let state = gs.status.map(|status| status.state).unwrap_or_default();
+
                                 info!("GameServer Update, name: {}", gs.object_meta.unwrap().name);
-                                info!("GameServer Update, state: {}", gs.status.unwrap().state);
+                                info!("GameServer Update, state: {}", state);
+
+                                // we stop watching before shutting ourselves down
+                                // so this is someone else shutting us down
+                                if state == SHUTDOWN_STATE {
+                                    drain.request(DrainReason::Orchestrator);
+                                }
                             }
                             Ok(None) => {
                                 info!("server closed the GameServer watch stream");
@@ -1,5 +1,6 @@
 #![cfg(feature = "gamelift")]
 
+use std::net::IpAddr;
 use std::sync::Arc;
 
 use aws_gamelift_server_sdk_rs::{
@@ -8,33 +9,73 @@ use aws_gamelift_server_sdk_rs::{
 use bevy::prelude::*;
 use tokio::sync::RwLock;
 
-pub type GameliftApi = Arc<RwLock<aws_gamelift_server_sdk_rs::api::Api>>;
+use super::PublicEndpoint;
+use crate::drain::{DrainReason, DrainSender};
 
-pub(super) async fn new_api() -> anyhow::Result<GameliftApi> {
+#[derive(Clone)]
+pub struct GameliftState {
+    api: Arc<RwLock<aws_gamelift_server_sdk_rs::api::Api>>,
+
+    // set once gamelift starts a game session on us
+    endpoint: Arc<RwLock<Option<PublicEndpoint>>>,
+
+    drain: DrainSender,
+}
+
+pub(super) async fn new_api(drain: DrainSender) -> anyhow::Result<GameliftState> {
     let mut api = aws_gamelift_server_sdk_rs::api::Api::default();
     api.init_sdk().await?;
 
-    Ok(Arc::new(RwLock::new(api)))
+    Ok(GameliftState {
+        api: Arc::new(RwLock::new(api)),
+        endpoint: Arc::new(RwLock::new(None)),
+        drain,
+    })
+}
+
+pub(super) async fn public_endpoint(
+    gamelift: GameliftState,
+) -> anyhow::Result<Option<PublicEndpoint>> {
+    let endpoint = gamelift.endpoint.read().await.clone();
+
+    // on_start_game_session doesn't have to beat our own placement
+    if endpoint.is_none() {
+        warn!("no gamelift game session yet, advertised addresses may not be reachable!");
+    }
+
+    Ok(endpoint)
 }
 
 pub(super) async fn ready(
-    api: GameliftApi,
+    gamelift: GameliftState,
     port: u16,
     log_paths: Vec<String>,
 ) -> anyhow::Result<()> {
     info!("readying gamelift ...");
 
+    let api = gamelift.api.clone();
     api.write()
         .await
         .process_ready(ProcessParameters {
             on_start_game_session: Box::new({
                 let api = api.clone();
+                let endpoint = gamelift.endpoint.clone();
                 move |game_session| {
                     Box::pin({
                         let api = api.clone();
+                        let endpoint = endpoint.clone();
                         async move {
                             debug!("{:?}", game_session);
 
+                            endpoint.write().await.replace(PublicEndpoint {
+                                addresses: game_session
+                                    .ip_address
+                                    .parse::<IpAddr>()
+                                    .into_iter()
+                                    .collect(),
+                                port: u16::try_from(game_session.port).ok(),
+                            });
+
                             api.write().await.activate_game_session().await.unwrap();
 
                             info!("session active!");
@@ -45,7 +86,16 @@ pub(super) async fn ready(
             on_update_game_session: Box::new(|update_game_session| {
                 Box::pin(async move { debug!("{:?}", update_game_session) })
             }),
-            on_process_terminate: Box::new(|| Box::pin(async {})),
+            on_process_terminate: Box::new({
+                let drain = gamelift.drain.clone();
+                move || {
+                    let drain = drain.clone();
+                    Box::pin(async move {
+                        info!("gamelift terminating process ...");
+                        drain.request(DrainReason::Orchestrator);
+                    })
+                }
+            }),
             on_health_check: Box::new(|| Box::pin(async { true })),
             port: port as i32,
             log_parameters: LogParameters { log_paths },
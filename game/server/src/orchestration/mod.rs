@@ -1,11 +1,15 @@
 mod agones;
 mod gamelift;
 
+use std::net::IpAddr;
+
 use bevy::prelude::*;
 use bevy_tokio_tasks::TokioTasksRuntime;
 
 use common::gameserver::GameServerOrchestration;
 
+use crate::drain::DrainSender;
+
 #[derive(Debug, Event)]
 pub struct StartWatcherEvent;
 
@@ -24,6 +28,13 @@ pub fn start_watcher(
     orchestration.unwrap().start_watcher(&runtime);
 }
 
+// where the orchestrator says clients can reach us
+#[derive(Debug, Default, Clone)]
+pub struct PublicEndpoint {
+    pub addresses: Vec<IpAddr>,
+    pub port: Option<u16>,
+}
+
 #[derive(Clone, Resource)]
 pub enum Orchestration {
     Local,
@@ -32,34 +43,42 @@ pub enum Orchestration {
     Agones(agones::AgonesState),
 
     #[cfg(feature = "gamelift")]
-    GameLift(gamelift::GameliftApi),
+    GameLift(gamelift::GameliftState),
 }
 
 impl Orchestration {
-    pub async fn new(r#type: crate::options::OrchestrationType) -> anyhow::Result<Self> {
+    // the orchestrator can ask us to drain through the sender
+    pub async fn new(
+        r#type: crate::options::OrchestrationType,
+        drain: DrainSender,
+    ) -> anyhow::Result<Self> {
         match r#type {
             crate::options::OrchestrationType::Local => Ok(Self::Local),
 
             #[cfg(feature = "agones")]
-            crate::options::OrchestrationType::Agones => Ok(Self::Agones(agones::new_sdk().await?)),
+            crate::options::OrchestrationType::Agones => {
+                Ok(Self::Agones(agones::new_sdk(drain).await?))
+            }
 
             #[cfg(feature = "gamelift")]
             crate::options::OrchestrationType::GameLift => {
-                Ok(Self::GameLift(gamelift::new_api().await?))
+                Ok(Self::GameLift(gamelift::new_api(drain).await?))
             }
         }
     }
 
+    // can we go back to placement after a session ends
+    // gamelift expects the process to end with its session
     #[inline]
-    pub fn shutdown_empty(&self) -> bool {
+    pub fn is_reusable(&self) -> bool {
         match self {
-            Self::Local => false,
+            Self::Local => true,
 
             #[cfg(feature = "agones")]
             Self::Agones(_) => true,
 
             #[cfg(feature = "gamelift")]
-            Self::GameLift(_) => true,
+            Self::GameLift(_) => false,
         }
     }
 
@@ -88,14 +107,26 @@ impl Orchestration {
             }
 
             #[cfg(feature = "gamelift")]
-            Self::GameLift(api) => {
-                gamelift::ready(api.clone(), port, log_paths.clone()).await?;
+            Self::GameLift(gamelift) => {
+                gamelift::ready(gamelift.clone(), port, log_paths.clone()).await?;
             }
         }
 
         Ok(())
     }
 
+    pub async fn public_endpoint(&self) -> anyhow::Result<Option<PublicEndpoint>> {
+        match self {
+            Self::Local => Ok(None),
+
+            #[cfg(feature = "agones")]
+            Self::Agones(sdk) => agones::public_endpoint(sdk.clone()).await,
+
+            #[cfg(feature = "gamelift")]
+            Self::GameLift(gamelift) => gamelift::public_endpoint(gamelift.clone()).await,
+        }
+    }
+
     pub fn start_watcher(&self, runtime: &TokioTasksRuntime) {
         match self {
             #[cfg(feature = "agones")]
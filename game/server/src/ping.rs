@@ -0,0 +1,139 @@
+use bevy::{prelude::*, time::common_conditions::on_timer};
+use bevy_replicon::prelude::*;
+
+use game_common::{
+    network::{
+        InputUpdateEvent, PingEvent, PongEvent, ServerDisconnectEvent, TimeSyncRequestEvent,
+        TimeSyncResponseEvent,
+    },
+    server::ActivePlayer,
+    InputValidationSet, SimulationTick,
+};
+
+use crate::{options::Options, server, AppState};
+
+const TIMEOUT_REASON: &str = "Timed out";
+
+// client is already being disconnected
+#[derive(Debug, Component)]
+struct TimedOut;
+
+#[derive(Debug)]
+pub struct PingPlugin;
+
+impl Plugin for PingPlugin {
+    fn build(&self, app: &mut App) {
+        let ping_interval = app.world().resource::<Options>().ping_interval();
+
+        app.add_systems(
+            PreUpdate,
+            (
+                handle_pongs,
+                handle_time_sync_requests,
+                handle_client_activity,
+            )
+                .after(InputValidationSet)
+                .run_if(in_state(AppState::InGame))
+                .run_if(server_running),
+        )
+        .add_systems(
+            Update,
+            (
+                send_pings.run_if(on_timer(ping_interval)),
+                check_client_timeouts,
+            )
+                .run_if(in_state(AppState::InGame))
+                .run_if(server_running),
+        );
+    }
+}
+
+fn send_pings(
+    mut active_players: Query<&mut ActivePlayer>,
+    mut evw_ping: EventWriter<ToClients<PingEvent>>,
+) {
+    for mut active_player in &mut active_players {
+        let sequence = active_player.start_ping();
+        evw_ping.send(ToClients {
+            mode: SendMode::Direct(active_player.client_id),
+            event: PingEvent(sequence),
+        });
+    }
+}
+
+fn handle_pongs(
+    mut evr_pong: EventReader<FromClient<PongEvent>>,
+    mut active_players: Query<&mut ActivePlayer>,
+) {
+    for FromClient { client_id, event } in evr_pong.read() {
+        if let Some(mut active_player) = active_players
+            .iter_mut()
+            .find(|active_player| active_player.client_id == *client_id)
+        {
+            active_player.pong(event.0);
+        }
+    }
+}
+
+fn handle_time_sync_requests(
+    time: Res<Time<Real>>,
+    fixed_time: Res<Time<Fixed>>,
+    tick: Res<SimulationTick>,
+    mut evr_request: EventReader<FromClient<TimeSyncRequestEvent>>,
+    mut evw_response: EventWriter<ToClients<TimeSyncResponseEvent>>,
+) {
+    for FromClient { client_id, event } in evr_request.read() {
+        evw_response.send(ToClients {
+            mode: SendMode::Direct(*client_id),
+            event: TimeSyncResponseEvent {
+                client_time: event.client_time,
+                server_time: time.elapsed_secs_f64(),
+                tick: tick.0,
+                tick_secs: fixed_time.timestep().as_secs_f64(),
+            },
+        });
+    }
+}
+
+// any input counts as the client still being there
+fn handle_client_activity(
+    mut evr_input_update: EventReader<FromClient<InputUpdateEvent>>,
+    mut active_players: Query<&mut ActivePlayer>,
+) {
+    for client_id in evr_input_update.read().map(|evt| evt.client_id) {
+        if let Some(mut active_player) = active_players
+            .iter_mut()
+            .find(|active_player| active_player.client_id == client_id)
+        {
+            active_player.seen();
+        }
+    }
+}
+
+fn check_client_timeouts(
+    mut commands: Commands,
+    options: Res<Options>,
+    active_players: Query<(Entity, &ActivePlayer), Without<TimedOut>>,
+    mut evw_disconnect: EventWriter<ToClients<ServerDisconnectEvent>>,
+) {
+    for (entity, active_player) in &active_players {
+        if !active_player.is_unresponsive(options.client_timeout()) {
+            continue;
+        }
+
+        warn!(
+            "player {} ({:?}) hasn't been heard from in {:?}, disconnecting",
+            active_player.user_id,
+            active_player.client_id,
+            options.client_timeout()
+        );
+
+        server::disconnect_with_reason(
+            &mut commands,
+            &mut evw_disconnect,
+            active_player.client_id,
+            TIMEOUT_REASON,
+        );
+        commands.entity(entity).insert(TimedOut);
+    }
+}
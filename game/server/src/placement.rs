@@ -7,7 +7,7 @@ use crate::{
     is_not_headless,
     options::Options,
     orchestration::{start_watcher, Orchestration, StartWatcherEvent},
-    server::HeartbeatEvent,
+    server::{HeartbeatEvent, ServerSocket},
     tasks, AppState,
 };
 
@@ -38,6 +38,7 @@ impl Plugin for PlacementPlugin {
 
 fn enter(
     options: Res<Options>,
+    server_socket: Res<ServerSocket>,
     orchestration: Res<Orchestration>,
     runtime: Res<TokioTasksRuntime>,
 ) {
@@ -46,7 +47,7 @@ fn enter(
     tasks::spawn_task(
         &runtime,
         {
-            let port = options.port;
+            let port = server_socket.addr().port();
             let log_paths = options.log_paths.clone();
             let orchestration = orchestration.clone();
             move || async move { orchestration.ready(port, log_paths).await }
@@ -1,7 +1,7 @@
-use std::net::UdpSocket;
-use std::time::Duration;
+use std::io;
+use std::net::{IpAddr, SocketAddr, UdpSocket};
 
-use bevy::{prelude::*, time::common_conditions::on_timer};
+use bevy::{ecs::system::RunSystemOnce, prelude::*, time::common_conditions::on_timer};
 use bevy_mod_reqwest::*;
 use bevy_mod_websocket::*;
 use bevy_replicon::prelude::*;
@@ -12,9 +12,11 @@ use bevy_replicon_renet::{
 };
 use bevy_tokio_tasks::TokioTasksRuntime;
 
+use common::{gameserver::GameServerState, netcode};
 use game_common::{
-    network::{ConnectEvent, InputUpdateEvent, PlayerJumpEvent},
-    player,
+    cleanup_state,
+    network::{ConnectEvent, ServerDisconnectEvent},
+    player::{self, InputBufferMetrics},
     server::{ActivePlayer, GameServerInfo, GameSessionInfo, PendingPlayer},
     spawn::SpawnPoint,
     utils::current_timestamp,
@@ -22,42 +24,140 @@ use game_common::{
 };
 
 use crate::{
-    api, game, notifs, options::Options, orchestration::Orchestration, placement, tasks, AppState,
+    advertise,
+    afk::AfkPlugin,
+    api,
+    drain::{DrainPlugin, DrainSender, Draining},
+    game, notifs,
+    options::{Options, PortRange},
+    orchestration::{Orchestration, PublicEndpoint},
+    ping::PingPlugin,
+    placement, tasks,
+    tick::{TickMetrics, TickPlugin},
+    validation::{BannedPlayers, ValidationPlugin},
+    AppState,
 };
 
-const HEARTBEAT_FREQUENCY: Duration = Duration::from_secs(5);
+#[derive(Debug, Default, Event)]
+pub struct HeartbeatEvent {
+    // id of the request the heartbeat acknowledges
+    pub request_id: Option<String>,
+}
+
+impl HeartbeatEvent {
+    pub fn for_request(request_id: Option<String>) -> Self {
+        Self { request_id }
+    }
+}
+
+// id of the placement request that's being initialized
+// so the backend can tie the InitServer heartbeat back to it
+#[derive(Debug, Resource)]
+pub struct PlacementRequestId(pub String);
+
+// game socket, bound at startup so the orchestrator
+// and backend are always told the port we actually got
+#[derive(Debug, Resource)]
+pub struct ServerSocket {
+    socket: UdpSocket,
+    addr: SocketAddr,
+}
+
+impl ServerSocket {
+    fn bind(options: &Options) -> anyhow::Result<Self> {
+        let host = options.host.parse::<IpAddr>()?;
+
+        let socket = match options.port_range {
+            Some(port_range) => Self::bind_range(host, port_range)?,
+            None => UdpSocket::bind(SocketAddr::new(host, options.port))?,
+        };
+        let addr = socket.local_addr()?;
 
+        Ok(Self { socket, addr })
+    }
+
+    fn bind_range(host: IpAddr, port_range: PortRange) -> anyhow::Result<UdpSocket> {
+        for port in port_range.iter() {
+            match UdpSocket::bind(SocketAddr::new(host, port)) {
+                Ok(socket) => return Ok(socket),
+                Err(err) if err.kind() == io::ErrorKind::AddrInUse => continue,
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        anyhow::bail!("no free ports in range {}", port_range)
+    }
+
+    #[inline]
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+// number of sessions this process has hosted
+#[derive(Debug, Default, Resource)]
+pub struct SessionCount(pub u32);
+
+// client that was sent a disconnect reason and gets disconnected next frame
+// so the reason goes out first
+#[derive(Debug, Component)]
+struct PendingDisconnect(ClientId);
+
+// tears down the current session, then either goes back to placement
+// or shuts down if we shouldn't host any more sessions
 #[derive(Debug, Default, Event)]
-pub struct HeartbeatEvent;
+pub struct EndSessionEvent;
 
 #[derive(Debug)]
 pub struct ServerPlugin;
 
 impl Plugin for ServerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins((placement::PlacementPlugin, game::GamePlugin))
-            .add_event::<HeartbeatEvent>()
-            .add_systems(Startup, setup)
-            .add_systems(
-                PreUpdate,
-                (handle_connect, validate_input_update, validate_jump_event)
-                    .after(ServerSet::Receive)
-                    .run_if(in_state(AppState::InGame))
-                    .run_if(server_running),
-            )
-            .add_systems(
-                Update,
-                (
-                    handle_network_events.run_if(in_state(GameState::InGame)),
-                    handle_timeouts.run_if(in_state(GameState::InGame)),
-                    heartbeat_monitor.run_if(on_timer(HEARTBEAT_FREQUENCY)),
-                    handle_heartbeat_events,
-                ),
-            )
-            .add_systems(OnEnter(AppState::InitServer), init_server)
-            .add_systems(OnEnter(AppState::InGame), enter)
-            .add_systems(OnExit(AppState::InGame), exit)
-            .add_systems(OnEnter(AppState::Shutdown), shutdown);
+        let heartbeat_interval = app.world().resource::<Options>().heartbeat_interval();
+
+        app.add_plugins((
+            placement::PlacementPlugin,
+            game::GamePlugin,
+            DrainPlugin,
+            PingPlugin,
+            AfkPlugin,
+            ValidationPlugin,
+            TickPlugin,
+        ))
+        .add_event::<HeartbeatEvent>()
+        .add_event::<EndSessionEvent>()
+        .init_resource::<SessionCount>()
+        .add_systems(Startup, setup)
+        .add_systems(
+            PreUpdate,
+            (handle_connect, handle_pending_disconnects)
+                .after(ServerSet::Receive)
+                .run_if(in_state(AppState::InGame))
+                .run_if(server_running),
+        )
+        .add_systems(
+            Update,
+            (
+                (handle_network_events, handle_timeouts)
+                    .run_if(in_state(GameState::InGame))
+                    .run_if(in_state(AppState::InGame)),
+                handle_end_session.run_if(in_state(AppState::InGame)),
+                heartbeat_monitor.run_if(on_timer(heartbeat_interval)),
+                handle_heartbeat_events,
+            ),
+        )
+        .add_systems(OnEnter(AppState::InitServer), init_server)
+        .add_systems(OnEnter(AppState::InGame), enter)
+        .add_systems(
+            OnExit(AppState::InGame),
+            (
+                exit,
+                cleanup_state::<PendingPlayer>,
+                cleanup_state::<ActivePlayer>,
+                cleanup_state::<PendingDisconnect>,
+            ),
+        )
+        .add_systems(OnEnter(AppState::Shutdown), shutdown);
     }
 }
 
@@ -66,22 +166,38 @@ fn setup(
     options: Res<Options>,
     mut ws_client: WebSocketClient,
     runtime: Res<TokioTasksRuntime>,
+    drain: Res<DrainSender>,
     mut evw_heartbeat: EventWriter<HeartbeatEvent>,
+    mut exit: EventWriter<AppExit>,
 ) {
     let server_info = GameServerInfo::new();
-    info!("starting server {}", server_info.server_id);
+    let server_id = server_info.server_id;
+    info!("starting server {}", server_id);
+
+    commands.insert_resource(server_info);
+
+    // nothing else has started yet, so just exit
+    let server_socket = match ServerSocket::bind(&options) {
+        Ok(server_socket) => server_socket,
+        Err(err) => {
+            error!("failed to bind game socket: {}", err);
+            exit.send(AppExit::from_code(1));
+            return;
+        }
+    };
+    info!("bound game socket at {}", server_socket.addr());
+    commands.insert_resource(server_socket);
 
     // let the backend know we're starting up
     evw_heartbeat.send_default();
 
-    notifs::subscribe(&mut ws_client, server_info.server_id);
-
-    commands.insert_resource(server_info);
+    notifs::subscribe(&mut ws_client, &options.notifs_url, server_id);
 
     let orchestration_type = options.orchestration;
+    let drain = drain.clone();
     tasks::spawn_task(
         &runtime,
-        move || async move { Orchestration::new(orchestration_type).await },
+        move || async move { Orchestration::new(orchestration_type, drain).await },
         |ctx, output| {
             ctx.world.insert_resource(output);
 
@@ -112,24 +228,100 @@ fn shutdown(orchestration: Res<Orchestration>, runtime: Res<TokioTasksRuntime>)
 }
 
 fn enter(
+    mut session_count: ResMut<SessionCount>,
     mut game_state: ResMut<NextState<GameState>>,
     mut evw_heartbeat: EventWriter<HeartbeatEvent>,
 ) {
     info!("entering server app game ...");
 
+    session_count.0 += 1;
+
     evw_heartbeat.send_default();
 
     game_state.set(GameState::LoadAssets);
 }
 
-fn exit(mut commands: Commands) {
+fn exit(
+    mut commands: Commands,
+    server: Option<ResMut<RenetServer>>,
+    transport: Option<ResMut<NetcodeServerTransport>>,
+) {
     info!("exiting server app game ...");
 
+    // let any remaining clients know we're going away
+    if let (Some(mut server), Some(mut transport)) = (server, transport) {
+        transport.disconnect_all(&mut server);
+    }
+
     commands.remove_resource::<GameSessionInfo>();
     commands.remove_resource::<RenetServer>();
     commands.remove_resource::<NetcodeServerTransport>();
 }
 
+pub fn disconnect_with_reason(
+    commands: &mut Commands,
+    evw_disconnect: &mut EventWriter<ToClients<ServerDisconnectEvent>>,
+    client_id: ClientId,
+    reason: impl Into<String>,
+) {
+    evw_disconnect.send(ToClients {
+        mode: SendMode::Direct(client_id),
+        event: ServerDisconnectEvent {
+            reason: reason.into(),
+        },
+    });
+
+    commands.spawn(PendingDisconnect(client_id));
+}
+
+fn handle_pending_disconnects(
+    mut commands: Commands,
+    mut server: ResMut<RenetServer>,
+    pending_disconnects: Query<(Entity, &PendingDisconnect)>,
+) {
+    for (entity, pending_disconnect) in &pending_disconnects {
+        // disconnect handling will clean up the player
+        server.disconnect(pending_disconnect.0.get());
+        commands.entity(entity).despawn();
+    }
+}
+
+fn handle_end_session(
+    options: Res<Options>,
+    orchestration: Res<Orchestration>,
+    draining: Option<Res<Draining>>,
+    session_count: Res<SessionCount>,
+    mut evr_end_session: EventReader<EndSessionEvent>,
+    mut app_state: ResMut<NextState<AppState>>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if evr_end_session.is_empty() {
+        return;
+    }
+    evr_end_session.clear();
+
+    // despawn the world alongside the app state change
+    // so it's gone before anything for the next state is spawned
+    game_state.set(GameState::WaitingForApp);
+
+    if draining.is_some() {
+        info!("drained, shutting down");
+        app_state.set(AppState::Shutdown);
+    } else if !orchestration.is_reusable() {
+        info!("session ended, shutting down");
+        app_state.set(AppState::Shutdown);
+    } else if options.max_sessions > 0 && session_count.0 >= options.max_sessions {
+        info!(
+            "hosted {} sessions, shutting down to recycle",
+            session_count.0
+        );
+        app_state.set(AppState::Shutdown);
+    } else {
+        info!("session ended, returning to placement");
+        app_state.set(AppState::WaitForPlacement);
+    }
+}
+
 fn heartbeat_monitor(
     orchestration: Res<Orchestration>,
     state: Res<State<AppState>>,
@@ -155,25 +347,48 @@ fn heartbeat_monitor(
 #[allow(clippy::too_many_arguments)]
 fn handle_heartbeat_events(
     mut client: BevyReqwest,
+    options: Res<Options>,
     orchestration: Option<Res<Orchestration>>,
     server_info: Res<GameServerInfo>,
     session_info: Option<Res<GameSessionInfo>>,
     state: Res<State<AppState>>,
+    draining: Option<Res<Draining>>,
     pending_players: Query<&PendingPlayer>,
     active_players: Query<&ActivePlayer>,
+    input_metrics: Res<InputBufferMetrics>,
+    tick_metrics: Res<TickMetrics>,
     mut evr_heartbeat: EventReader<HeartbeatEvent>,
 ) {
     if let Some(orchestration) = orchestration {
         if !evr_heartbeat.is_empty() {
+            // one heartbeat covers every event this frame
+            let request_id = evr_heartbeat
+                .read()
+                .filter_map(|evt| evt.request_id.clone())
+                .last();
+
+            // keeps the backend from sending us anything new
+            let state = match **state {
+                AppState::InitServer | AppState::InGame if draining.is_some() => {
+                    GameServerState::Draining
+                }
+                state => state.into(),
+            };
+
             api::heartbeat(
                 &mut client,
+                &options.api_url,
                 server_info.server_id,
                 server_info.connection_info.clone(),
-                (**state).into(),
+                state,
                 orchestration.as_api_type(),
+                options.region.clone(),
                 session_info.as_deref(),
                 pending_players.iter(),
                 active_players.iter(),
+                &input_metrics,
+                &tick_metrics,
+                request_id,
             )
             .unwrap();
         }
@@ -182,46 +397,115 @@ fn handle_heartbeat_events(
     evr_heartbeat.clear();
 }
 
-#[allow(clippy::too_many_arguments)]
 fn init_server(
+    mut commands: Commands,
+    orchestration: Res<Orchestration>,
+    runtime: Res<TokioTasksRuntime>,
+    placement_request_id: Option<Res<PlacementRequestId>>,
+    mut evw_heartbeat: EventWriter<HeartbeatEvent>,
+) {
+    info!("init network ...");
+
+    // let the backend know we're initializing the game
+    evw_heartbeat.send(HeartbeatEvent::for_request(
+        placement_request_id.map(|request_id| request_id.0.clone()),
+    ));
+    commands.remove_resource::<PlacementRequestId>();
+
+    // the orchestrator may know our public endpoint better than we do
+    let orchestration = orchestration.clone();
+    tasks::spawn_task(
+        &runtime,
+        move || async move { orchestration.public_endpoint().await },
+        |ctx, public_endpoint| {
+            run_start_network(ctx.world, public_endpoint);
+        },
+        |ctx, err| {
+            warn!("failed to get orchestration public endpoint: {}", err);
+            run_start_network(ctx.world, None);
+        },
+    );
+}
+
+fn run_start_network(world: &mut World, public_endpoint: Option<PublicEndpoint>) {
+    if let Err(err) = world.run_system_once_with(public_endpoint, start_network) {
+        error!("failed to start network: {}", err);
+        world
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::Shutdown);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn start_network(
+    In(public_endpoint): In<Option<PublicEndpoint>>,
     mut commands: Commands,
     options: Res<Options>,
     channels: Res<RepliconChannels>,
+    server_socket: Res<ServerSocket>,
     mut server_info: ResMut<GameServerInfo>,
     session_info: Res<GameSessionInfo>,
     mut app_state: ResMut<NextState<AppState>>,
-    mut evw_heartbeat: EventWriter<HeartbeatEvent>,
 ) {
-    info!("init network ...");
+    let server_addr = server_socket.addr();
+    let socket = match server_socket.socket.try_clone() {
+        Ok(socket) => socket,
+        Err(err) => {
+            error!("failed to use game socket: {}", err);
+            app_state.set(AppState::Shutdown);
+            return;
+        }
+    };
 
-    // let the backend know we're initializing the game
-    evw_heartbeat.send_default();
+    // connect tokens are only valid for the addresses we advertise
+    let (addrs, port) =
+        match advertise::advertised_endpoint(&options, public_endpoint.as_ref(), server_addr) {
+            Ok(endpoint) => endpoint,
+            Err(err) => {
+                error!("failed to get advertised endpoint: {}", err);
+                app_state.set(AppState::Shutdown);
+                return;
+            }
+        };
+    server_info.connection_info.set(addrs, port);
+
+    let private_key = match options.connect_token_key() {
+        Ok(private_key) => private_key,
+        Err(err) => {
+            error!("invalid connect token key: {}", err);
+            app_state.set(AppState::Shutdown);
+            return;
+        }
+    };
 
-    let server_addr = options.address().parse().unwrap();
-    let socket = UdpSocket::bind(server_addr).unwrap();
     let current_time = current_timestamp();
     let server_config = ServerConfig {
         current_time,
         max_clients: session_info.max_players as usize,
         protocol_id: PROTOCOL_ID,
-        public_addresses: vec![server_addr],
-        authentication: ServerAuthentication::Unsecure,
+        public_addresses: server_info.connection_info.addresses(),
+        authentication: ServerAuthentication::Secure { private_key },
     };
 
     info!("listening at {} ...", server_addr);
 
+    let transport = match NetcodeServerTransport::new(server_config, socket) {
+        Ok(transport) => transport,
+        Err(err) => {
+            error!("failed to create server transport: {}", err);
+            app_state.set(AppState::Shutdown);
+            return;
+        }
+    };
+
     let server = RenetServer::new(ConnectionConfig {
         server_channels_config: channels.get_server_configs(),
         client_channels_config: channels.get_client_configs(),
         ..Default::default()
     });
     commands.insert_resource(server);
-
-    let transport = NetcodeServerTransport::new(server_config, socket).unwrap();
     commands.insert_resource(transport);
 
-    server_info.connection_info.update(server_addr);
-
     app_state.set(AppState::InGame);
 }
 
@@ -267,10 +551,9 @@ fn handle_network_events(
 fn handle_timeouts(
     mut commands: Commands,
     time: Res<Time>,
-    orchestration: Res<Orchestration>,
     mut session_info: ResMut<GameSessionInfo>,
     mut pending_players: Query<(Entity, &mut PendingPlayer)>,
-    mut exit: EventWriter<AppExit>,
+    mut evw_end_session: EventWriter<EndSessionEvent>,
 ) {
     for (entity, mut pending_player) in &mut pending_players {
         if pending_player.is_timeout(time.delta()) {
@@ -278,9 +561,9 @@ fn handle_timeouts(
         }
     }
 
-    if orchestration.shutdown_empty() && session_info.update_shutdown_timer(time.delta()) {
-        info!("session timeout, exiting");
-        exit.send(AppExit::Success);
+    if session_info.update_shutdown_timer(time.delta()) {
+        info!("session timeout, ending session");
+        evw_end_session.send_default();
     }
 }
 
@@ -290,13 +573,37 @@ fn handle_connect(
     mut evr_connect: EventReader<FromClient<ConnectEvent>>,
     assets: Option<Res<GameAssetState>>,
     mut server: ResMut<RenetServer>,
+    transport: Res<NetcodeServerTransport>,
+    banned: Res<BannedPlayers>,
     mut session_info: ResMut<GameSessionInfo>,
     pending_players: Query<(Entity, &PendingPlayer)>,
     spawnpoints: Query<&GlobalTransform, With<SpawnPoint>>,
     mut evw_heartbeat: EventWriter<HeartbeatEvent>,
 ) {
     for FromClient { client_id, event } in evr_connect.read() {
-        let user_id = event.0;
+        // the connect token is signed by the api, so trust it over the event
+        let Some(user_data) = transport.user_data(client_id.get()) else {
+            warn!("client {:?} has no connect token user data", client_id);
+            server.disconnect(client_id.get());
+            continue;
+        };
+        let user_id = netcode::decode_user_data(&user_data);
+
+        if event.0 != user_id {
+            warn!(
+                "client {:?} claimed to be {} but token is for {}",
+                client_id, event.0, user_id
+            );
+            server.disconnect(client_id.get());
+            continue;
+        }
+
+        if banned.is_banned(user_id) {
+            warn!("banned player {} tried to connect", user_id);
+            server.disconnect(client_id.get());
+            continue;
+        }
+
         info!("player {} connected", user_id);
 
         if !session_info.client_connected(
@@ -322,43 +629,3 @@ fn handle_connect(
         );
     }
 }
-
-fn validate_input_update(
-    mut evr_input_update: EventReader<FromClient<InputUpdateEvent>>,
-    session_info: Res<GameSessionInfo>,
-    mut server: ResMut<RenetServer>,
-) {
-    for FromClient {
-        client_id,
-        event: _,
-    } in evr_input_update.read()
-    {
-        if !session_info.has_client(client_id) {
-            warn!("client {:?} not in session", client_id);
-            server.disconnect(client_id.get());
-            continue;
-        }
-
-        // shared game handles the event
-    }
-}
-
-fn validate_jump_event(
-    mut evr_jump: EventReader<FromClient<PlayerJumpEvent>>,
-    session_info: Res<GameSessionInfo>,
-    mut server: ResMut<RenetServer>,
-) {
-    for FromClient {
-        client_id,
-        event: _,
-    } in evr_jump.read()
-    {
-        if !session_info.has_client(client_id) {
-            warn!("client {:?} not in session", client_id);
-            server.disconnect(client_id.get());
-            continue;
-        }
-
-        // shared game handles the event
-    }
-}
@@ -0,0 +1,102 @@
+use bevy::prelude::*;
+
+use game_common::GameState;
+
+use crate::AppState;
+
+// how quickly the normal frame time follows the frame rate
+const FRAME_SMOOTHING: f64 = 0.05;
+
+// ticks the simulation had to run back to back to catch up with real time this session
+#[derive(Debug, Default, Resource)]
+pub struct TickMetrics {
+    pub overruns: u32,
+
+    // ticks run so far this frame
+    frame_ticks: u32,
+
+    // how long a frame normally takes, in seconds
+    frame_secs: f64,
+
+    behind: bool,
+}
+
+#[derive(Debug)]
+pub struct TickPlugin;
+
+impl Plugin for TickPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TickMetrics>()
+            .add_systems(
+                FixedFirst,
+                count_ticks
+                    .run_if(in_state(GameState::InGame))
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(
+                Last,
+                check_overruns
+                    .run_if(in_state(GameState::InGame))
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(OnEnter(AppState::InGame), enter);
+    }
+}
+
+fn enter(mut commands: Commands) {
+    commands.insert_resource(TickMetrics::default());
+}
+
+fn count_ticks(mut metrics: ResMut<TickMetrics>) {
+    metrics.frame_ticks += 1;
+}
+
+// a frame normally runs as many ticks as fit in it (one headless, maybe more at a low refresh rate)
+// so anything a long frame runs past that is the simulation catching up after falling behind
+// (a little jitter can also run an extra tick in a frame, that isn't falling behind)
+fn check_overruns(
+    time: Res<Time<Real>>,
+    fixed_time: Res<Time<Fixed>>,
+    mut metrics: ResMut<TickMetrics>,
+) {
+    let frame_ticks = std::mem::take(&mut metrics.frame_ticks);
+
+    let timestep = fixed_time.timestep().as_secs_f64();
+    let delta = time.delta_secs_f64();
+    if timestep <= 0.0 || delta <= 0.0 {
+        return;
+    }
+
+    if metrics.frame_secs <= 0.0 {
+        metrics.frame_secs = delta;
+    }
+    let frame_secs = metrics.frame_secs.max(timestep);
+
+    let expected_ticks = ((frame_secs / timestep).round() as u32).max(1);
+    let overrun = frame_ticks.saturating_sub(expected_ticks);
+
+    if delta > frame_secs * 1.5 && overrun > 0 {
+        metrics.overruns += overrun;
+
+        if !metrics.behind {
+            warn!(
+                "simulation fell behind, ran {} extra ticks after a {:.2}ms frame",
+                overrun,
+                delta * 1000.0
+            );
+            metrics.behind = true;
+        }
+        return;
+    }
+
+    // long frames are left out so they don't become normal
+    metrics.frame_secs += (delta - metrics.frame_secs) * FRAME_SMOOTHING;
+
+    if metrics.behind {
+        info!(
+            "simulation caught up, {} overruns this session",
+            metrics.overruns
+        );
+        metrics.behind = false;
+    }
+}
@@ -0,0 +1,323 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::{prelude::*, utils::Duration};
+use bevy_replicon::prelude::*;
+use bevy_replicon_renet::renet::RenetServer;
+
+use common::user::UserId;
+use game_common::{
+    network::{InputUpdateEvent, ServerDisconnectEvent, INPUT_REDUNDANCY},
+    server::{ActivePlayer, GameSessionInfo},
+    InputState, InputValidationSet,
+};
+
+use crate::{options::Options, server, AppState};
+
+const KICK_REASON: &str = "Kicked for invalid input";
+const BAN_REASON: &str = "Banned for invalid input";
+
+// the client clamps move before sending, this just covers float error
+const MOVE_TOLERANCE: f32 = 0.01;
+
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Violation {
+    NonFiniteInput,
+    TooManyInputs,
+    MoveMagnitude,
+    LookDelta,
+    InputRate,
+    JumpRate,
+}
+
+#[derive(Debug, Event)]
+struct InputViolationEvent {
+    client_id: ClientId,
+    user_id: UserId,
+    violation: Violation,
+}
+
+// per client event counts for the current rate limit window
+#[derive(Debug, Component)]
+struct InputLimits {
+    window: Timer,
+
+    input_count: u32,
+    jump_count: u32,
+
+    // newest input that's been validated, updates resend a few old inputs along with
+    // the new one and those were already checked (and counted) the first time through
+    last_sequence: Option<u32>,
+}
+
+impl Default for InputLimits {
+    fn default() -> Self {
+        Self {
+            window: Timer::new(RATE_LIMIT_WINDOW, TimerMode::Repeating),
+            input_count: 0,
+            jump_count: 0,
+            last_sequence: None,
+        }
+    }
+}
+
+// player is already being kicked, drop anything else they send
+#[derive(Debug, Component)]
+struct Kicked;
+
+// violations this session, kept across reconnects
+#[derive(Debug, Default, Resource)]
+struct InputViolations(HashMap<UserId, u32>);
+
+// TODO: bans only last as long as this server until the backend has somewhere to keep them
+#[derive(Debug, Default, Resource)]
+pub struct BannedPlayers(HashSet<UserId>);
+
+impl BannedPlayers {
+    #[inline]
+    pub fn is_banned(&self, user_id: UserId) -> bool {
+        self.0.contains(&user_id)
+    }
+}
+
+#[derive(Debug)]
+pub struct ValidationPlugin;
+
+impl Plugin for ValidationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<InputViolationEvent>()
+            .init_resource::<InputViolations>()
+            .init_resource::<BannedPlayers>()
+            .add_systems(
+                PreUpdate,
+                (
+                    (track_clients, update_limits),
+                    validate_input_update,
+                    handle_violations,
+                )
+                    .chain()
+                    .in_set(InputValidationSet)
+                    .run_if(in_state(AppState::InGame))
+                    .run_if(server_running),
+            )
+            .add_systems(OnExit(AppState::InGame), reset_violations);
+    }
+}
+
+// sanitizes the update in place, non-finite input can't be salvaged
+fn sanitize_input_update(
+    event: &mut InputUpdateEvent,
+    max_look_delta: f32,
+    last_sequence: Option<u32>,
+) -> Result<Option<Violation>, Violation> {
+    let mut violation = None;
+
+    // keep the newest, those are the ones that haven't been seen yet
+    if event.inputs.len() > INPUT_REDUNDANCY {
+        event.inputs.drain(..event.inputs.len() - INPUT_REDUNDANCY);
+        violation = Some(Violation::TooManyInputs);
+    }
+
+    // already validated, and the input buffer would ignore them anyway
+    if let Some(last_sequence) = last_sequence {
+        event.inputs.retain(|input| input.sequence > last_sequence);
+    }
+
+    for input in &mut event.inputs {
+        if let Some(input_violation) = sanitize_input(&mut input.input_state, max_look_delta)? {
+            violation = Some(input_violation);
+        }
+    }
+
+    Ok(violation)
+}
+
+fn sanitize_input(
+    input_state: &mut InputState,
+    max_look_delta: f32,
+) -> Result<Option<Violation>, Violation> {
+    if !input_state.r#move.is_finite() || !input_state.look.is_finite() {
+        return Err(Violation::NonFiniteInput);
+    }
+
+    let mut violation = None;
+
+    if input_state.r#move.length() > 1.0 + MOVE_TOLERANCE {
+        input_state.r#move = input_state.r#move.clamp_length_max(1.0);
+        violation = Some(Violation::MoveMagnitude);
+    }
+
+    let look = input_state
+        .look
+        .clamp(Vec2::splat(-max_look_delta), Vec2::splat(max_look_delta));
+    if look != input_state.look {
+        input_state.look = look;
+        violation = Some(Violation::LookDelta);
+    }
+
+    Ok(violation)
+}
+
+fn track_clients(
+    mut commands: Commands,
+    active_players: Query<Entity, (With<ActivePlayer>, Without<InputLimits>)>,
+) {
+    for entity in &active_players {
+        commands.entity(entity).insert(InputLimits::default());
+    }
+}
+
+fn update_limits(time: Res<Time>, mut limits: Query<&mut InputLimits>) {
+    for mut limits in &mut limits {
+        if limits.window.tick(time.delta()).just_finished() {
+            limits.input_count = 0;
+            limits.jump_count = 0;
+        }
+    }
+}
+
+// replaces this frame's input events with the sanitized ones
+// so the shared game only ever applies validated input
+#[allow(clippy::too_many_arguments)]
+fn validate_input_update(
+    options: Res<Options>,
+    session_info: Res<GameSessionInfo>,
+    mut server: ResMut<RenetServer>,
+    mut events: ResMut<Events<FromClient<InputUpdateEvent>>>,
+    mut active_players: Query<(&ActivePlayer, Option<&mut InputLimits>, Has<Kicked>)>,
+    mut evw_violation: EventWriter<InputViolationEvent>,
+) {
+    let received = events
+        .iter_current_update_events()
+        .map(|evt| (evt.client_id, evt.event.clone()))
+        .collect::<Vec<_>>();
+    events.clear();
+
+    for (client_id, mut event) in received {
+        if !session_info.has_client(&client_id) {
+            warn!("client {:?} not in session", client_id);
+            server.disconnect(client_id.get());
+            continue;
+        }
+
+        let Some((active_player, mut limits, kicked)) = active_players
+            .iter_mut()
+            .find(|(active_player, _, _)| active_player.client_id == client_id)
+        else {
+            continue;
+        };
+
+        if kicked {
+            continue;
+        }
+
+        let mut send_violation = |violation| {
+            evw_violation.send(InputViolationEvent {
+                client_id,
+                user_id: active_player.user_id,
+                violation,
+            });
+        };
+
+        // drop anything over the limit, but only count it once per window
+        let mut last_sequence = None;
+        if let Some(limits) = limits.as_mut() {
+            limits.input_count += 1;
+            if limits.input_count > options.max_input_rate() {
+                if limits.input_count == options.max_input_rate() + 1 {
+                    send_violation(Violation::InputRate);
+                }
+                continue;
+            }
+
+            last_sequence = limits.last_sequence;
+            limits.last_sequence = event
+                .inputs
+                .iter()
+                .map(|input| input.sequence)
+                .max()
+                .max(last_sequence);
+        }
+
+        match sanitize_input_update(&mut event, options.max_look_delta, last_sequence) {
+            Ok(violation) => {
+                if let Some(violation) = violation {
+                    send_violation(violation);
+                }
+
+                // jumps over the limit are ignored, the rest of the input is fine
+                if let Some(limits) = limits.as_mut() {
+                    for input in event.inputs.iter_mut().filter(|input| input.jump) {
+                        limits.jump_count += 1;
+                        if limits.jump_count > options.max_jump_rate {
+                            if limits.jump_count == options.max_jump_rate + 1 {
+                                send_violation(Violation::JumpRate);
+                            }
+                            input.jump = false;
+                        }
+                    }
+                }
+
+                events.send(FromClient { client_id, event });
+            }
+            Err(violation) => send_violation(violation),
+        }
+    }
+}
+
+fn handle_violations(
+    mut commands: Commands,
+    options: Res<Options>,
+    mut violations: ResMut<InputViolations>,
+    mut banned: ResMut<BannedPlayers>,
+    active_players: Query<(Entity, &ActivePlayer), Without<Kicked>>,
+    mut evr_violation: EventReader<InputViolationEvent>,
+    mut evw_disconnect: EventWriter<ToClients<ServerDisconnectEvent>>,
+) {
+    // Kicked doesn't land until the end of the frame
+    let mut kicked = HashSet::new();
+
+    for evt in evr_violation.read() {
+        let count = violations.0.entry(evt.user_id).or_default();
+        *count += 1;
+
+        warn!(
+            "player {} ({:?}) input violation {:?} ({} this session)",
+            evt.user_id, evt.client_id, evt.violation, count
+        );
+
+        let reason = if options.input_violation_ban_threshold > 0
+            && *count >= options.input_violation_ban_threshold
+        {
+            warn!("banning player {} for invalid input", evt.user_id);
+            banned.0.insert(evt.user_id);
+            BAN_REASON
+        } else if options.input_violation_kick_threshold > 0
+            && *count >= options.input_violation_kick_threshold
+        {
+            warn!("kicking player {} for invalid input", evt.user_id);
+            KICK_REASON
+        } else {
+            continue;
+        };
+
+        let Some((entity, _)) = active_players
+            .iter()
+            .find(|(_, active_player)| active_player.client_id == evt.client_id)
+        else {
+            continue;
+        };
+
+        if !kicked.insert(evt.client_id) {
+            continue;
+        }
+
+        server::disconnect_with_reason(&mut commands, &mut evw_disconnect, evt.client_id, reason);
+        commands.entity(entity).insert(Kicked);
+    }
+}
+
+fn reset_violations(mut violations: ResMut<InputViolations>) {
+    violations.0.clear();
+}
@@ -0,0 +1,34 @@
+use axum::{async_trait, extract::FromRequestParts, http::request::Parts};
+use subtle::ConstantTimeEq;
+use tracing::warn;
+
+use internal::axum::{extract::BearerAuth, AppError};
+
+use crate::state::AppState;
+
+#[derive(Debug)]
+pub struct AdminUser;
+
+#[async_trait]
+impl FromRequestParts<AppState> for AdminUser {
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let Some(admin_token) = state.options.admin_token.as_ref() else {
+            warn!("admin request with no admin token configured!");
+            return Err(AppError::unauthorized("Admin API disabled"));
+        };
+
+        let bearer = BearerAuth::from_request_parts(parts, state).await?;
+        // constant time so the token can't be guessed a byte at a time
+        if !bool::from(bearer.token().as_bytes().ct_eq(admin_token.as_bytes())) {
+            warn!("invalid admin token!");
+            return Err(AppError::unauthorized("Invalid admin token"));
+        }
+
+        Ok(Self)
+    }
+}
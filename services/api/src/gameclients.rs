@@ -0,0 +1,125 @@
+use std::net::{IpAddr, SocketAddr};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use renetcode::{ConnectToken, NETCODE_KEY_BYTES};
+use tokio::time::Duration;
+use tracing::warn;
+use uuid::Uuid;
+
+use common::{netcode, user::UserId};
+use internal::{
+    gameserver::get_find_server_lock_key,
+    models::gameserver::GameServerInfo,
+    redis::{RedisConnection, RELEASE_LOCK_SCRIPT},
+};
+
+// netcode limit on the number of addresses in a token
+const MAX_SERVER_ADDRESSES: usize = 32;
+
+// released when dropped so a cancelled request doesn't hold it for the whole ttl
+pub struct FindServerLock {
+    conn: RedisConnection,
+    user_id: UserId,
+    lock_id: Uuid,
+}
+
+impl Drop for FindServerLock {
+    fn drop(&mut self) {
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            warn!(
+                "no runtime to release find server lock for {}, it will expire",
+                self.user_id
+            );
+            return;
+        };
+
+        let mut conn = self.conn.clone();
+        let user_id = self.user_id;
+        let lock_id = self.lock_id;
+        runtime.spawn(async move {
+            if let Err(err) = release_find_server_lock(&mut conn, user_id, lock_id).await {
+                warn!(
+                    "failed to release find server lock for {}: {}",
+                    user_id, err
+                );
+            }
+        });
+    }
+}
+
+pub async fn acquire_find_server_lock(
+    conn: &mut RedisConnection,
+    user_id: UserId,
+    ttl: Duration,
+) -> anyhow::Result<Option<FindServerLock>> {
+    let lock_id = Uuid::new_v4();
+
+    let acquired: bool = redis::cmd("SET")
+        .arg(get_find_server_lock_key(user_id))
+        .arg(lock_id.to_string())
+        .arg("NX")
+        .arg("EX")
+        .arg(ttl.as_secs().max(1))
+        .query_async::<Option<String>>(conn)
+        .await?
+        .is_some();
+
+    Ok(acquired.then(|| FindServerLock {
+        conn: conn.clone(),
+        user_id,
+        lock_id,
+    }))
+}
+
+async fn release_find_server_lock(
+    conn: &mut RedisConnection,
+    user_id: UserId,
+    lock_id: Uuid,
+) -> anyhow::Result<()> {
+    let _: i64 = RELEASE_LOCK_SCRIPT
+        .key(get_find_server_lock_key(user_id))
+        .arg(lock_id.to_string())
+        .invoke_async(conn)
+        .await?;
+
+    Ok(())
+}
+
+// mints a netcode connect token for the user to connect to the given server
+// the token is only valid for the server's advertised addresses
+pub fn generate_connect_token(
+    key: &[u8; NETCODE_KEY_BYTES],
+    expire: Duration,
+    timeout_secs: i32,
+    user_id: UserId,
+    server_info: &GameServerInfo,
+) -> anyhow::Result<String> {
+    let server_addresses = server_info
+        .v4addrs
+        .iter()
+        .chain(server_info.v6addrs.iter())
+        .filter_map(|addr| addr.parse::<IpAddr>().ok())
+        .map(|ip| SocketAddr::new(ip, server_info.port))
+        .take(MAX_SERVER_ADDRESSES)
+        .collect::<Vec<_>>();
+    if server_addresses.is_empty() {
+        anyhow::bail!("server {} has no addresses", server_info.server_id);
+    }
+
+    let current_time = SystemTime::now().duration_since(UNIX_EPOCH)?;
+    let token = ConnectToken::generate(
+        current_time,
+        netcode::PROTOCOL_ID,
+        expire.as_secs(),
+        netcode::client_id_for_user(user_id),
+        timeout_secs,
+        server_addresses,
+        Some(&netcode::encode_user_data(user_id)),
+        key,
+    )?;
+
+    let mut buf = Vec::new();
+    token.write(&mut buf)?;
+
+    Ok(netcode::encode_connect_token(&buf))
+}
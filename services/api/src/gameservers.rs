@@ -1,23 +1,28 @@
 use redis::{AsyncCommands, Pipeline};
-use tokio::time::{sleep, timeout, Duration};
+use tokio::time::{sleep, timeout, Duration, Instant};
 use tracing::{info, warn};
 use uuid::Uuid;
 
-use common::{gameserver::*, user::UserId};
+use common::{gameserver::*, user::UserId, GameSettings};
 use internal::{
+    axum::RequestId,
     gameserver::{
         get_gameserver_key, get_gamesession_key, GAMESERVERS_INDEX, GAMESESSIONS_BACKFILL_SET,
         WAITING_GAMESERVERS_INDEX,
     },
+    models,
     notifs::AsNotification,
     redis::RedisConnection,
 };
 
-use crate::{gamesessions, models, notifs, state::AppState};
+use crate::{gamesessions, notifs, state::AppState};
 
-const PLACEMENT_TIMEOUT: Duration = Duration::from_secs(30);
-const RESERVATION_TIMEOUT: Duration = Duration::from_secs(5);
-const SERVER_INFO_TTL: u64 = 10;
+#[derive(Debug)]
+pub enum AllocationResult {
+    Allocated(models::gameserver::GameServerInfo),
+    Unavailable,
+    Timeout,
+}
 
 pub async fn read_gameserver_info(
     conn: &mut RedisConnection,
@@ -30,21 +35,47 @@ pub async fn read_gameserver_info(
     Ok(None)
 }
 
+pub async fn list_gameservers(
+    conn: &mut RedisConnection,
+) -> anyhow::Result<Vec<models::gameserver::GameServerInfo>> {
+    let server_ids: Vec<String> = conn.zrange(GAMESERVERS_INDEX, 0, -1).await?;
+
+    let mut servers = Vec::with_capacity(server_ids.len());
+    for server_id in server_ids {
+        let server_id = Uuid::parse_str(&server_id)?;
+
+        // the index may be stale until the next heartbeat cleans it up
+        if let Some(server_info) = read_gameserver_info(conn, server_id).await? {
+            servers.push(server_info);
+        }
+    }
+
+    Ok(servers)
+}
+
+pub async fn remove_waiting_gameserver(
+    conn: &mut RedisConnection,
+    server_id: Uuid,
+) -> anyhow::Result<()> {
+    let _: () = conn
+        .zrem(WAITING_GAMESERVERS_INDEX, server_id.to_string())
+        .await?;
+
+    Ok(())
+}
+
 pub async fn update_gameserver(
     pipeline: &mut Pipeline,
     gameserver_info: &models::gameserver::GameServerInfo,
+    ttl: u64,
 ) -> anyhow::Result<()> {
     let value = serde_json::to_string(&gameserver_info)?;
 
     let now = chrono::Utc::now().timestamp() as u64;
-    let expiry = now - SERVER_INFO_TTL;
+    let expiry = now - ttl;
 
     // save the server info
-    pipeline.set_ex(
-        get_gameserver_key(gameserver_info.server_id),
-        value,
-        SERVER_INFO_TTL,
-    );
+    pipeline.set_ex(get_gameserver_key(gameserver_info.server_id), value, ttl);
 
     // update the server index
     pipeline.zadd(
@@ -79,7 +110,10 @@ async fn wait_for_reservation(
         // TODO: back off
         sleep(Duration::from_secs(1)).await;
 
-        let game_session_info: String = conn.get(&key).await?;
+        let game_session_info: Option<String> = conn.get(&key).await?;
+        let Some(game_session_info) = game_session_info else {
+            anyhow::bail!("game session {} ended before reservation", game_session_id);
+        };
         let game_session_info: models::gamesession::GameSessionInfo =
             serde_json::from_str(&game_session_info)?;
 
@@ -91,13 +125,14 @@ async fn wait_for_reservation(
 
 pub async fn reserve_backfill_slot(
     app_state: &mut AppState,
+    request_id: &RequestId,
     user_id: UserId,
-) -> anyhow::Result<Option<models::gameserver::GameServerInfo>> {
+) -> anyhow::Result<AllocationResult> {
     let backfill_sessions =
         gamesessions::get_backfill_game_sessions(&mut app_state.redis_connection).await?;
     if backfill_sessions.is_empty() {
         warn!("no sessions available for backfill!");
-        return Ok(None);
+        return Ok(AllocationResult::Unavailable);
     }
     info!("{} sessions awaiting backfill", backfill_sessions.len());
 
@@ -119,11 +154,15 @@ pub async fn reserve_backfill_slot(
                 read_gameserver_info(&mut app_state.redis_connection, game_session_info.server_id)
                     .await?;
             if let Some(server_info) = server_info {
+                let reservation_timeout = app_state.options.reservation_timeout();
+                let now = Instant::now();
+
                 notifs::notify_gameserver(
                     app_state,
                     internal::notifs::ReservationRequestV1::new(game_session_id, vec![user_id])
-                        .as_notification(server_info.server_id)?,
-                    Some(RESERVATION_TIMEOUT),
+                        .as_notification(server_info.server_id)?
+                        .with_request_id(request_id.clone()),
+                    Some(reservation_timeout),
                 )
                 .await?;
 
@@ -135,16 +174,21 @@ pub async fn reserve_backfill_slot(
                 // or a "send messages on notifs connect" piece
 
                 let res = timeout(
-                    RESERVATION_TIMEOUT,
+                    reservation_timeout,
                     wait_for_reservation(&mut app_state.redis_connection, game_session_id, user_id),
                 )
                 .await;
                 if res.is_err() {
                     warn!("reservation timeout!");
-                    return Ok(None);
+                    metrics::histogram!("reservation_duration_seconds", "result" => "timeout")
+                        .record(now.elapsed().as_secs_f64());
+                    return Ok(AllocationResult::Timeout);
                 }
 
-                return Ok(Some(server_info));
+                metrics::histogram!("reservation_duration_seconds", "result" => "reserved")
+                    .record(now.elapsed().as_secs_f64());
+
+                return Ok(AllocationResult::Allocated(server_info));
             } else {
                 warn!("invalid backfill server {}", game_session_info.server_id);
 
@@ -160,7 +204,7 @@ pub async fn reserve_backfill_slot(
         }
     }
 
-    Ok(None)
+    Ok(AllocationResult::Unavailable)
 }
 
 async fn wait_for_placement(
@@ -188,7 +232,12 @@ async fn wait_for_placement(
             }
         }
 
-        if server_info.state == common::gameserver::GameServerState::InGame {
+        // a draining server still hosts the session it was placed with
+        if matches!(
+            server_info.state,
+            common::gameserver::GameServerState::InGame
+                | common::gameserver::GameServerState::Draining
+        ) {
             return Ok(Some(server_info));
         }
     }
@@ -196,16 +245,17 @@ async fn wait_for_placement(
 
 pub async fn allocate_game_server(
     app_state: &mut AppState,
+    request_id: &RequestId,
     user_id: UserId,
     game_session_id: Uuid,
-) -> anyhow::Result<Option<models::gameserver::GameServerInfo>> {
+) -> anyhow::Result<AllocationResult> {
     let server_ids: Vec<(String, u64)> = app_state
         .redis_connection
         .zpopmin(WAITING_GAMESERVERS_INDEX, 1)
         .await?;
     if server_ids.len() != 1 {
         warn!("no game servers available for placement!");
-        return Ok(None);
+        return Ok(AllocationResult::Unavailable);
     }
     info!("{} servers available for placement", server_ids.len());
 
@@ -217,14 +267,25 @@ pub async fn allocate_game_server(
         if server_info.state != common::gameserver::GameServerState::WaitingForPlacement {
             // TODO: don't fail, try again until we can't find one
             warn!("server not waiting for placement!");
-            return Ok(None);
+            return Ok(AllocationResult::Unavailable);
         }
 
+        let placement_timeout = app_state.options.placement_timeout();
+        let now = Instant::now();
+
+        // TODO: look these up by match type once there's more than one
+        let game_settings = GameSettings::default();
+
         notifs::notify_gameserver(
             app_state,
-            internal::notifs::PlacementRequestV1::new(game_session_id, vec![user_id])
-                .as_notification(server_id)?,
-            Some(PLACEMENT_TIMEOUT),
+            internal::notifs::PlacementRequestV1::new(
+                game_session_id,
+                vec![user_id],
+                game_settings,
+            )
+            .as_notification(server_id)?
+            .with_request_id(request_id.clone()),
+            Some(placement_timeout),
         )
         .await?;
 
@@ -236,24 +297,52 @@ pub async fn allocate_game_server(
         // or a "send messages on notifs connect" piece
 
         let res = timeout(
-            PLACEMENT_TIMEOUT,
+            placement_timeout,
             wait_for_placement(&mut app_state.redis_connection, server_id, game_session_id),
         )
         .await;
         if res.is_err() {
             warn!("placement timeout!");
-            return Ok(None);
+            metrics::histogram!("placement_duration_seconds", "result" => "timeout")
+                .record(now.elapsed().as_secs_f64());
+            return Ok(AllocationResult::Timeout);
         }
 
         info!("session {} placed on {}", game_session_id, server_id);
 
+        metrics::histogram!("placement_duration_seconds", "result" => "placed")
+            .record(now.elapsed().as_secs_f64());
+
         // TODO: if this comes back as None we should loop
         let server_info = res??.unwrap();
 
-        return Ok(Some(server_info));
+        return Ok(AllocationResult::Allocated(server_info));
     } else {
         warn!("invalid placement server {}", server_id);
     }
 
-    Ok(None)
+    Ok(AllocationResult::Unavailable)
+}
+
+pub async fn update_gameserver_metrics(conn: &mut RedisConnection) -> anyhow::Result<()> {
+    let servers = list_gameservers(conn).await?;
+
+    for state in [
+        GameServerState::Init,
+        GameServerState::WaitingForPlacement,
+        GameServerState::Loading,
+        GameServerState::InGame,
+        GameServerState::Draining,
+        GameServerState::Shutdown,
+    ] {
+        let count = servers
+            .iter()
+            .filter(|server_info| server_info.state == state)
+            .count();
+
+        metrics::gauge!("gameservers", "state" => format!("{:?}", state).to_lowercase())
+            .set(count as f64);
+    }
+
+    Ok(())
 }
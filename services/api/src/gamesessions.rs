@@ -3,13 +3,10 @@ use uuid::Uuid;
 
 use internal::{
     gameserver::{get_gamesession_key, GAMESESSIONS_BACKFILL_SET, GAMESESSIONS_INDEX},
+    models,
     redis::RedisConnection,
 };
 
-use crate::models;
-
-const SESSION_INFO_TTL: u64 = 60;
-
 pub async fn read_game_session_info(
     conn: &mut RedisConnection,
     game_session_id: Uuid,
@@ -21,20 +18,64 @@ pub async fn read_game_session_info(
     Ok(None)
 }
 
+pub async fn list_game_sessions(
+    conn: &mut RedisConnection,
+) -> anyhow::Result<Vec<models::gamesession::GameSessionInfo>> {
+    let game_session_ids: Vec<String> = conn.zrange(GAMESESSIONS_INDEX, 0, -1).await?;
+
+    let mut game_sessions = Vec::with_capacity(game_session_ids.len());
+    for game_session_id in game_session_ids {
+        let game_session_id = Uuid::parse_str(&game_session_id)?;
+
+        // the index may be stale until the next heartbeat cleans it up
+        if let Some(game_session_info) = read_game_session_info(conn, game_session_id).await? {
+            game_sessions.push(game_session_info);
+        }
+    }
+
+    Ok(game_sessions)
+}
+
+pub async fn remove_backfill_game_session(
+    conn: &mut RedisConnection,
+    game_session_id: Uuid,
+) -> anyhow::Result<()> {
+    let _: () = conn
+        .hdel(GAMESESSIONS_BACKFILL_SET, game_session_id.to_string())
+        .await?;
+
+    Ok(())
+}
+
+// the server moved on, so stop offering the session for backfill
+// rather than waiting for it to expire
+pub async fn remove_game_session(
+    pipeline: &mut Pipeline,
+    game_session_id: Uuid,
+) -> anyhow::Result<()> {
+    pipeline.del(get_gamesession_key(game_session_id));
+    pipeline.zrem(GAMESESSIONS_INDEX, game_session_id.to_string());
+    pipeline.hdel(GAMESESSIONS_BACKFILL_SET, game_session_id.to_string());
+
+    Ok(())
+}
+
 pub async fn update_game_session(
     pipeline: &mut Pipeline,
     game_session_info: &models::gamesession::GameSessionInfo,
+    backfill: bool,
+    ttl: u64,
 ) -> anyhow::Result<()> {
     let value = serde_json::to_string(&game_session_info)?;
 
     let now = chrono::Utc::now().timestamp() as u64;
-    let expiry = now - SESSION_INFO_TTL;
+    let expiry = now - ttl;
 
     // save the session info
     pipeline.set_ex(
         get_gamesession_key(game_session_info.game_session_id),
         value,
-        SESSION_INFO_TTL,
+        ttl,
     );
 
     // update the session index
@@ -47,7 +88,7 @@ pub async fn update_game_session(
 
     // update sessions that need backfill
     let openslots = game_session_info.player_slots_remaining();
-    if openslots > 0 {
+    if backfill && openslots > 0 {
         pipeline.hset(
             GAMESESSIONS_BACKFILL_SET,
             game_session_info.game_session_id.to_string(),
@@ -63,6 +104,38 @@ pub async fn update_game_session(
     Ok(())
 }
 
+// the server reports totals for the session, so only count what's new since the last heartbeat
+pub fn update_game_session_metrics(
+    previous: Option<&models::gamesession::GameSessionInfo>,
+    game_session_info: &models::gamesession::GameSessionInfo,
+) {
+    let (input_buffer_underruns, input_buffer_overruns, tick_overruns) = previous
+        .map(|previous| {
+            (
+                previous.input_buffer_underruns,
+                previous.input_buffer_overruns,
+                previous.tick_overruns,
+            )
+        })
+        .unwrap_or_default();
+
+    metrics::counter!("input_buffer_underruns_total").increment(
+        game_session_info
+            .input_buffer_underruns
+            .saturating_sub(input_buffer_underruns) as u64,
+    );
+    metrics::counter!("input_buffer_overruns_total").increment(
+        game_session_info
+            .input_buffer_overruns
+            .saturating_sub(input_buffer_overruns) as u64,
+    );
+    metrics::counter!("tick_overruns_total").increment(
+        game_session_info
+            .tick_overruns
+            .saturating_sub(tick_overruns) as u64,
+    );
+}
+
 pub async fn get_backfill_game_sessions(
     conn: &mut RedisConnection,
 ) -> anyhow::Result<Vec<(String, u64)>> {
@@ -0,0 +1,469 @@
+use axum::{debug_handler, extract::State};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use common::{
+    gameserver::{GameServerOrchestration, GameServerState},
+    user::UserId,
+    ErrorResponse,
+};
+use internal::{
+    axum::{
+        extract::{Json, Path, Query},
+        AppError, RequestId,
+    },
+    models,
+    notifs::{AsNotification, Notification},
+};
+
+use crate::{auth::AdminUser, gameservers, gamesessions, notifs, state::AppState};
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AdminFilterParamsV1 {
+    pub state: Option<GameServerState>,
+    pub orchestration: Option<GameServerOrchestration>,
+    pub region: Option<String>,
+}
+
+impl AdminFilterParamsV1 {
+    fn matches(&self, server_info: &models::gameserver::GameServerInfo) -> bool {
+        self.state.is_none_or(|state| state == server_info.state)
+            && self
+                .orchestration
+                .is_none_or(|orchestration| orchestration == server_info.orchestration)
+            && self
+                .region
+                .as_ref()
+                .is_none_or(|region| server_info.region.as_ref() == Some(region))
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ListGameServersResponseV1 {
+    pub game_servers: Vec<models::gameserver::GameServerInfo>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct GetGameServerResponseV1 {
+    pub game_server: models::gameserver::GameServerInfo,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub game_session: Option<models::gamesession::GameSessionInfo>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ListGameSessionsResponseV1 {
+    pub game_sessions: Vec<models::gamesession::GameSessionInfo>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct GetGameSessionResponseV1 {
+    pub game_session: models::gamesession::GameSessionInfo,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub game_server: Option<models::gameserver::GameServerInfo>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ListNotifsResponseV1 {
+    pub notifs: Vec<Notification>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct PostKickPlayerRequestV1 {
+    #[schema(value_type = Uuid)]
+    pub player_id: UserId,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AdminActionResponseV1 {
+    pub notif_id: Uuid,
+}
+
+async fn read_gameserver_info(
+    app_state: &mut AppState,
+    server_id: Uuid,
+) -> Result<models::gameserver::GameServerInfo, AppError> {
+    gameservers::read_gameserver_info(&mut app_state.redis_connection, server_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Game server not found"))
+}
+
+async fn read_game_session_info(
+    app_state: &mut AppState,
+    game_session_id: Uuid,
+) -> Result<models::gamesession::GameSessionInfo, AppError> {
+    gamesessions::read_game_session_info(&mut app_state.redis_connection, game_session_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Game session not found"))
+}
+
+async fn send_admin_notif(
+    app_state: &mut AppState,
+    request_id: RequestId,
+    notification: Notification,
+) -> Result<Json<AdminActionResponseV1>, AppError> {
+    let notif_id = notification.id;
+    notifs::notify_gameserver(app_state, notification.with_request_id(request_id), None).await?;
+
+    Ok(Json(AdminActionResponseV1 { notif_id }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/gameservers/v1",
+    tag = "admin",
+    security(("admin_token" = [])),
+    params(AdminFilterParamsV1),
+    responses(
+        (status = 200, description = "Game servers", body = ListGameServersResponseV1),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 429, description = "Rate limited, see Retry-After", body = ErrorResponse),
+        (status = 500, description = "Internal error", body = ErrorResponse),
+    )
+)]
+#[debug_handler]
+pub async fn get_list_gameservers_v1(
+    _admin: AdminUser,
+    State(mut app_state): State<AppState>,
+    Query(params): Query<AdminFilterParamsV1>,
+) -> Result<Json<ListGameServersResponseV1>, AppError> {
+    let game_servers = gameservers::list_gameservers(&mut app_state.redis_connection)
+        .await?
+        .into_iter()
+        .filter(|server_info| params.matches(server_info))
+        .collect();
+
+    Ok(Json(ListGameServersResponseV1 { game_servers }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/gameservers/{server_id}/v1",
+    tag = "admin",
+    security(("admin_token" = [])),
+    params(("server_id" = Uuid, Path, description = "Game server id")),
+    responses(
+        (status = 200, description = "Game server", body = GetGameServerResponseV1),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "Game server not found", body = ErrorResponse),
+        (status = 429, description = "Rate limited, see Retry-After", body = ErrorResponse),
+        (status = 500, description = "Internal error", body = ErrorResponse),
+    )
+)]
+#[debug_handler]
+pub async fn get_gameserver_v1(
+    _admin: AdminUser,
+    State(mut app_state): State<AppState>,
+    Path(server_id): Path<Uuid>,
+) -> Result<Json<GetGameServerResponseV1>, AppError> {
+    let game_server = read_gameserver_info(&mut app_state, server_id).await?;
+
+    let game_session = match game_server.game_session_id {
+        Some(game_session_id) => {
+            gamesessions::read_game_session_info(&mut app_state.redis_connection, game_session_id)
+                .await?
+        }
+        None => None,
+    };
+
+    Ok(Json(GetGameServerResponseV1 {
+        game_server,
+        game_session,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/gameservers/{server_id}/notifs/v1",
+    tag = "admin",
+    security(("admin_token" = [])),
+    params(("server_id" = Uuid, Path, description = "Game server id")),
+    responses(
+        (status = 200, description = "Unexpired notifications", body = ListNotifsResponseV1),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 429, description = "Rate limited, see Retry-After", body = ErrorResponse),
+        (status = 500, description = "Internal error", body = ErrorResponse),
+    )
+)]
+#[debug_handler]
+pub async fn get_gameserver_notifs_v1(
+    _admin: AdminUser,
+    State(mut app_state): State<AppState>,
+    Path(server_id): Path<Uuid>,
+) -> Result<Json<ListNotifsResponseV1>, AppError> {
+    let notifs =
+        notifs::read_gameserver_mailbox(&mut app_state.redis_connection, server_id).await?;
+
+    Ok(Json(ListNotifsResponseV1 { notifs }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/gameservers/{server_id}/notifs/{notif_id}/resend/v1",
+    tag = "admin",
+    security(("admin_token" = [])),
+    params(("server_id" = Uuid, Path, description = "Game server id"), ("notif_id" = Uuid, Path, description = "Notification id")),
+    responses(
+        (status = 200, description = "Notification resent", body = AdminActionResponseV1),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "Notification not found", body = ErrorResponse),
+        (status = 429, description = "Rate limited, see Retry-After", body = ErrorResponse),
+        (status = 500, description = "Internal error", body = ErrorResponse),
+    )
+)]
+#[debug_handler]
+pub async fn post_resend_gameserver_notif_v1(
+    _admin: AdminUser,
+    State(mut app_state): State<AppState>,
+    Path((server_id, notif_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<AdminActionResponseV1>, AppError> {
+    if !notifs::resend_gameserver_notif(&mut app_state, server_id, notif_id).await? {
+        return Err(AppError::not_found("Notification not found"));
+    }
+
+    Ok(Json(AdminActionResponseV1 { notif_id }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/gameservers/{server_id}/drain/v1",
+    tag = "admin",
+    security(("admin_token" = [])),
+    params(("server_id" = Uuid, Path, description = "Game server id")),
+    responses(
+        (status = 200, description = "Notification sent", body = AdminActionResponseV1),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "Game server not found", body = ErrorResponse),
+        (status = 409, description = "Game server already draining or shutting down", body = ErrorResponse),
+        (status = 429, description = "Rate limited, see Retry-After", body = ErrorResponse),
+        (status = 500, description = "Internal error", body = ErrorResponse),
+    )
+)]
+#[debug_handler]
+pub async fn post_drain_gameserver_v1(
+    _admin: AdminUser,
+    request_id: RequestId,
+    State(mut app_state): State<AppState>,
+    Path(server_id): Path<Uuid>,
+) -> Result<Json<AdminActionResponseV1>, AppError> {
+    let server_info = read_gameserver_info(&mut app_state, server_id).await?;
+    if server_info.state == GameServerState::Shutdown {
+        return Err(AppError::conflict("Game server already shutting down"));
+    }
+
+    if server_info.state == GameServerState::Draining {
+        return Err(AppError::conflict("Game server already draining"));
+    }
+
+    info!("draining game server {} ...", server_id);
+
+    // stop placing new sessions / players on the server
+    gameservers::remove_waiting_gameserver(&mut app_state.redis_connection, server_id).await?;
+    if let Some(game_session_id) = server_info.game_session_id {
+        gamesessions::remove_backfill_game_session(
+            &mut app_state.redis_connection,
+            game_session_id,
+        )
+        .await?;
+    }
+
+    send_admin_notif(
+        &mut app_state,
+        request_id,
+        internal::notifs::DrainRequestV1::default().as_notification(server_id)?,
+    )
+    .await
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/gameservers/{server_id}/shutdown/v1",
+    tag = "admin",
+    security(("admin_token" = [])),
+    params(("server_id" = Uuid, Path, description = "Game server id")),
+    responses(
+        (status = 200, description = "Notification sent", body = AdminActionResponseV1),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "Game server not found", body = ErrorResponse),
+        (status = 409, description = "Game server already shutting down", body = ErrorResponse),
+        (status = 429, description = "Rate limited, see Retry-After", body = ErrorResponse),
+        (status = 500, description = "Internal error", body = ErrorResponse),
+    )
+)]
+#[debug_handler]
+pub async fn post_shutdown_gameserver_v1(
+    _admin: AdminUser,
+    request_id: RequestId,
+    State(mut app_state): State<AppState>,
+    Path(server_id): Path<Uuid>,
+) -> Result<Json<AdminActionResponseV1>, AppError> {
+    let server_info = read_gameserver_info(&mut app_state, server_id).await?;
+    if server_info.state == GameServerState::Shutdown {
+        return Err(AppError::conflict("Game server already shutting down"));
+    }
+
+    info!("shutting down game server {} ...", server_id);
+
+    gameservers::remove_waiting_gameserver(&mut app_state.redis_connection, server_id).await?;
+
+    send_admin_notif(
+        &mut app_state,
+        request_id,
+        internal::notifs::ShutdownRequestV1::default().as_notification(server_id)?,
+    )
+    .await
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/gamesessions/v1",
+    tag = "admin",
+    security(("admin_token" = [])),
+    params(AdminFilterParamsV1),
+    responses(
+        (status = 200, description = "Game sessions", body = ListGameSessionsResponseV1),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 429, description = "Rate limited, see Retry-After", body = ErrorResponse),
+        (status = 500, description = "Internal error", body = ErrorResponse),
+    )
+)]
+#[debug_handler]
+pub async fn get_list_gamesessions_v1(
+    _admin: AdminUser,
+    State(mut app_state): State<AppState>,
+    Query(params): Query<AdminFilterParamsV1>,
+) -> Result<Json<ListGameSessionsResponseV1>, AppError> {
+    let mut game_sessions = vec![];
+    for game_session in gamesessions::list_game_sessions(&mut app_state.redis_connection).await? {
+        // sessions are filtered on the server they're placed on
+        let server_info = gameservers::read_gameserver_info(
+            &mut app_state.redis_connection,
+            game_session.server_id,
+        )
+        .await?;
+        if server_info.is_some_and(|server_info| params.matches(&server_info)) {
+            game_sessions.push(game_session);
+        }
+    }
+
+    Ok(Json(ListGameSessionsResponseV1 { game_sessions }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/gamesessions/{game_session_id}/v1",
+    tag = "admin",
+    security(("admin_token" = [])),
+    params(("game_session_id" = Uuid, Path, description = "Game session id")),
+    responses(
+        (status = 200, description = "Game session", body = GetGameSessionResponseV1),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "Game session not found", body = ErrorResponse),
+        (status = 429, description = "Rate limited, see Retry-After", body = ErrorResponse),
+        (status = 500, description = "Internal error", body = ErrorResponse),
+    )
+)]
+#[debug_handler]
+pub async fn get_gamesession_v1(
+    _admin: AdminUser,
+    State(mut app_state): State<AppState>,
+    Path(game_session_id): Path<Uuid>,
+) -> Result<Json<GetGameSessionResponseV1>, AppError> {
+    let game_session = read_game_session_info(&mut app_state, game_session_id).await?;
+
+    let game_server =
+        gameservers::read_gameserver_info(&mut app_state.redis_connection, game_session.server_id)
+            .await?;
+
+    Ok(Json(GetGameSessionResponseV1 {
+        game_session,
+        game_server,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/gamesessions/{game_session_id}/end/v1",
+    tag = "admin",
+    security(("admin_token" = [])),
+    params(("game_session_id" = Uuid, Path, description = "Game session id")),
+    responses(
+        (status = 200, description = "Notification sent", body = AdminActionResponseV1),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "Game session not found", body = ErrorResponse),
+        (status = 429, description = "Rate limited, see Retry-After", body = ErrorResponse),
+        (status = 500, description = "Internal error", body = ErrorResponse),
+    )
+)]
+#[debug_handler]
+pub async fn post_end_gamesession_v1(
+    _admin: AdminUser,
+    request_id: RequestId,
+    State(mut app_state): State<AppState>,
+    Path(game_session_id): Path<Uuid>,
+) -> Result<Json<AdminActionResponseV1>, AppError> {
+    let game_session = read_game_session_info(&mut app_state, game_session_id).await?;
+
+    info!("ending game session {} ...", game_session_id);
+
+    gamesessions::remove_backfill_game_session(&mut app_state.redis_connection, game_session_id)
+        .await?;
+
+    send_admin_notif(
+        &mut app_state,
+        request_id,
+        internal::notifs::EndSessionRequestV1::new(game_session_id)
+            .as_notification(game_session.server_id)?,
+    )
+    .await
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/gamesessions/{game_session_id}/kick/v1",
+    tag = "admin",
+    security(("admin_token" = [])),
+    params(("game_session_id" = Uuid, Path, description = "Game session id")),
+    request_body = PostKickPlayerRequestV1,
+    responses(
+        (status = 200, description = "Notification sent", body = AdminActionResponseV1),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "Game session or player not found", body = ErrorResponse),
+        (status = 429, description = "Rate limited, see Retry-After", body = ErrorResponse),
+        (status = 500, description = "Internal error", body = ErrorResponse),
+    )
+)]
+#[debug_handler]
+pub async fn post_kick_player_v1(
+    _admin: AdminUser,
+    request_id: RequestId,
+    State(mut app_state): State<AppState>,
+    Path(game_session_id): Path<Uuid>,
+    Json(request): Json<PostKickPlayerRequestV1>,
+) -> Result<Json<AdminActionResponseV1>, AppError> {
+    let game_session = read_game_session_info(&mut app_state, game_session_id).await?;
+
+    if !game_session.has_player(request.player_id) {
+        return Err(AppError::not_found("Player not in game session"));
+    }
+
+    info!(
+        "kicking player {} from game session {} ...",
+        request.player_id, game_session_id
+    );
+
+    send_admin_notif(
+        &mut app_state,
+        request_id,
+        internal::notifs::KickPlayerRequestV1::new(game_session_id, request.player_id)
+            .as_notification(game_session.server_id)?,
+    )
+    .await
+}
@@ -1,58 +1,145 @@
-use axum::{
-    debug_handler,
-    extract::{Query, State},
-    Json,
-};
-use axum_extra::TypedHeader;
-use headers::authorization::{Authorization, Bearer};
+use axum::{debug_handler, extract::State};
 use serde::Deserialize;
 use tracing::{info, warn};
 use uuid::Uuid;
 
-use common::{gameclient::*, user::User};
-use internal::axum::AppError;
+use common::{
+    gameclient::*,
+    user::{User, UserId},
+    ErrorResponse,
+};
+use internal::{
+    axum::{
+        extract::{BearerAuth, Json, Query},
+        AppError, RequestId,
+    },
+    models::gameserver::GameServerInfo,
+};
 
-use crate::{gameservers, state::AppState};
+use crate::{
+    gameclients,
+    gameservers::{self, AllocationResult},
+    state::AppState,
+};
 
 #[derive(Debug, Deserialize)]
 pub struct FindServerParamsV1 {}
 
+#[utoipa::path(
+    get,
+    path = "/gameclient/find_server/v1",
+    tag = "gameclient",
+    security(("user_token" = [])),
+    responses(
+        (status = 200, description = "Server found", body = FindServerResponseV1),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 409, description = "Already finding a server", body = ErrorResponse),
+        (status = 429, description = "Rate limited, see Retry-After", body = ErrorResponse),
+        (status = 503, description = "No game servers available", body = ErrorResponse),
+        (status = 504, description = "Timed out waiting for a game server", body = ErrorResponse),
+        (status = 500, description = "Internal error", body = ErrorResponse),
+    )
+)]
 #[debug_handler]
 pub async fn get_find_server_v1(
-    TypedHeader(bearer): TypedHeader<Authorization<Bearer>>,
+    bearer: BearerAuth,
+    request_id: RequestId,
     State(mut app_state): State<AppState>,
     Query(_params): Query<FindServerParamsV1>,
 ) -> Result<Json<FindServerResponseV1>, AppError> {
-    let user = User::read_from_token(bearer.token()).await?;
+    let user = User::read_from_token(bearer.token())
+        .await
+        .map_err(|_| AppError::unauthorized("Invalid user token"))?;
+
+    // held until we return, or the request is dropped
+    let Some(_lock) = gameclients::acquire_find_server_lock(
+        &mut app_state.redis_connection,
+        user.user_id,
+        app_state.options.find_server_lock_ttl(),
+    )
+    .await?
+    else {
+        warn!("find server already in progress for {}", user.user_id);
+        metrics::counter!("find_server_total", "outcome" => "in_progress").increment(1);
+        return Err(AppError::conflict("Already finding a server"));
+    };
+
+    find_server(&mut app_state, &request_id, user.user_id).await
+}
+
+fn find_server_response(
+    app_state: &AppState,
+    user_id: UserId,
+    server_info: &GameServerInfo,
+) -> anyhow::Result<FindServerResponseV1> {
+    let connect_token = gameclients::generate_connect_token(
+        &app_state.connect_token_key,
+        app_state.options.connect_token_expire(),
+        app_state.options.connect_token_timeout_secs,
+        user_id,
+        server_info,
+    )?;
 
-    info!("finding game server for {} ...", user.user_id);
+    Ok(FindServerResponseV1 {
+        v4addrs: server_info.v4addrs.clone(),
+        v6addrs: server_info.v6addrs.clone(),
+        port: server_info.port,
+        connect_token,
+    })
+}
+
+async fn find_server(
+    app_state: &mut AppState,
+    request_id: &RequestId,
+    user_id: UserId,
+) -> Result<Json<FindServerResponseV1>, AppError> {
+    info!("finding game server for {} ...", user_id);
 
     // TODO: check for reconnect
 
     // not reconnect, check for backfill
-    if let Some(server_info) =
-        gameservers::reserve_backfill_slot(&mut app_state, user.user_id).await?
-    {
-        return Ok(Json(FindServerResponseV1 {
-            address: server_info.v4addrs[0].clone(),
-            port: server_info.port,
-        }));
+    let mut timed_out = false;
+    match gameservers::reserve_backfill_slot(app_state, request_id, user_id).await? {
+        AllocationResult::Allocated(server_info) => {
+            metrics::counter!("find_server_total", "outcome" => "backfill").increment(1);
+
+            return Ok(Json(find_server_response(
+                app_state,
+                user_id,
+                &server_info,
+            )?));
+        }
+        AllocationResult::Timeout => timed_out = true,
+        AllocationResult::Unavailable => (),
     }
 
     info!("no backfill servers available, allocating session");
 
     let game_session_id = Uuid::new_v4();
 
-    if let Some(server_info) =
-        gameservers::allocate_game_server(&mut app_state, user.user_id, game_session_id).await?
+    match gameservers::allocate_game_server(app_state, request_id, user_id, game_session_id).await?
     {
-        return Ok(Json(FindServerResponseV1 {
-            address: server_info.v4addrs[0].clone(),
-            port: server_info.port,
-        }));
+        AllocationResult::Allocated(server_info) => {
+            metrics::counter!("find_server_total", "outcome" => "placed").increment(1);
+
+            return Ok(Json(find_server_response(
+                app_state,
+                user_id,
+                &server_info,
+            )?));
+        }
+        AllocationResult::Timeout => timed_out = true,
+        AllocationResult::Unavailable => (),
+    }
+
+    if timed_out {
+        warn!("timed out finding a server!");
+        metrics::counter!("find_server_total", "outcome" => "timeout").increment(1);
+        return Err(AppError::timeout("Timed out waiting for a game server"));
     }
 
     warn!("no placement servers available!");
+    metrics::counter!("find_server_total", "outcome" => "none").increment(1);
 
-    Ok(Json(FindServerResponseV1::default()))
+    Err(AppError::no_capacity("No game servers available"))
 }
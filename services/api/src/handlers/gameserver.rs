@@ -1,21 +1,48 @@
-use axum::{debug_handler, extract::State, Json};
-use axum_extra::TypedHeader;
-use headers::authorization::{Authorization, Bearer};
+use axum::{debug_handler, extract::State};
+use tracing::info;
 use uuid::Uuid;
 
-use common::gameserver::*;
-use internal::axum::AppError;
+use common::{gameserver::*, ErrorResponse};
+use internal::{
+    axum::{
+        extract::{BearerAuth, Json},
+        AppError,
+    },
+    models,
+};
 
-use crate::{gameservers, gamesessions, models, state::AppState};
+use crate::{gameservers, gamesessions, state::AppState};
 
+#[utoipa::path(
+    post,
+    path = "/gameserver/heartbeat/v1",
+    tag = "gameserver",
+    security(("server_token" = [])),
+    request_body = PostHeartbeatRequestV1,
+    responses(
+        (status = 200, description = "Heartbeat accepted", body = PostHeartbeatResponseV1),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 429, description = "Rate limited, see Retry-After", body = ErrorResponse),
+        (status = 500, description = "Internal error", body = ErrorResponse),
+    )
+)]
 #[debug_handler]
 pub async fn post_heartbeat_v1(
-    TypedHeader(bearer): TypedHeader<Authorization<Bearer>>,
+    bearer: BearerAuth,
     State(mut app_state): State<AppState>,
     Json(request): Json<PostHeartbeatRequestV1>,
 ) -> Result<Json<PostHeartbeatResponseV1>, AppError> {
     // TODO: validate the server token
-    let server_id = Uuid::parse_str(bearer.token())?;
+    let server_id = Uuid::parse_str(bearer.token())
+        .map_err(|_| AppError::unauthorized("Invalid server token"))?;
+
+    if let Some(request_id) = &request.request_id {
+        info!(
+            "heartbeat from {} acknowledging request {}",
+            server_id, request_id
+        );
+    }
 
     let gameserver_info = models::gameserver::GameServerInfo::new(server_id, &request.server_info);
     let game_session_info =
@@ -27,16 +54,65 @@ pub async fn post_heartbeat_v1(
                 models::gamesession::GameSessionInfo::new(server_id, game_session_info)
             });
 
+    // the session this server was hosting, if it's ended it can't be backfilled anymore
+    let ended_game_session_id =
+        gameservers::read_gameserver_info(&mut app_state.redis_connection, server_id)
+            .await?
+            .and_then(|previous| previous.game_session_id)
+            .filter(|previous_game_session_id| {
+                game_session_info.as_ref().is_none_or(|game_session_info| {
+                    game_session_info.game_session_id != *previous_game_session_id
+                })
+            });
+
+    let previous_game_session_info = match &game_session_info {
+        Some(game_session_info) => {
+            gamesessions::read_game_session_info(
+                &mut app_state.redis_connection,
+                game_session_info.game_session_id,
+            )
+            .await?
+        }
+        None => None,
+    };
+
     let mut pipeline = redis::pipe();
 
-    gameservers::update_gameserver(&mut pipeline, &gameserver_info).await?;
-    if let Some(game_session_info) = game_session_info {
-        gamesessions::update_game_session(&mut pipeline, &game_session_info).await?;
+    if let Some(ended_game_session_id) = ended_game_session_id {
+        info!(
+            "server {} ended game session {}",
+            server_id, ended_game_session_id
+        );
+        gamesessions::remove_game_session(&mut pipeline, ended_game_session_id).await?;
+    }
+
+    gameservers::update_gameserver(
+        &mut pipeline,
+        &gameserver_info,
+        app_state.options.server_info_ttl_secs,
+    )
+    .await?;
+    if let Some(game_session_info) = &game_session_info {
+        // draining servers won't take any more players
+        gamesessions::update_game_session(
+            &mut pipeline,
+            game_session_info,
+            request.server_info.state != GameServerState::Draining,
+            app_state.options.session_info_ttl_secs,
+        )
+        .await?;
     }
 
     let _: () = pipeline
         .query_async(&mut app_state.redis_connection)
         .await?;
 
+    if let Some(game_session_info) = &game_session_info {
+        gamesessions::update_game_session_metrics(
+            previous_game_session_info.as_ref(),
+            game_session_info,
+        );
+    }
+
     Ok(Json(PostHeartbeatResponseV1 {}))
 }
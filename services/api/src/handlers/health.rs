@@ -0,0 +1,31 @@
+use axum::{debug_handler, extract::State, http::StatusCode, response::IntoResponse};
+use redis::AsyncCommands;
+use tracing::warn;
+
+use internal::shutdown::is_shutting_down;
+
+use crate::state::AppState;
+
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    tag = "service",
+    responses(
+        (status = 200, description = "Ready to serve requests", body = String),
+        (status = 503, description = "Shutting down or redis unavailable", body = String),
+    )
+)]
+#[debug_handler]
+pub async fn get_readyz(State(mut app_state): State<AppState>) -> impl IntoResponse {
+    if is_shutting_down(&app_state.shutdown) {
+        return (StatusCode::SERVICE_UNAVAILABLE, "Shutting down");
+    }
+
+    let pong: redis::RedisResult<String> = app_state.redis_connection.ping().await;
+    if let Err(err) = pong {
+        warn!("readiness check failed, redis unavailable: {}", err);
+        return (StatusCode::SERVICE_UNAVAILABLE, "Redis unavailable");
+    }
+
+    (StatusCode::OK, "OK")
+}
@@ -1,2 +1,5 @@
+pub mod admin;
 pub mod gameclient;
 pub mod gameserver;
+pub mod health;
+pub mod openapi;
@@ -0,0 +1,21 @@
+use std::sync::LazyLock;
+
+use axum::debug_handler;
+use utoipa::OpenApi;
+
+use internal::axum::extract::Json;
+
+use crate::openapi::ApiDoc;
+
+static OPENAPI: LazyLock<utoipa::openapi::OpenApi> = LazyLock::new(ApiDoc::openapi);
+
+#[utoipa::path(
+    get,
+    path = "/openapi.json",
+    tag = "service",
+    responses((status = 200, description = "OpenAPI description of this api"))
+)]
+#[debug_handler]
+pub async fn get_openapi() -> Json<utoipa::openapi::OpenApi> {
+    Json(OPENAPI.clone())
+}
@@ -1,8 +1,10 @@
+mod auth;
+mod gameclients;
 mod gameservers;
 mod gamesessions;
 mod handlers;
-mod models;
 mod notifs;
+mod openapi;
 mod options;
 mod routes;
 mod state;
@@ -11,46 +13,24 @@ use std::net::SocketAddr;
 
 use axum::{
     http::{HeaderValue, Method},
-    middleware, Router,
+    middleware,
 };
-use clap::Parser;
 use tower::ServiceBuilder;
 use tower_http::{
     cors::CorsLayer,
     trace::{DefaultMakeSpan, DefaultOnFailure, TraceLayer},
     LatencyUnit,
 };
-use tracing::{info, Level};
-use tracing_subscriber::FmtSubscriber;
+use tracing::{info, warn};
 
-use internal::{axum as axum_util, redis};
+use common::config;
+use internal::{axum as axum_util, logging, redis, shutdown};
 
 use options::Options;
 use state::AppState;
 
 // TODO: add authentication
 
-fn init_logging() -> anyhow::Result<()> {
-    let subscriber = FmtSubscriber::builder()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| {
-                // axum logs rejections from built-in extractors with the `axum::rejection`
-                // target, at `TRACE` level. `axum::rejection=trace` enables showing those events
-                format!(
-                    "{}=debug,tower_http=debug,axum::rejection=trace",
-                    env!("CARGO_CRATE_NAME")
-                )
-                .into()
-            }),
-        )
-        .with_max_level(Level::INFO)
-        .finish();
-
-    tracing::subscriber::set_global_default(subscriber)?;
-
-    Ok(())
-}
-
 fn init_cors_layer() -> anyhow::Result<CorsLayer> {
     info!("initializing CORS layer...");
 
@@ -61,15 +41,44 @@ fn init_cors_layer() -> anyhow::Result<CorsLayer> {
     Ok(layer)
 }
 
+fn start_metrics_task(app_state: &AppState) {
+    info!("starting metrics task ...");
+
+    let mut redis_connection = app_state.redis_connection.clone();
+    let interval = app_state.options.metrics_update_interval();
+    tokio::spawn(async move {
+        loop {
+            if let Err(err) = gameservers::update_gameserver_metrics(&mut redis_connection).await {
+                warn!("failed to update gameserver metrics: {}", err);
+            }
+
+            tokio::time::sleep(interval).await;
+        }
+    });
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    let options = Options::parse();
+    let options: Options = config::load(options::ENV_PREFIX)?;
+
+    logging::init_logging(env!("CARGO_CRATE_NAME"), &options.logging)?;
+    axum_util::init_metrics()?;
 
-    init_logging()?;
+    let connect_token_key = options.connect_token_key()?;
 
     let redis_connection = redis::connect(options.redis_host.clone()).await?;
 
-    let app_state = AppState::new(options, redis_connection);
+    let shutdown = shutdown::init_shutdown_signal();
+
+    let app_state = AppState::new(
+        options,
+        redis_connection,
+        connect_token_key,
+        shutdown.clone(),
+    );
+    start_metrics_task(&app_state);
+
+    let shutdown_timeout = app_state.options.shutdown_timeout();
 
     let addr = app_state
         .options
@@ -77,11 +86,12 @@ async fn main() -> anyhow::Result<()> {
         .parse::<SocketAddr>()
         .unwrap_or_else(|_| panic!("Invalid address: {}", app_state.options.address()));
 
-    let app = routes::init_routes(Router::new())
+    let app = routes::init_routes(&app_state)
         .layer(init_cors_layer()?)
         .layer(
             ServiceBuilder::new()
                 .layer(middleware::from_fn(axum_util::tracing_wrapper))
+                .layer(middleware::from_fn(axum_util::metrics_wrapper))
                 .layer(
                     TraceLayer::new_for_http()
                         .make_span_with(
@@ -99,9 +109,23 @@ async fn main() -> anyhow::Result<()> {
 
     let listener = tokio::net::TcpListener::bind(addr).await?;
     info!("listening on {}", listener.local_addr()?);
-    Ok(axum::serve(
+
+    // stop accepting connections on shutdown
+    // and give in-flight requests until the deadline to finish
+    let server = axum::serve(
         listener,
         app.into_make_service_with_connect_info::<SocketAddr>(),
     )
-    .await?)
+    .with_graceful_shutdown(shutdown::wait_for_shutdown(shutdown.clone()));
+
+    tokio::select! {
+        res = server.into_future() => res?,
+        _ = shutdown::shutdown_deadline(shutdown, shutdown_timeout) => {
+            warn!("shutdown timeout exceeded, dropping in-flight requests");
+        }
+    }
+
+    info!("shutdown complete");
+
+    Ok(())
 }
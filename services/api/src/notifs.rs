@@ -1,25 +1,100 @@
 use redis::AsyncCommands;
 use tokio::time::Duration;
-use tracing::info;
+use tracing::{info, warn};
+use uuid::Uuid;
 
-use internal::notifs::Notification;
+use internal::{
+    gameserver::{get_gameserver_mailbox_key, get_notif_key},
+    notifs::Notification,
+    redis::RedisConnection,
+};
 
 use crate::AppState;
 
 pub async fn notify_gameserver(
     app_state: &mut AppState,
     notification: Notification,
-    _ttl: Option<Duration>,
+    ttl: Option<Duration>,
 ) -> anyhow::Result<()> {
+    let server_id = Uuid::parse_str(&notification.recipient)?;
     let notif = serde_json::to_string(&notification)?;
     info!("notifying gameserver: {}", notif);
 
+    let ttl = ttl
+        .unwrap_or_else(|| app_state.options.notif_ttl())
+        .as_secs()
+        .max(1);
+    let now = chrono::Utc::now().timestamp() as u64;
+    let mailbox_key = get_gameserver_mailbox_key(server_id);
+
+    // add the notif to the recipient's mailbox so it can be inspected / resent
+    // the mailbox index is scored by expiry time
+    let mut pipeline = redis::pipe();
+    pipeline.set_ex(get_notif_key(notification.id), &notif, ttl);
+    pipeline.zadd(&mailbox_key, notification.id.to_string(), now + ttl);
+    pipeline.zrembyscore(&mailbox_key, 0, now);
+    pipeline.expire(&mailbox_key, ttl as i64);
+    pipeline.publish(internal::GAMESERVER_NOTIFS_CHANNEL, notif);
+
+    let _: () = pipeline
+        .query_async(&mut app_state.redis_connection)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn read_gameserver_mailbox(
+    conn: &mut RedisConnection,
+    server_id: Uuid,
+) -> anyhow::Result<Vec<Notification>> {
+    let now = chrono::Utc::now().timestamp() as u64;
+
+    let notif_ids: Vec<String> = conn
+        .zrangebyscore(get_gameserver_mailbox_key(server_id), now, "+inf")
+        .await?;
+
+    let mut notifs = Vec::with_capacity(notif_ids.len());
+    for notif_id in notif_ids {
+        let notif_id = Uuid::parse_str(&notif_id)?;
+
+        let notif: Option<String> = conn.get(get_notif_key(notif_id)).await?;
+        if let Some(notif) = notif {
+            notifs.push(serde_json::from_str(&notif)?);
+        }
+    }
+
+    Ok(notifs)
+}
+
+pub async fn resend_gameserver_notif(
+    app_state: &mut AppState,
+    server_id: Uuid,
+    notif_id: Uuid,
+) -> anyhow::Result<bool> {
+    let notif: Option<String> = app_state
+        .redis_connection
+        .get(get_notif_key(notif_id))
+        .await?;
+    let Some(notif) = notif else {
+        warn!("notif {} not found", notif_id);
+        return Ok(false);
+    };
+
+    let notification: Notification = serde_json::from_str(&notif)?;
+    if notification.recipient != server_id.to_string() {
+        warn!(
+            "notif {} recipient mismatch, got {} expected {}",
+            notif_id, notification.recipient, server_id
+        );
+        return Ok(false);
+    }
+
+    info!("resending gameserver notif: {}", notif);
+
     let _: () = app_state
         .redis_connection
         .publish(internal::GAMESERVER_NOTIFS_CHANNEL, notif)
         .await?;
 
-    // TODO: add the notif the recipient's mailbox (with ttl if provided)
-
-    Ok(())
+    Ok(true)
 }
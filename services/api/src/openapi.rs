@@ -0,0 +1,112 @@
+use utoipa::{
+    openapi::{
+        path::{Parameter, ParameterBuilder, ParameterIn},
+        security::{Http, HttpAuthScheme, SecurityScheme},
+        HeaderBuilder, Object, Required, Type,
+    },
+    Modify, OpenApi,
+};
+
+use common::REQUEST_ID_HEADER;
+
+use crate::handlers;
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "bevy-multiplayer api"),
+    paths(
+        handlers::gameclient::get_find_server_v1,
+        handlers::gameserver::post_heartbeat_v1,
+        handlers::admin::get_list_gameservers_v1,
+        handlers::admin::get_gameserver_v1,
+        handlers::admin::get_gameserver_notifs_v1,
+        handlers::admin::post_resend_gameserver_notif_v1,
+        handlers::admin::post_drain_gameserver_v1,
+        handlers::admin::post_shutdown_gameserver_v1,
+        handlers::admin::get_list_gamesessions_v1,
+        handlers::admin::get_gamesession_v1,
+        handlers::admin::post_end_gamesession_v1,
+        handlers::admin::post_kick_player_v1,
+        handlers::health::get_readyz,
+        internal::axum::handler_healthz,
+        internal::axum::handler_metrics,
+        handlers::openapi::get_openapi,
+    ),
+    components(schemas(common::ErrorResponse)),
+    modifiers(&SecurityAddon, &RequestIdAddon),
+    tags(
+        (name = "gameclient", description = "Game client api"),
+        (name = "gameserver", description = "Game server api"),
+        (name = "admin", description = "Fleet and session management"),
+        (name = "service", description = "Health, metrics and api description"),
+    )
+)]
+pub struct ApiDoc;
+
+// referenced by name in the handler security requirements
+const SECURITY_SCHEMES: &[&str] = &["user_token", "server_token", "admin_token"];
+
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+
+        // TODO: user and server tokens should be JWTs
+        // for now they're just the user / server id
+        for &name in SECURITY_SCHEMES {
+            components.add_security_scheme(
+                name,
+                SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer)),
+            );
+        }
+    }
+}
+
+// every request accepts and returns a request id (see tracing_wrapper)
+struct RequestIdAddon;
+
+impl RequestIdAddon {
+    fn parameter() -> Parameter {
+        ParameterBuilder::new()
+            .name(REQUEST_ID_HEADER)
+            .parameter_in(ParameterIn::Header)
+            .required(Required::False)
+            .description(Some("Correlation id, generated if not provided"))
+            .schema(Some(Object::with_type(Type::String)))
+            .build()
+    }
+}
+
+impl Modify for RequestIdAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        for path_item in openapi.paths.paths.values_mut() {
+            for operation in [
+                &mut path_item.get,
+                &mut path_item.post,
+                &mut path_item.put,
+                &mut path_item.delete,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                operation
+                    .parameters
+                    .get_or_insert_with(Vec::new)
+                    .push(Self::parameter());
+
+                for response in operation.responses.responses.values_mut() {
+                    if let utoipa::openapi::RefOr::T(response) = response {
+                        response.headers.insert(
+                            REQUEST_ID_HEADER.to_owned(),
+                            HeaderBuilder::new()
+                                .schema(Object::with_type(Type::String))
+                                .description(Some("Correlation id for the request"))
+                                .build(),
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
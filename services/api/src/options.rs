@@ -1,7 +1,24 @@
+use std::net::IpAddr;
+use std::time::Duration;
+
 use clap::Parser;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use common::{
+    config::ConfigArgs,
+    netcode::{self, CONNECT_TOKEN_KEY_BYTES},
+};
+use internal::{axum::RateLimit, logging::LoggingOptions};
+
+pub const ENV_PREFIX: &str = "BMP_API_";
 
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Serialize, Deserialize)]
 pub struct Options {
+    #[command(flatten)]
+    #[serde(skip)]
+    pub config: ConfigArgs,
+
     #[arg(long, default_value = "0.0.0.0")]
     pub host: String,
 
@@ -10,9 +27,158 @@ pub struct Options {
 
     #[arg(long, default_value = "redis://localhost/")]
     pub redis_host: String,
+
+    #[command(flatten)]
+    #[serde(flatten)]
+    pub logging: LoggingOptions,
+
+    #[arg(long, default_value_t = 40)]
+    pub shutdown_timeout_secs: u64,
+
+    // how long to wait for a server to accept a new session
+    #[arg(long, default_value_t = 30)]
+    pub placement_timeout_secs: u64,
+
+    // how long to wait for a server to reserve a backfill slot
+    #[arg(long, default_value_t = 5)]
+    pub reservation_timeout_secs: u64,
+
+    // servers / sessions that haven't heartbeat in this long are expired
+    #[arg(long, default_value_t = 10)]
+    pub server_info_ttl_secs: u64,
+
+    #[arg(long, default_value_t = 60)]
+    pub session_info_ttl_secs: u64,
+
+    // how long notifs are kept in the server mailbox by default
+    #[arg(long, default_value_t = 60)]
+    pub notif_ttl_secs: u64,
+
+    // should cover backfill + placement so the lock
+    // only expires if the request holding it dies
+    #[arg(long, default_value_t = 120)]
+    pub find_server_lock_ttl_secs: u64,
+
+    #[arg(long, default_value_t = 15)]
+    pub metrics_update_interval_secs: u64,
+
+    #[arg(long, env = "ADMIN_TOKEN")]
+    pub admin_token: Option<String>,
+
+    // base64 encoded key shared with the game servers to sign connect tokens
+    #[arg(long, env = "CONNECT_TOKEN_KEY")]
+    pub connect_token_key: Option<String>,
+
+    // use the development key checked into the repo if no key is set, local development only!
+    #[arg(long)]
+    #[serde(default)]
+    pub insecure_dev_key: bool,
+
+    // how long the client has to use the token
+    #[arg(long, default_value_t = 30)]
+    pub connect_token_expire_secs: u64,
+
+    // netcode connection timeout
+    #[arg(long, default_value_t = 15)]
+    pub connect_token_timeout_secs: i32,
+
+    // proxies allowed to tell us the client address with X-Forwarded-For
+    #[arg(long, value_delimiter = ',')]
+    #[serde(default)]
+    pub trusted_proxies: Vec<IpAddr>,
+
+    #[arg(long, env = "DISABLE_RATE_LIMIT")]
+    #[serde(default)]
+    pub disable_rate_limit: bool,
+
+    // rate limits are a token bucket of burst size
+    // refilled at the per minute rate, 0 disables the limit
+    #[arg(long, default_value_t = 5)]
+    pub find_server_rate_limit_burst: u32,
+
+    #[arg(long, default_value_t = 10)]
+    pub find_server_rate_limit_per_min: u32,
+
+    #[arg(long, default_value_t = 30)]
+    pub heartbeat_rate_limit_burst: u32,
+
+    #[arg(long, default_value_t = 120)]
+    pub heartbeat_rate_limit_per_min: u32,
+
+    #[arg(long, default_value_t = 30)]
+    pub admin_rate_limit_burst: u32,
+
+    #[arg(long, default_value_t = 120)]
+    pub admin_rate_limit_per_min: u32,
 }
 
 impl Options {
+    #[inline]
+    pub fn shutdown_timeout(&self) -> Duration {
+        Duration::from_secs(self.shutdown_timeout_secs)
+    }
+
+    #[inline]
+    pub fn placement_timeout(&self) -> Duration {
+        Duration::from_secs(self.placement_timeout_secs)
+    }
+
+    #[inline]
+    pub fn reservation_timeout(&self) -> Duration {
+        Duration::from_secs(self.reservation_timeout_secs)
+    }
+
+    #[inline]
+    pub fn notif_ttl(&self) -> Duration {
+        Duration::from_secs(self.notif_ttl_secs)
+    }
+
+    #[inline]
+    pub fn find_server_lock_ttl(&self) -> Duration {
+        Duration::from_secs(self.find_server_lock_ttl_secs)
+    }
+
+    #[inline]
+    pub fn connect_token_expire(&self) -> Duration {
+        Duration::from_secs(self.connect_token_expire_secs)
+    }
+
+    #[inline]
+    pub fn metrics_update_interval(&self) -> Duration {
+        Duration::from_secs(self.metrics_update_interval_secs)
+    }
+
+    pub fn connect_token_key(&self) -> anyhow::Result<[u8; CONNECT_TOKEN_KEY_BYTES]> {
+        match &self.connect_token_key {
+            Some(key) => netcode::decode_connect_token_key(key),
+            None if self.insecure_dev_key => {
+                warn!("no connect token key set, using the insecure development key!");
+                netcode::decode_connect_token_key(netcode::DEV_CONNECT_TOKEN_KEY)
+            }
+            None => anyhow::bail!(
+                "no connect token key set (use --insecure-dev-key for local development)"
+            ),
+        }
+    }
+
+    pub fn find_server_rate_limit(&self) -> RateLimit {
+        RateLimit::new(
+            self.find_server_rate_limit_burst,
+            self.find_server_rate_limit_per_min,
+        )
+    }
+
+    pub fn heartbeat_rate_limit(&self) -> RateLimit {
+        RateLimit::new(
+            self.heartbeat_rate_limit_burst,
+            self.heartbeat_rate_limit_per_min,
+        )
+    }
+
+    pub fn admin_rate_limit(&self) -> RateLimit {
+        RateLimit::new(self.admin_rate_limit_burst, self.admin_rate_limit_per_min)
+    }
+
     pub fn address(&self) -> String {
         format!("{}:{}", self.host, self.port)
     }
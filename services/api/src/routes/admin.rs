@@ -0,0 +1,29 @@
+use axum::middleware;
+
+use internal::axum as axum_util;
+
+use crate::{handlers::admin::*, state::AppState};
+
+use super::ApiRouter;
+
+pub fn init_routes(app_state: &AppState) -> ApiRouter {
+    let admin_limiter = app_state.rate_limiter("admin", app_state.options.admin_rate_limit());
+
+    ApiRouter::default()
+        .route::<__path_get_list_gameservers_v1, _, _>(get_list_gameservers_v1)
+        .route::<__path_get_gameserver_v1, _, _>(get_gameserver_v1)
+        .route::<__path_get_gameserver_notifs_v1, _, _>(get_gameserver_notifs_v1)
+        .route::<__path_post_resend_gameserver_notif_v1, _, _>(post_resend_gameserver_notif_v1)
+        .route::<__path_post_drain_gameserver_v1, _, _>(post_drain_gameserver_v1)
+        .route::<__path_post_shutdown_gameserver_v1, _, _>(post_shutdown_gameserver_v1)
+        .route::<__path_get_list_gamesessions_v1, _, _>(get_list_gamesessions_v1)
+        .route::<__path_get_gamesession_v1, _, _>(get_gamesession_v1)
+        .route::<__path_post_end_gamesession_v1, _, _>(post_end_gamesession_v1)
+        .route::<__path_post_kick_player_v1, _, _>(post_kick_player_v1)
+        .map_router(|router| {
+            router.route_layer(middleware::from_fn_with_state(
+                admin_limiter,
+                axum_util::rate_limit,
+            ))
+        })
+}
@@ -1,7 +1,22 @@
-use axum::{routing::get, Router};
+use axum::middleware;
+
+use internal::axum as axum_util;
 
 use crate::{handlers::gameclient::*, state::AppState};
 
-pub fn init_routes(app: Router<AppState>) -> Router<AppState> {
-    app.route("/gameclient/find_server/v1", get(get_find_server_v1))
+use super::ApiRouter;
+
+pub fn init_routes(app_state: &AppState) -> ApiRouter {
+    let find_server_limiter =
+        app_state.rate_limiter("find_server", app_state.options.find_server_rate_limit());
+
+    ApiRouter::default().route_with::<__path_get_find_server_v1, _, _>(
+        get_find_server_v1,
+        |method_router| {
+            method_router.layer(middleware::from_fn_with_state(
+                find_server_limiter,
+                axum_util::rate_limit,
+            ))
+        },
+    )
 }
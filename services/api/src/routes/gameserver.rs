@@ -1,7 +1,22 @@
-use axum::{routing::post, Router};
+use axum::middleware;
+
+use internal::axum as axum_util;
 
 use crate::{handlers::gameserver::*, state::AppState};
 
-pub fn init_routes(app: Router<AppState>) -> Router<AppState> {
-    app.route("/gameserver/heartbeat/v1", post(post_heartbeat_v1))
+use super::ApiRouter;
+
+pub fn init_routes(app_state: &AppState) -> ApiRouter {
+    let heartbeat_limiter =
+        app_state.rate_limiter("heartbeat", app_state.options.heartbeat_rate_limit());
+
+    ApiRouter::default().route_with::<__path_post_heartbeat_v1, _, _>(
+        post_heartbeat_v1,
+        |method_router| {
+            method_router.layer(middleware::from_fn_with_state(
+                heartbeat_limiter,
+                axum_util::rate_limit,
+            ))
+        },
+    )
 }
@@ -0,0 +1,11 @@
+use internal::axum as axum_util;
+
+use crate::handlers::health::*;
+
+use super::ApiRouter;
+
+pub fn init_routes() -> ApiRouter {
+    ApiRouter::default()
+        .route::<axum_util::__path_handler_healthz, _, _>(axum_util::handler_healthz)
+        .route::<__path_get_readyz, _, _>(get_readyz)
+}
@@ -1,19 +1,127 @@
+mod admin;
 mod gameclient;
 mod gameserver;
+mod health;
+mod openapi;
 
-use axum::Router;
+use std::collections::HashSet;
+
+use axum::{
+    handler::Handler,
+    routing::{on, MethodFilter, MethodRouter},
+    Router,
+};
 use tracing::info;
+use utoipa::{openapi::path::HttpMethod, OpenApi};
 
 use internal::axum as axum_util;
 
-use crate::state::AppState;
+use crate::{openapi::ApiDoc, state::AppState};
+
+// routes take their path and methods from the handler's #[utoipa::path]
+// and every documented path has to be routed, so the router can't drift from the api description
+#[derive(Default)]
+pub struct ApiRouter {
+    router: Router<AppState>,
+    routed: HashSet<String>,
+}
+
+impl ApiRouter {
+    pub fn route<P, H, T>(self, handler: H) -> Self
+    where
+        P: utoipa::Path,
+        H: Handler<T, AppState>,
+        T: 'static,
+    {
+        self.route_with::<P, H, T>(handler, |method_router| method_router)
+    }
+
+    // for layers that only apply to this route
+    pub fn route_with<P, H, T>(
+        mut self,
+        handler: H,
+        f: impl FnOnce(MethodRouter<AppState>) -> MethodRouter<AppState>,
+    ) -> Self
+    where
+        P: utoipa::Path,
+        H: Handler<T, AppState>,
+        T: 'static,
+    {
+        let path = P::path();
+
+        let method_filter = P::methods()
+            .into_iter()
+            .map(method_filter)
+            .reduce(MethodFilter::or)
+            .unwrap_or_else(|| panic!("No methods for route {}", path));
+
+        self.router = self
+            .router
+            .route(&axum_path(&path), f(on(method_filter, handler)));
+        self.routed.insert(path);
+
+        self
+    }
+
+    // for layers that apply to every route so far
+    pub fn map_router(mut self, f: impl FnOnce(Router<AppState>) -> Router<AppState>) -> Self {
+        self.router = f(self.router);
+        self
+    }
+
+    pub fn merge(mut self, other: ApiRouter) -> Self {
+        self.router = self.router.merge(other.router);
+        self.routed.extend(other.routed);
+        self
+    }
+
+    pub fn into_router(self) -> Router<AppState> {
+        let openapi = ApiDoc::openapi();
+
+        for path in openapi.paths.paths.keys() {
+            if !self.routed.contains(path) {
+                panic!("Documented path {} is not routed", path);
+            }
+        }
+
+        for path in &self.routed {
+            if !openapi.paths.paths.contains_key(path) {
+                panic!("Routed path {} is not documented", path);
+            }
+        }
+
+        self.router
+    }
+}
+
+fn method_filter(method: HttpMethod) -> MethodFilter {
+    match method {
+        HttpMethod::Get => MethodFilter::GET,
+        HttpMethod::Post => MethodFilter::POST,
+        HttpMethod::Put => MethodFilter::PUT,
+        HttpMethod::Delete => MethodFilter::DELETE,
+        HttpMethod::Options => MethodFilter::OPTIONS,
+        HttpMethod::Head => MethodFilter::HEAD,
+        HttpMethod::Patch => MethodFilter::PATCH,
+        HttpMethod::Trace => MethodFilter::TRACE,
+    }
+}
+
+// openapi path parameters are {name}, axum's are :name
+fn axum_path(path: &str) -> String {
+    path.replace('{', ":").replace('}', "")
+}
 
-pub fn init_routes(app: Router<AppState>) -> Router<AppState> {
+pub fn init_routes(app_state: &AppState) -> Router<AppState> {
     info!("initializing routes...");
 
-    // TODO: this is ugly
-    let app = gameclient::init_routes(app);
-    let app = gameserver::init_routes(app);
+    let app = ApiRouter::default()
+        .merge(gameclient::init_routes(app_state))
+        .merge(gameserver::init_routes(app_state))
+        .merge(health::init_routes())
+        .merge(admin::init_routes(app_state))
+        .merge(openapi::init_routes())
+        .route::<axum_util::__path_handler_metrics, _, _>(axum_util::handler_metrics);
 
-    app.fallback(axum_util::handler_404)
+    app.into_router().fallback(axum_util::handler_404)
 }
@@ -0,0 +1,7 @@
+use crate::handlers::openapi::*;
+
+use super::ApiRouter;
+
+pub fn init_routes() -> ApiRouter {
+    ApiRouter::default().route::<__path_get_openapi, _, _>(get_openapi)
+}
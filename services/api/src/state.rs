@@ -1,20 +1,46 @@
 use std::sync::Arc;
 
+use common::netcode::CONNECT_TOKEN_KEY_BYTES;
+use internal::{
+    axum::{RateLimit, RateLimiter},
+    shutdown::ShutdownReceiver,
+};
+
 use crate::{options::Options, redis::RedisConnection};
 
 #[derive(Clone)]
 pub struct AppState {
-    #[allow(dead_code)]
     pub options: Arc<Options>,
 
     pub redis_connection: RedisConnection,
+
+    pub connect_token_key: [u8; CONNECT_TOKEN_KEY_BYTES],
+
+    pub shutdown: ShutdownReceiver,
 }
 
 impl AppState {
-    pub fn new(options: Options, redis_connection: RedisConnection) -> Self {
+    pub fn new(
+        options: Options,
+        redis_connection: RedisConnection,
+        connect_token_key: [u8; CONNECT_TOKEN_KEY_BYTES],
+        shutdown: ShutdownReceiver,
+    ) -> Self {
         Self {
             options: Arc::new(options),
             redis_connection,
+            connect_token_key,
+            shutdown,
         }
     }
+
+    pub fn rate_limiter(&self, name: &str, limit: RateLimit) -> RateLimiter {
+        RateLimiter::new(
+            name,
+            limit,
+            self.redis_connection.clone(),
+            self.options.trusted_proxies.clone(),
+            !self.options.disable_rate_limit,
+        )
+    }
 }
@@ -3,23 +3,22 @@ use axum::{
     extract::{ws::WebSocketUpgrade, State},
     response::IntoResponse,
 };
-use axum_extra::TypedHeader;
-use headers::authorization::{Authorization, Bearer};
 use tracing::{error, info};
 use uuid::Uuid;
 
-use internal::axum::AppError;
+use internal::axum::{extract::BearerAuth, AppError};
 
 use crate::{notifs, AppState};
 
 #[debug_handler]
 pub async fn get_subscribe_notifs(
-    TypedHeader(bearer): TypedHeader<Authorization<Bearer>>,
+    bearer: BearerAuth,
     State(app_state): State<AppState>,
     ws: WebSocketUpgrade,
 ) -> Result<impl IntoResponse, AppError> {
     // TODO: validate the client token
-    let user_id = Uuid::parse_str(bearer.token())?;
+    let user_id = Uuid::parse_str(bearer.token())
+        .map_err(|_| AppError::unauthorized("Invalid client token"))?;
 
     info!("{} subscribing to notifications ...", user_id);
 
@@ -0,0 +1,27 @@
+use axum::{debug_handler, extract::State, http::StatusCode, response::IntoResponse};
+use redis::AsyncCommands;
+use tracing::warn;
+
+use internal::shutdown::is_shutting_down;
+
+use crate::state::AppState;
+
+#[debug_handler]
+pub async fn get_readyz(State(mut app_state): State<AppState>) -> impl IntoResponse {
+    if is_shutting_down(&app_state.shutdown) {
+        return (StatusCode::SERVICE_UNAVAILABLE, "Shutting down");
+    }
+
+    let pong: redis::RedisResult<String> = app_state.redis_connection.ping().await;
+    if let Err(err) = pong {
+        warn!("readiness check failed, redis unavailable: {}", err);
+        return (StatusCode::SERVICE_UNAVAILABLE, "Redis unavailable");
+    }
+
+    if !app_state.gameserver_listener.is_running() || !app_state.gameclient_listener.is_running() {
+        warn!("readiness check failed, notifs listener not running");
+        return (StatusCode::SERVICE_UNAVAILABLE, "Listener not running");
+    }
+
+    (StatusCode::OK, "OK")
+}
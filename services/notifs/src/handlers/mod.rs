@@ -1,2 +1,3 @@
 pub mod gameclient;
 pub mod gameserver;
+pub mod health;
@@ -1,12 +1,20 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
 use axum::extract::ws::Message;
 use futures_util::{SinkExt, StreamExt};
-use tokio::task;
-use tracing::{debug, info};
+use redis::aio::PubSubStream;
+use tokio::{
+    sync::RwLock,
+    task,
+    time::{sleep, Duration},
+};
+use tracing::{debug, info, warn};
 use uuid::Uuid;
 
-use internal::notifs::Notification;
+use internal::{axum::OptFmt, notifs::Notification};
 
-use crate::AppState;
+use crate::{notifs::NotifSender, state::ListenerStatus, AppState};
 
 /*
 TODO:
@@ -21,80 +29,144 @@ to the sender because it can know sooner in the flow if
 the recipient is available or not
  */
 
-pub async fn start_gameclient_listener(
-    app_state: &AppState,
-) -> anyhow::Result<task::JoinHandle<()>> {
-    info!("starting game client notifs listener ...");
+async fn subscribe(redis_host: &str, channel: &'static str) -> anyhow::Result<PubSubStream> {
+    let client = redis::Client::open(redis_host)?;
+    let (mut sink, stream) = client.get_async_pubsub().await?.split();
+    sink.subscribe(channel).await?;
 
-    let client = redis::Client::open(app_state.options.redis_host.as_str())?;
-    let (mut sink, mut stream) = client.get_async_pubsub().await?.split();
-    sink.subscribe(internal::GAMECLIENT_NOTIFS_CHANNEL)
-        .await
-        .unwrap();
+    Ok(stream)
+}
 
-    let game_clients = app_state.game_clients.clone();
-    Ok(task::spawn(async move {
-        // TODO: error handling
-        loop {
-            let msg = stream.next().await.unwrap();
-            let payload: String = msg.get_payload().unwrap();
-            debug!(
-                "got game client notif: {} (channel: {})",
-                payload,
-                msg.get_channel_name()
-            );
+async fn dispatch(
+    recipients: &RwLock<HashMap<Uuid, NotifSender>>,
+    recipient_type: &'static str,
+    payload: String,
+) -> anyhow::Result<()> {
+    let notif: Notification = serde_json::from_str(&payload)?;
+    let recipient = Uuid::parse_str(&notif.recipient)?;
 
-            let notif: Notification = serde_json::from_str(&payload).unwrap();
-            let recipient = Uuid::parse_str(&notif.recipient).unwrap();
+    let mut recipients = recipients.write().await;
+    if let Some(sender) = recipients.get_mut(&recipient) {
+        info!(
+            "notifying {} {} of {} (request {})",
+            recipient_type,
+            recipient,
+            notif.id,
+            OptFmt(notif.request_id.as_ref())
+        );
 
-            {
-                let mut game_clients = game_clients.write().await;
-                if let Some(sender) = game_clients.get_mut(&recipient) {
-                    info!("notifying game client {}", recipient);
-                    sender.send(Message::Text(payload)).await.unwrap();
-                } else {
-                    debug!("ignoring notif for {}", recipient);
-                }
-            }
+        if let Err(err) = sender.send(Message::Text(payload)).await {
+            metrics::counter!("notifs_dropped_total", "recipient_type" => recipient_type, "reason" => "send_failed").increment(1);
+            anyhow::bail!("failed to notify {} {}: {}", recipient_type, recipient, err);
         }
-    }))
+
+        metrics::counter!("notifs_delivered_total", "recipient_type" => recipient_type)
+            .increment(1);
+    } else {
+        debug!(
+            "ignoring notif {} for {} (request {})",
+            notif.id,
+            recipient,
+            OptFmt(notif.request_id.as_ref())
+        );
+
+        metrics::counter!("notifs_dropped_total", "recipient_type" => recipient_type, "reason" => "not_connected").increment(1);
+    }
+
+    Ok(())
 }
 
-pub async fn start_gameserver_listener(
-    app_state: &AppState,
+async fn start_listener(
+    redis_host: String,
+    reconnect_interval: Duration,
+    channel: &'static str,
+    recipients: Arc<RwLock<HashMap<Uuid, NotifSender>>>,
+    recipient_type: &'static str,
+    status: ListenerStatus,
 ) -> anyhow::Result<task::JoinHandle<()>> {
-    info!("starting game server notifs listener ...");
-
-    let client = redis::Client::open(app_state.options.redis_host.as_str())?;
-    let (mut sink, mut stream) = client.get_async_pubsub().await?.split();
-    sink.subscribe(internal::GAMESERVER_NOTIFS_CHANNEL)
-        .await
-        .unwrap();
+    // fail fast if we can't subscribe at startup
+    let mut stream = subscribe(&redis_host, channel).await?;
+    status.set_running(true);
 
-    let game_servers = app_state.game_servers.clone();
     Ok(task::spawn(async move {
-        // TODO: error handling
         loop {
-            let msg = stream.next().await.unwrap();
-            let payload: String = msg.get_payload().unwrap();
-            debug!(
-                "got game server notif: {} (channel: {})",
-                payload,
-                msg.get_channel_name()
+            while let Some(msg) = stream.next().await {
+                let payload: String = match msg.get_payload() {
+                    Ok(payload) => payload,
+                    Err(err) => {
+                        warn!("invalid {} notif payload: {}", recipient_type, err);
+                        continue;
+                    }
+                };
+                debug!(
+                    "got {} notif: {} (channel: {})",
+                    recipient_type,
+                    payload,
+                    msg.get_channel_name()
+                );
+
+                if let Err(err) = dispatch(&recipients, recipient_type, payload).await {
+                    warn!("failed to dispatch {} notif: {}", recipient_type, err);
+                }
+            }
+
+            warn!(
+                "{} notifs subscription closed, reconnecting ...",
+                recipient_type
             );
+            status.set_running(false);
 
-            let notif: Notification = serde_json::from_str(&payload).unwrap();
-            let recipient = Uuid::parse_str(&notif.recipient).unwrap();
+            loop {
+                metrics::counter!("notifs_listener_reconnects_total", "recipient_type" => recipient_type).increment(1);
 
-            {
-                let mut game_servers = game_servers.write().await;
-                if let Some(sender) = game_servers.get_mut(&recipient) {
-                    info!("notifying game server {}", recipient);
-                    sender.send(Message::Text(payload)).await.unwrap();
-                } else {
-                    debug!("ignoring notif for {}", recipient);
+                match subscribe(&redis_host, channel).await {
+                    Ok(new_stream) => {
+                        info!("{} notifs listener reconnected", recipient_type);
+                        stream = new_stream;
+                        status.set_running(true);
+                        break;
+                    }
+                    Err(err) => {
+                        warn!(
+                            "failed to reconnect {} notifs listener: {}",
+                            recipient_type, err
+                        );
+                        sleep(reconnect_interval).await;
+                    }
                 }
             }
         }
     }))
 }
+
+pub async fn start_gameclient_listener(
+    app_state: &AppState,
+) -> anyhow::Result<task::JoinHandle<()>> {
+    info!("starting game client notifs listener ...");
+
+    start_listener(
+        app_state.options.redis_host.clone(),
+        app_state.options.listener_reconnect_interval(),
+        internal::GAMECLIENT_NOTIFS_CHANNEL,
+        app_state.game_clients.clone(),
+        "gameclient",
+        app_state.gameclient_listener.clone(),
+    )
+    .await
+}
+
+pub async fn start_gameserver_listener(
+    app_state: &AppState,
+) -> anyhow::Result<task::JoinHandle<()>> {
+    info!("starting game server notifs listener ...");
+
+    start_listener(
+        app_state.options.redis_host.clone(),
+        app_state.options.listener_reconnect_interval(),
+        internal::GAMESERVER_NOTIFS_CHANNEL,
+        app_state.game_servers.clone(),
+        "gameserver",
+        app_state.gameserver_listener.clone(),
+    )
+    .await
+}
@@ -11,17 +11,16 @@ use axum::{
     http::{HeaderValue, Method},
     middleware, Router,
 };
-use clap::Parser;
 use tower::ServiceBuilder;
 use tower_http::{
     cors::CorsLayer,
     trace::{DefaultMakeSpan, DefaultOnFailure, TraceLayer},
     LatencyUnit,
 };
-use tracing::{info, Level};
-use tracing_subscriber::FmtSubscriber;
+use tracing::{info, warn};
 
-use internal::axum as axum_util;
+use common::config;
+use internal::{axum as axum_util, logging, redis, shutdown};
 
 use listener::{start_gameclient_listener, start_gameserver_listener};
 use options::Options;
@@ -29,27 +28,6 @@ use state::AppState;
 
 // TODO: add authentication
 
-fn init_logging() -> anyhow::Result<()> {
-    let subscriber = FmtSubscriber::builder()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| {
-                // axum logs rejections from built-in extractors with the `axum::rejection`
-                // target, at `TRACE` level. `axum::rejection=trace` enables showing those events
-                format!(
-                    "{}=debug,tower_http=debug,axum::rejection=trace",
-                    env!("CARGO_CRATE_NAME")
-                )
-                .into()
-            }),
-        )
-        .with_max_level(Level::INFO)
-        .finish();
-
-    tracing::subscriber::set_global_default(subscriber)?;
-
-    Ok(())
-}
-
 fn init_cors_layer() -> anyhow::Result<CorsLayer> {
     info!("initializing CORS layer...");
 
@@ -62,14 +40,21 @@ fn init_cors_layer() -> anyhow::Result<CorsLayer> {
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    let options = Options::parse();
+    let options: Options = config::load(options::ENV_PREFIX)?;
 
-    init_logging()?;
+    logging::init_logging(env!("CARGO_CRATE_NAME"), &options.logging)?;
+    axum_util::init_metrics()?;
 
-    let app_state = AppState::new(options);
+    let redis_connection = redis::connect(options.redis_host.clone()).await?;
+
+    let shutdown = shutdown::init_shutdown_signal();
+
+    let app_state = AppState::new(options, redis_connection, shutdown.clone());
     start_gameclient_listener(&app_state).await?;
     start_gameserver_listener(&app_state).await?;
 
+    let shutdown_timeout = app_state.options.shutdown_timeout();
+
     let addr = app_state
         .options
         .address()
@@ -81,6 +66,7 @@ async fn main() -> anyhow::Result<()> {
         .layer(
             ServiceBuilder::new()
                 .layer(middleware::from_fn(axum_util::tracing_wrapper))
+                .layer(middleware::from_fn(axum_util::metrics_wrapper))
                 .layer(
                     TraceLayer::new_for_http()
                         .make_span_with(
@@ -94,13 +80,33 @@ async fn main() -> anyhow::Result<()> {
                 )
                 .into_inner(),
         )
-        .with_state(app_state);
+        .with_state(app_state.clone());
 
     let listener = tokio::net::TcpListener::bind(addr).await?;
     info!("listening on {}", listener.local_addr()?);
-    Ok(axum::serve(
+
+    // websockets aren't tracked by the graceful shutdown
+    // so close them before we stop accepting connections
+    let server = axum::serve(
         listener,
         app.into_make_service_with_connect_info::<SocketAddr>(),
     )
-    .await?)
+    .with_graceful_shutdown({
+        let shutdown = shutdown.clone();
+        async move {
+            shutdown::wait_for_shutdown(shutdown).await;
+            notifs::close_all_notifs(&app_state).await;
+        }
+    });
+
+    tokio::select! {
+        res = server.into_future() => res?,
+        _ = shutdown::shutdown_deadline(shutdown, shutdown_timeout) => {
+            warn!("shutdown timeout exceeded, dropping connections");
+        }
+    }
+
+    info!("shutdown complete");
+
+    Ok(())
 }
@@ -1,14 +1,17 @@
-use axum::extract::ws::{Message, WebSocket};
+use std::collections::HashMap;
+
+use axum::extract::ws::{close_code, CloseFrame, Message, WebSocket};
 use futures_util::{
     stream::{SplitSink, SplitStream},
-    StreamExt,
+    SinkExt, StreamExt,
 };
-use tracing::info;
+use tokio::sync::RwLock;
+use tracing::{info, warn};
 use uuid::Uuid;
 
 use common::user::UserId;
 
-use crate::state::{GameClientSet, GameServerSet};
+use crate::state::{AppState, GameClientSet, GameServerSet};
 
 pub type NotifSender = SplitSink<WebSocket, Message>;
 
@@ -30,13 +33,19 @@ pub async fn handle_gameserver_notifs(
     info!("{} subscribed to notifications ...", server_id);
 
     let (sender, receiver) = socket.split();
-    game_servers.write().await.insert(server_id, sender);
+    {
+        let mut game_servers = game_servers.write().await;
+        game_servers.insert(server_id, sender);
+        metrics::gauge!("notifs_connected_gameservers").set(game_servers.len() as f64);
+    }
 
     idle_notifs(receiver).await;
 
     info!("{} closed notifications connection", server_id);
 
-    game_servers.write().await.remove(&server_id);
+    let mut game_servers = game_servers.write().await;
+    game_servers.remove(&server_id);
+    metrics::gauge!("notifs_connected_gameservers").set(game_servers.len() as f64);
 }
 
 pub async fn handle_gameclient_notifs(
@@ -47,11 +56,42 @@ pub async fn handle_gameclient_notifs(
     info!("{} subscribed to notifications ...", user_id);
 
     let (sender, receiver) = socket.split();
-    game_clients.write().await.insert(user_id, sender);
+    {
+        let mut game_clients = game_clients.write().await;
+        game_clients.insert(user_id, sender);
+        metrics::gauge!("notifs_connected_gameclients").set(game_clients.len() as f64);
+    }
 
     idle_notifs(receiver).await;
 
     info!("{} closed notifications connection", user_id);
 
-    game_clients.write().await.remove(&user_id);
+    let mut game_clients = game_clients.write().await;
+    game_clients.remove(&user_id);
+    metrics::gauge!("notifs_connected_gameclients").set(game_clients.len() as f64);
+}
+
+async fn close_notifs(senders: &RwLock<HashMap<Uuid, NotifSender>>) {
+    for (id, sender) in senders.write().await.iter_mut() {
+        // going away lets the other side know to reconnect elsewhere
+        let res = sender
+            .send(Message::Close(Some(CloseFrame {
+                code: close_code::AWAY,
+                reason: "server shutting down".into(),
+            })))
+            .await;
+        if let Err(err) = res {
+            warn!(
+                "failed to close notifications connection for {}: {}",
+                id, err
+            );
+        }
+    }
+}
+
+pub async fn close_all_notifs(app_state: &AppState) {
+    info!("closing notifications connections ...");
+
+    close_notifs(&app_state.game_servers).await;
+    close_notifs(&app_state.game_clients).await;
 }
@@ -1,7 +1,19 @@
+use std::time::Duration;
+
 use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+use common::config::ConfigArgs;
+use internal::logging::LoggingOptions;
+
+pub const ENV_PREFIX: &str = "BMP_NOTIFS_";
 
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Serialize, Deserialize)]
 pub struct Options {
+    #[command(flatten)]
+    #[serde(skip)]
+    pub config: ConfigArgs,
+
     #[arg(long, default_value = "0.0.0.0")]
     pub host: String,
 
@@ -10,10 +22,31 @@ pub struct Options {
 
     #[arg(long, default_value = "redis://localhost/")]
     pub redis_host: String,
+
+    #[command(flatten)]
+    #[serde(flatten)]
+    pub logging: LoggingOptions,
+
+    #[arg(long, default_value_t = 10)]
+    pub shutdown_timeout_secs: u64,
+
+    // how long to wait between redis resubscribe attempts
+    #[arg(long, default_value_t = 5)]
+    pub listener_reconnect_interval_secs: u64,
 }
 
 impl Options {
     pub fn address(&self) -> String {
         format!("{}:{}", self.host, self.port)
     }
+
+    #[inline]
+    pub fn shutdown_timeout(&self) -> Duration {
+        Duration::from_secs(self.shutdown_timeout_secs)
+    }
+
+    #[inline]
+    pub fn listener_reconnect_interval(&self) -> Duration {
+        Duration::from_secs(self.listener_reconnect_interval_secs)
+    }
 }
@@ -0,0 +1,10 @@
+use axum::{routing::get, Router};
+
+use internal::axum as axum_util;
+
+use crate::{handlers::health::*, state::AppState};
+
+pub fn init_routes(app: Router<AppState>) -> Router<AppState> {
+    app.route("/healthz", get(axum_util::handler_healthz))
+        .route("/readyz", get(get_readyz))
+}
@@ -1,7 +1,8 @@
 mod gameclient;
 mod gameserver;
+mod health;
 
-use axum::Router;
+use axum::{routing::get, Router};
 use tracing::info;
 
 use internal::axum as axum_util;
@@ -14,6 +15,8 @@ pub fn init_routes(app: Router<AppState>) -> Router<AppState> {
     // TODO: this is ugly
     let app = gameclient::init_routes(app);
     let app = gameserver::init_routes(app);
+    let app = health::init_routes(app);
 
-    app.fallback(axum_util::handler_404)
+    app.route("/metrics", get(axum_util::handler_metrics))
+        .fallback(axum_util::handler_404)
 }
@@ -1,32 +1,66 @@
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
 
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
 use common::user::UserId;
+use internal::{redis::RedisConnection, shutdown::ShutdownReceiver};
 
 use crate::options::Options;
 
 pub type GameServerSet = Arc<RwLock<HashMap<Uuid, crate::notifs::NotifSender>>>;
 pub type GameClientSet = Arc<RwLock<HashMap<UserId, crate::notifs::NotifSender>>>;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Default, Clone)]
+pub struct ListenerStatus(Arc<AtomicBool>);
+
+impl ListenerStatus {
+    #[inline]
+    pub fn is_running(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    #[inline]
+    pub fn set_running(&self, running: bool) {
+        self.0.store(running, Ordering::Relaxed);
+    }
+}
+
+#[derive(Clone)]
 pub struct AppState {
-    #[allow(dead_code)]
     pub options: Arc<Options>,
 
+    pub redis_connection: RedisConnection,
+
+    pub shutdown: ShutdownReceiver,
+
     pub game_servers: GameServerSet,
     pub game_clients: GameClientSet,
+
+    pub gameserver_listener: ListenerStatus,
+    pub gameclient_listener: ListenerStatus,
 }
 
 impl AppState {
-    pub fn new(options: Options) -> Self {
+    pub fn new(
+        options: Options,
+        redis_connection: RedisConnection,
+        shutdown: ShutdownReceiver,
+    ) -> Self {
         Self {
             options: Arc::new(options),
+            redis_connection,
+            shutdown,
 
             game_servers: Arc::new(RwLock::new(HashMap::new())),
             game_clients: Arc::new(RwLock::new(HashMap::new())),
+
+            gameserver_listener: ListenerStatus::default(),
+            gameclient_listener: ListenerStatus::default(),
         }
     }
 }
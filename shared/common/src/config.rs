@@ -0,0 +1,99 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use clap::{parser::ValueSource, Args, CommandFactory, FromArgMatches};
+use figment::{
+    providers::{Env, Format, Serialized, Toml},
+    value::{Dict, Value},
+    Figment,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+const REDACTED: &str = "<redacted>";
+
+// flattened into each binary's Options
+#[derive(Args, Debug, Default, Clone)]
+pub struct ConfigArgs {
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+
+    #[arg(long)]
+    pub print_config: bool,
+}
+
+// loads the binary's options from (lowest to highest priority)
+//   defaults -> TOML config file -> {env_prefix}* env vars -> CLI flags
+//
+// the config file can also be set with the {env_prefix}CONFIG env var
+// CLI flags with their own env var (like ADMIN_TOKEN) are treated as CLI flags
+pub fn load<T>(env_prefix: &str) -> anyhow::Result<T>
+where
+    T: CommandFactory + FromArgMatches + Serialize + DeserializeOwned,
+{
+    let command = T::command();
+    let arg_ids = command
+        .get_arguments()
+        .map(|arg| arg.get_id().to_string())
+        .collect::<HashSet<_>>();
+
+    let matches = command.get_matches();
+    let options = T::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+
+    // only the values that were actually passed override the lower layers,
+    // everything else is a clap default
+    let mut overrides = Dict::new();
+    if let Value::Dict(_, values) = Value::serialize(&options)? {
+        for (key, value) in values {
+            if arg_ids.contains(&key)
+                && matches!(
+                    matches.value_source(&key),
+                    Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+                )
+            {
+                overrides.insert(key, value);
+            }
+        }
+    }
+
+    let config_path = matches
+        .get_one::<PathBuf>("config")
+        .cloned()
+        .or_else(|| std::env::var_os(format!("{}CONFIG", env_prefix)).map(PathBuf::from));
+
+    let mut figment = Figment::from(Serialized::defaults(&options));
+    if let Some(config_path) = config_path {
+        if !config_path.exists() {
+            anyhow::bail!("config file {} not found", config_path.display());
+        }
+        figment = figment.merge(Toml::file_exact(config_path));
+    }
+
+    let options: T = figment
+        .merge(Env::prefixed(env_prefix).ignore(&["config"]))
+        .merge(Serialized::defaults(overrides))
+        .extract()?;
+
+    if matches.get_flag("print_config") {
+        let mut printed = toml::Value::try_from(&options)?;
+        redact_secrets(&mut printed);
+        print!("{}", toml::to_string_pretty(&printed)?);
+        std::process::exit(0);
+    }
+
+    Ok(options)
+}
+
+// secrets are named *_token or *_key, mask them so printed configs can be shared
+fn redact_secrets(value: &mut toml::Value) {
+    let toml::Value::Table(table) = value else {
+        return;
+    };
+
+    for (key, value) in table.iter_mut() {
+        if key.ends_with("_token") || key.ends_with("_key") {
+            *value = toml::Value::String(REDACTED.to_owned());
+        } else {
+            redact_secrets(value);
+        }
+    }
+}
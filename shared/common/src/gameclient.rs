@@ -1,10 +1,14 @@
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct FindServerResponseV1 {
-    // TODO: set all of the addresses
-    // and let the client pick the one to try
-    // (or try them all)
-    pub address: String,
+    // every address the server is reachable at,
+    // the client picks which ones to try
+    pub v4addrs: Vec<String>,
+    pub v6addrs: Vec<String>,
     pub port: u16,
+
+    // base64 encoded netcode connect token for the server
+    pub connect_token: String,
 }
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -6,6 +8,7 @@ use crate::user::UserId;
 // TODO: things not shared with the client should be moved to the internal lib
 
 #[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[serde(rename_all = "lowercase")]
 pub enum GameServerState {
     #[default]
@@ -13,10 +16,13 @@ pub enum GameServerState {
     WaitingForPlacement,
     Loading,
     InGame,
+    // finishing the current session, not taking new players
+    Draining,
     Shutdown,
 }
 
 #[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[serde(rename_all = "lowercase")]
 pub enum GameServerOrchestration {
     #[default]
@@ -26,6 +32,7 @@ pub enum GameServerOrchestration {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct GameServerInfo {
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     pub v4addrs: Vec<String>,
@@ -36,27 +43,56 @@ pub struct GameServerInfo {
     pub state: GameServerState,
     pub orchestration: GameServerOrchestration,
 
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub region: Option<String>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub game_session_info: Option<GameSessionInfo>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct GameSessionInfo {
     pub game_session_id: Uuid,
 
     pub max_players: u16,
 
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    #[cfg_attr(feature = "openapi", schema(value_type = Vec<Uuid>))]
     pub active_player_ids: Vec<UserId>,
 
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    #[cfg_attr(feature = "openapi", schema(value_type = Vec<Uuid>))]
     pub pending_player_ids: Vec<UserId>,
+
+    // last measured round trip time of each active player
+    #[serde(skip_serializing_if = "HashMap::is_empty", default)]
+    #[cfg_attr(feature = "openapi", schema(value_type = HashMap<Uuid, u32>))]
+    pub player_rtt_ms: HashMap<UserId, u32>,
+
+    // ticks simulated without a player's input this session
+    #[serde(default)]
+    pub input_buffer_underruns: u32,
+
+    // player input dropped for getting too far ahead this session
+    #[serde(default)]
+    pub input_buffer_overruns: u32,
+
+    // ticks the simulation fell behind real time this session
+    #[serde(default)]
+    pub tick_overruns: u32,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct PostHeartbeatRequestV1 {
     pub server_info: GameServerInfo,
+
+    // id of the request this heartbeat is acknowledging, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct PostHeartbeatResponseV1 {}
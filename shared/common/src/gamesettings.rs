@@ -1,10 +1,37 @@
-#[derive(Debug)]
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct GameSettings {
     pub max_players: u16,
+
+    // idle players are kicked after this long, 0 to never kick
+    pub idle_kick_secs: u64,
+
+    // how long before the kick idle players are warned
+    pub idle_warning_secs: u64,
 }
 
 impl Default for GameSettings {
     fn default() -> Self {
-        Self { max_players: 3 }
+        Self {
+            max_players: 3,
+            idle_kick_secs: 120,
+            idle_warning_secs: 30,
+        }
+    }
+}
+
+impl GameSettings {
+    #[inline]
+    pub fn idle_kick_timeout(&self) -> Option<Duration> {
+        (self.idle_kick_secs > 0).then(|| Duration::from_secs(self.idle_kick_secs))
+    }
+
+    #[inline]
+    pub fn idle_warning(&self) -> Duration {
+        Duration::from_secs(self.idle_warning_secs)
     }
 }
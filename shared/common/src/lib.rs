@@ -1,23 +1,48 @@
+pub mod config;
+mod error;
 pub mod gameclient;
 pub mod gameserver;
 mod gamesettings;
+pub mod netcode;
 pub mod user;
 
 use bevy_mod_reqwest::*;
 use tracing::error;
 
+pub use error::*;
 pub use gamesettings::*;
 
-pub fn check_reqwest_error(response: &ReqwestResponseEvent) -> bool {
+// correlates a request across the services and game server
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+pub fn read_reqwest_error(response: &ReqwestResponseEvent) -> Option<ErrorResponse> {
     if response.status().is_success() {
-        return true;
+        return None;
     }
 
-    error!(
-        "got error response {}: {}",
-        response.status(),
-        response.as_str().unwrap_or("invalid response")
-    );
+    Some(
+        response
+            .as_str()
+            .and_then(ErrorResponse::decode)
+            .unwrap_or_else(|| {
+                ErrorResponse::new(
+                    ErrorCode::Unknown,
+                    response.as_str().unwrap_or("invalid response"),
+                )
+            }),
+    )
+}
+
+pub fn check_reqwest_error(response: &ReqwestResponseEvent) -> bool {
+    if let Some(err) = read_reqwest_error(response) {
+        error!(
+            "got error response {} ({:?}): {}",
+            response.status(),
+            err.code,
+            err.message
+        );
+        return false;
+    }
 
-    false
+    true
 }
@@ -0,0 +1,54 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+use crate::user::UserId;
+
+pub const PROTOCOL_ID: u64 = 0;
+
+// sizes match renetcode's NETCODE_KEY_BYTES and NETCODE_USER_DATA_BYTES
+pub const CONNECT_TOKEN_KEY_BYTES: usize = 32;
+pub const CONNECT_TOKEN_USER_DATA_BYTES: usize = 256;
+
+// only for local development, real deployments must set their own key
+// TODO: pull this from a secrets store
+pub const DEV_CONNECT_TOKEN_KEY: &str = "ZGV2LWtleS1kby1ub3QtdXNlLWluLXByb2R1Y3Rpb24=";
+
+// private key shared by the api (to sign tokens) and game servers (to validate them)
+pub fn decode_connect_token_key(key: &str) -> anyhow::Result<[u8; CONNECT_TOKEN_KEY_BYTES]> {
+    let key = STANDARD.decode(key.trim())?;
+    key.try_into().map_err(|key: Vec<u8>| {
+        anyhow::anyhow!(
+            "connect token key must be {} bytes, got {}",
+            CONNECT_TOKEN_KEY_BYTES,
+            key.len()
+        )
+    })
+}
+
+#[inline]
+pub fn encode_connect_token(token: &[u8]) -> String {
+    STANDARD.encode(token)
+}
+
+#[inline]
+pub fn decode_connect_token(token: &str) -> anyhow::Result<Vec<u8>> {
+    Ok(STANDARD.decode(token)?)
+}
+
+// netcode client ids just need to be unique per server
+#[inline]
+pub fn client_id_for_user(user_id: UserId) -> u64 {
+    let (hi, lo) = user_id.as_u64_pair();
+    hi ^ lo
+}
+
+pub fn encode_user_data(user_id: UserId) -> [u8; CONNECT_TOKEN_USER_DATA_BYTES] {
+    let mut user_data = [0; CONNECT_TOKEN_USER_DATA_BYTES];
+    user_data[..16].copy_from_slice(user_id.as_bytes());
+    user_data
+}
+
+pub fn decode_user_data(user_data: &[u8; CONNECT_TOKEN_USER_DATA_BYTES]) -> UserId {
+    let mut user_id = [0; 16];
+    user_id.copy_from_slice(&user_data[..16]);
+    UserId::from_bytes(user_id)
+}
@@ -1,32 +1,123 @@
+use std::time::Duration;
+
 use axum::{
-    http::StatusCode,
+    http::{header, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
-use serde::Serialize;
-use tracing::error;
+use tracing::{debug, error};
 
-#[derive(Debug, Serialize)]
-struct ErrorResponse {
-    message: String,
-}
+use common::{ErrorCode, ErrorResponse};
 
 #[derive(Debug)]
-pub struct AppError(pub anyhow::Error);
+pub enum AppError {
+    Unauthorized(String),
+    NotFound(String),
+    Conflict(String),
+    NoCapacity(String),
+    Timeout(String),
+    RateLimited(Duration),
+    InvalidRequest(String),
+    Internal(anyhow::Error),
+}
+
+impl AppError {
+    pub fn unauthorized(message: impl Into<String>) -> Self {
+        Self::Unauthorized(message.into())
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::NotFound(message.into())
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::Conflict(message.into())
+    }
+
+    pub fn no_capacity(message: impl Into<String>) -> Self {
+        Self::NoCapacity(message.into())
+    }
+
+    pub fn timeout(message: impl Into<String>) -> Self {
+        Self::Timeout(message.into())
+    }
+
+    pub fn rate_limited(retry_after: Duration) -> Self {
+        Self::RateLimited(retry_after)
+    }
+
+    pub fn invalid_request(message: impl Into<String>) -> Self {
+        Self::InvalidRequest(message.into())
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::Conflict(_) => StatusCode::CONFLICT,
+            Self::NoCapacity(_) => StatusCode::SERVICE_UNAVAILABLE,
+            Self::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            Self::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+            Self::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::Unauthorized(_) => ErrorCode::Unauthorized,
+            Self::NotFound(_) => ErrorCode::NotFound,
+            Self::Conflict(_) => ErrorCode::Conflict,
+            Self::NoCapacity(_) => ErrorCode::NoCapacity,
+            Self::Timeout(_) => ErrorCode::Timeout,
+            Self::RateLimited(_) => ErrorCode::RateLimited,
+            Self::InvalidRequest(_) => ErrorCode::InvalidRequest,
+            Self::Internal(_) => ErrorCode::Internal,
+        }
+    }
+}
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        // TODO: this doesn't seem like the best place to log this,
-        // but I'm not sure how to extract the error message in the TraceLayer handler
-        error!("{}", self.0);
-
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                message: self.0.to_string(),
-            }),
-        )
-            .into_response()
+        let status = self.status();
+        let code = self.code();
+
+        let retry_after = match &self {
+            // round up so clients don't retry early
+            Self::RateLimited(retry_after) => {
+                Some(retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0))
+            }
+            _ => None,
+        };
+
+        let message = match self {
+            // internal details stay in the logs
+            Self::Internal(err) => {
+                // TODO: this doesn't seem like the best place to log this,
+                // but I'm not sure how to extract the error message in the TraceLayer handler
+                error!("{:#}", err);
+                "Internal server error".to_owned()
+            }
+            Self::RateLimited(_) => "Too many requests".to_owned(),
+            Self::Unauthorized(message)
+            | Self::NotFound(message)
+            | Self::Conflict(message)
+            | Self::NoCapacity(message)
+            | Self::Timeout(message)
+            | Self::InvalidRequest(message) => {
+                debug!("{:?}: {}", code, message);
+                message
+            }
+        };
+
+        let mut response = (status, Json(ErrorResponse::new(code, message))).into_response();
+        if let Some(retry_after) = retry_after {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, retry_after.max(1).into());
+        }
+
+        response
     }
 }
 
@@ -35,6 +126,6 @@ where
     E: Into<anyhow::Error>,
 {
     fn from(err: E) -> Self {
-        Self(err.into())
+        Self::Internal(err.into())
     }
 }
@@ -0,0 +1,110 @@
+use axum::{
+    async_trait,
+    extract::{FromRequest, FromRequestParts, Request},
+    http::request::Parts,
+    response::{IntoResponse, Response},
+};
+use axum_extra::TypedHeader;
+use headers::authorization::{Authorization, Bearer};
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::AppError;
+
+// wrappers around the axum extractors that reject with an AppError
+
+#[derive(Debug)]
+pub struct BearerAuth(pub Bearer);
+
+impl BearerAuth {
+    #[inline]
+    pub fn token(&self) -> &str {
+        self.0.token()
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for BearerAuth
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let TypedHeader(Authorization(bearer)) =
+            TypedHeader::<Authorization<Bearer>>::from_request_parts(parts, state)
+                .await
+                .map_err(|err| AppError::unauthorized(err.to_string()))?;
+
+        Ok(Self(bearer))
+    }
+}
+
+#[derive(Debug)]
+pub struct Path<T>(pub T);
+
+#[async_trait]
+impl<S, T> FromRequestParts<S> for Path<T>
+where
+    S: Send + Sync,
+    T: DeserializeOwned + Send,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let axum::extract::Path(value) = axum::extract::Path::<T>::from_request_parts(parts, state)
+            .await
+            .map_err(|err| AppError::invalid_request(err.body_text()))?;
+
+        Ok(Self(value))
+    }
+}
+
+#[derive(Debug)]
+pub struct Query<T>(pub T);
+
+#[async_trait]
+impl<S, T> FromRequestParts<S> for Query<T>
+where
+    S: Send + Sync,
+    T: DeserializeOwned,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let axum::extract::Query(value) =
+            axum::extract::Query::<T>::from_request_parts(parts, state)
+                .await
+                .map_err(|err| AppError::invalid_request(err.body_text()))?;
+
+        Ok(Self(value))
+    }
+}
+
+#[derive(Debug)]
+pub struct Json<T>(pub T);
+
+#[async_trait]
+impl<S, T> FromRequest<S> for Json<T>
+where
+    S: Send + Sync,
+    T: DeserializeOwned,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let axum::Json(value) = axum::Json::<T>::from_request(req, state)
+            .await
+            .map_err(|err| AppError::invalid_request(err.body_text()))?;
+
+        Ok(Self(value))
+    }
+}
+
+impl<T> IntoResponse for Json<T>
+where
+    T: Serialize,
+{
+    fn into_response(self) -> Response {
+        axum::Json(self.0).into_response()
+    }
+}
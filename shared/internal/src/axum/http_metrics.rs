@@ -0,0 +1,85 @@
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use axum::{debug_handler, extract::MatchedPath, http::StatusCode, response::IntoResponse};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use tracing::info;
+
+// seconds
+const LATENCY_BUCKETS: &[f64] = &[
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0,
+];
+
+const UPKEEP_INTERVAL: Duration = Duration::from_secs(5);
+
+static METRICS_HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+
+pub fn init_metrics() -> anyhow::Result<()> {
+    info!("initializing metrics ...");
+
+    let handle = PrometheusBuilder::new()
+        .set_buckets_for_metric(Matcher::Suffix("_seconds".into()), LATENCY_BUCKETS)?
+        .install_recorder()?;
+
+    // the recorder doesn't do its own upkeep without the exporter's http listener
+    let upkeep_handle = handle.clone();
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(UPKEEP_INTERVAL).await;
+            upkeep_handle.run_upkeep();
+        }
+    });
+
+    METRICS_HANDLE
+        .set(handle)
+        .map_err(|_| anyhow::anyhow!("metrics already initialized"))?;
+
+    Ok(())
+}
+
+#[cfg_attr(
+    feature = "openapi",
+    utoipa::path(
+        get,
+        path = "/metrics",
+        tag = "service",
+        responses((status = 200, description = "Prometheus metrics", body = String))
+    )
+)]
+#[debug_handler]
+pub async fn handler_metrics() -> impl IntoResponse {
+    match METRICS_HANDLE.get() {
+        Some(handle) => (StatusCode::OK, handle.render()),
+        None => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Metrics not initialized".to_owned(),
+        ),
+    }
+}
+
+pub async fn metrics_wrapper(
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    // use the matched route rather than the uri to keep the label cardinality down
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_owned())
+        .unwrap_or_else(|| "unmatched".to_owned());
+    let method = request.method().to_string();
+
+    let now = Instant::now();
+    let response = next.run(request).await;
+    let elapsed = now.elapsed();
+
+    let labels = [
+        ("method", method),
+        ("route", route),
+        ("status", response.status().as_u16().to_string()),
+    ];
+    metrics::counter!("http_requests_total", &labels).increment(1);
+    metrics::histogram!("http_request_duration_seconds", &labels).record(elapsed.as_secs_f64());
+
+    response
+}
@@ -3,12 +3,14 @@ use std::ops::Deref;
 use std::time::Instant;
 
 use axum::extract::ConnectInfo;
-use http::header;
-use tracing::info;
+use http::{header, HeaderValue};
+use tracing::{info, info_span, Instrument};
+
+use crate::logging::access_log_filter;
 
 use super::*;
 
-// TODO: if we can get an id into the Span then we can use
+// TODO: now that the request id is in the Span we could use
 // on_request and on_response instead of tracing_wrapper
 
 #[allow(dead_code)]
@@ -26,11 +28,7 @@ pub fn on_request<B>(request: &http::Request<B>, span: &tracing::Span) {
 
     let user_agent = get_request_header(request, header::USER_AGENT);
 
-    // don't log AWS health check requests
-    if !user_agent
-        .as_ref()
-        .is_some_and(|user_agent| user_agent.contains("HealthChecker"))
-    {
+    if !access_log_filter().is_excluded(request.uri().path(), user_agent) {
         info!(
             target: "bevy-multiplayer::api",
             "req:{} {}{} \"{} {} {:?}\" \"{}\" \"{}\"",
@@ -67,14 +65,18 @@ pub fn on_response<B>(
 // because I want to log everything about the
 // request / response together
 pub async fn tracing_wrapper(
-    request: axum::extract::Request,
+    mut request: axum::extract::Request,
     next: axum::middleware::Next,
 ) -> Result<impl axum::response::IntoResponse, axum::response::Response> {
+    let request_id = RequestId::from_request(&request);
+    request.extensions_mut().insert(request_id.clone());
+
     let method = request.method().clone();
     let uri = request.uri().clone();
     let version = request.version();
     let referer = get_request_header(&request, header::REFERER).map(str::to_owned);
     let user_agent = get_request_header(&request, header::USER_AGENT).map(str::to_owned);
+    let auth_id = get_bearer_id(&request);
 
     /*let (parts, body) = request.into_parts();
     let bytes = buffer_and_print("request", body).await.unwrap();
@@ -91,31 +93,40 @@ pub async fn tracing_wrapper(
         forwarded = false;
     }
 
+    // everything logged while handling the request is tagged with its id
+    let span = info_span!("request", request_id = %request_id);
+
     let now = Instant::now();
-    let response = next.run(request).await;
+    let mut response = next.run(request).instrument(span).await;
     let elapsed = now.elapsed();
 
+    if let Ok(value) = HeaderValue::from_str(request_id.as_str()) {
+        response
+            .headers_mut()
+            .insert(common::REQUEST_ID_HEADER, value);
+    }
+
     /*let (parts, body) = response.into_parts();
     let bytes = buffer_and_print("response", body).await.unwrap();
     let response = axum::response::Response::from_parts(parts, axum::body::Body::from(bytes));*/
 
-    // don't log AWS health check requests
-    if !user_agent
-        .as_ref()
-        .is_some_and(|user_agent| user_agent.contains("HealthChecker"))
-    {
+    // always log failures, even for excluded requests
+    let status = response.status();
+    if !status.is_success() || !access_log_filter().is_excluded(uri.path(), user_agent.as_deref()) {
         info!(
             target: "bevy-multiplayer::api",
-            "{}{} \"{} {} {:?}\" {} \"{}\" \"{}\" {:?}",
-            OptFmt(remote_addr),
-            if forwarded { " (forwarded)" } else { "" },
-            method,
-            uri,
-            version,
-            response.status().as_u16(),
-            OptFmt(referer),
-            OptFmt(user_agent),
-            elapsed,
+            method = %method,
+            path = %uri,
+            version = ?version,
+            status = status.as_u16(),
+            latency_ms = elapsed.as_secs_f64() * 1000.0,
+            remote_addr = %OptFmt(remote_addr),
+            forwarded,
+            referer = %OptFmt(referer),
+            user_agent = %OptFmt(user_agent),
+            auth_id = %OptFmt(auth_id),
+            request_id = %request_id,
+            "request",
         );
     }
 
@@ -1,19 +1,27 @@
 mod error;
+pub mod extract;
+mod http_metrics;
 mod http_tracing;
+mod rate_limit;
+mod request_id;
 
 use std::fmt;
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 
 use axum::{
     body::Bytes, debug_handler, extract::ConnectInfo, http::StatusCode, http::Uri,
     response::IntoResponse,
 };
-use http::{Request, header::AsHeaderName};
+use http::{Request, header, header::AsHeaderName};
 use http_body_util::BodyExt;
 use tracing::{debug, info};
+use uuid::Uuid;
 
 pub use error::*;
+pub use http_metrics::*;
 pub use http_tracing::*;
+pub use rate_limit::*;
+pub use request_id::*;
 
 #[debug_handler]
 pub async fn handler_404(uri: Uri) -> impl IntoResponse {
@@ -22,6 +30,20 @@ pub async fn handler_404(uri: Uri) -> impl IntoResponse {
     (StatusCode::NOT_FOUND, "Resource not found")
 }
 
+#[cfg_attr(
+    feature = "openapi",
+    utoipa::path(
+        get,
+        path = "/healthz",
+        tag = "service",
+        responses((status = 200, description = "Service is alive", body = String))
+    )
+)]
+#[debug_handler]
+pub async fn handler_healthz() -> impl IntoResponse {
+    (StatusCode::OK, "OK")
+}
+
 // copied from warp's log filter
 pub struct OptFmt<T>(pub Option<T>);
 
@@ -48,6 +70,14 @@ where
     None
 }
 
+// user / server ids are the bearer token for now
+// anything else (like the admin token) is left out so it doesn't end up in logs
+pub fn get_bearer_id<B>(request: &Request<B>) -> Option<Uuid> {
+    get_request_header(request, header::AUTHORIZATION)
+        .and_then(|authorization| authorization.strip_prefix("Bearer "))
+        .and_then(|token| Uuid::parse_str(token.trim()).ok())
+}
+
 fn get_forwarded_for<B>(request: &Request<B>) -> Option<&str> {
     // TODO: not sure if header::FORWARDED works here or not
     let forwarded_for = get_request_header(request, "X-Forwarded-For");
@@ -76,6 +106,31 @@ pub fn get_forwarded_addr<B>(request: &Request<B>) -> Option<SocketAddr> {
     None
 }
 
+// the address of whoever is actually calling us
+// X-Forwarded-For is only trusted from our own proxies, since anyone can send it,
+// so walk it back from the connection until we hit an address we don't trust
+pub fn get_client_ip<B>(request: &Request<B>, trusted_proxies: &[IpAddr]) -> Option<IpAddr> {
+    let mut client_ip = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip())?;
+
+    if let Some(forwarded_for) = get_request_header(request, "X-Forwarded-For") {
+        for forwarded in forwarded_for.rsplit(',') {
+            if !trusted_proxies.contains(&client_ip) {
+                break;
+            }
+
+            let Ok(forwarded) = forwarded.trim().parse() else {
+                break;
+            };
+            client_ip = forwarded;
+        }
+    }
+
+    Some(client_ip)
+}
+
 pub async fn buffer_and_print<B>(direction: &str, body: B) -> Result<Bytes, (StatusCode, String)>
 where
     B: axum::body::HttpBody<Data = Bytes>,
@@ -0,0 +1,170 @@
+use std::net::IpAddr;
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tracing::{debug, warn};
+
+use crate::redis::RedisConnection;
+
+use super::{get_bearer_id, get_client_ip, AppError};
+
+// token bucket, refilled based on the elapsed time since the last request
+// returns { allowed, retry after ms }
+pub static TOKEN_BUCKET_SCRIPT: LazyLock<redis::Script> = LazyLock::new(|| {
+    redis::Script::new(
+        r"
+local capacity = tonumber(ARGV[1])
+local rate = tonumber(ARGV[2])
+local now = tonumber(ARGV[3])
+
+local bucket = redis.call('HMGET', KEYS[1], 'tokens', 'ts')
+local tokens = tonumber(bucket[1]) or capacity
+local ts = tonumber(bucket[2]) or now
+
+tokens = math.min(capacity, tokens + math.max(0, now - ts) * rate)
+
+local allowed = 0
+local retry_after = 0
+if tokens >= 1 then
+    tokens = tokens - 1
+    allowed = 1
+else
+    retry_after = math.ceil((1 - tokens) / rate)
+end
+
+redis.call('HSET', KEYS[1], 'tokens', tostring(tokens), 'ts', now)
+redis.call('PEXPIRE', KEYS[1], math.ceil(capacity / rate))
+
+return { allowed, retry_after }
+",
+    )
+});
+
+#[derive(Debug, Copy, Clone)]
+pub struct RateLimit {
+    // max burst size
+    pub capacity: u32,
+
+    // tokens refilled per minute
+    pub per_minute: u32,
+}
+
+impl RateLimit {
+    pub fn new(capacity: u32, per_minute: u32) -> Self {
+        Self {
+            capacity,
+            per_minute,
+        }
+    }
+
+    // tokens per millisecond
+    fn rate(&self) -> f64 {
+        self.per_minute as f64 / 60_000.0
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimiter {
+    name: Arc<str>,
+    limit: RateLimit,
+    redis_connection: RedisConnection,
+    trusted_proxies: Arc<[IpAddr]>,
+    enabled: bool,
+}
+
+impl RateLimiter {
+    pub fn new(
+        name: impl Into<Arc<str>>,
+        limit: RateLimit,
+        redis_connection: RedisConnection,
+        trusted_proxies: impl Into<Arc<[IpAddr]>>,
+        enabled: bool,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            limit,
+            redis_connection,
+            trusted_proxies: trusted_proxies.into(),
+            enabled: enabled && limit.capacity > 0 && limit.per_minute > 0,
+        }
+    }
+
+    fn get_key(&self, identity: &str) -> String {
+        format!("ratelimit:{}:{}", self.name, identity)
+    }
+
+    // returns the time to wait if the request is limited
+    async fn check(&self, identity: &str) -> anyhow::Result<Option<Duration>> {
+        let now = now_millis();
+
+        let mut conn = self.redis_connection.clone();
+        let (allowed, retry_after): (u64, u64) = TOKEN_BUCKET_SCRIPT
+            .key(self.get_key(identity))
+            .arg(self.limit.capacity)
+            .arg(self.limit.rate())
+            .arg(now)
+            .invoke_async(&mut conn)
+            .await?;
+
+        if allowed == 1 {
+            return Ok(None);
+        }
+
+        Ok(Some(Duration::from_millis(retry_after)))
+    }
+}
+
+fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+// rate limits are keyed on the user / server id
+// falling back to the remote address for everything else
+fn get_identity(request: &Request, trusted_proxies: &[IpAddr]) -> Option<String> {
+    if let Some(id) = get_bearer_id(request) {
+        return Some(id.to_string());
+    }
+
+    get_client_ip(request, trusted_proxies).map(|ip| ip.to_string())
+}
+
+pub async fn rate_limit(
+    State(limiter): State<RateLimiter>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !limiter.enabled {
+        return next.run(request).await;
+    }
+
+    let Some(identity) = get_identity(&request, &limiter.trusted_proxies) else {
+        return next.run(request).await;
+    };
+
+    match limiter.check(&identity).await {
+        Ok(None) => next.run(request).await,
+        Ok(Some(retry_after)) => {
+            debug!(
+                "rate limited {} on {} (retry after {:?})",
+                identity, limiter.name, retry_after
+            );
+            metrics::counter!("rate_limited_total", "limiter" => limiter.name.to_string())
+                .increment(1);
+
+            AppError::rate_limited(retry_after).into_response()
+        }
+        Err(err) => {
+            // fail open, the store being down shouldn't take the api down with it
+            warn!("failed to check {} rate limit: {}", limiter.name, err);
+            next.run(request).await
+        }
+    }
+}
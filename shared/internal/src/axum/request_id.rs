@@ -0,0 +1,85 @@
+use std::convert::Infallible;
+use std::fmt;
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Request},
+    http::request::Parts,
+};
+use uuid::Uuid;
+
+use super::get_request_header;
+
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+// correlation id for a request, accepted from the X-Request-Id header
+// or generated by the tracing_wrapper if the caller didn't send one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(String);
+
+impl RequestId {
+    pub fn new() -> Self {
+        Self(Uuid::new_v4().to_string())
+    }
+
+    // only accept reasonable ids so they're safe to log / forward
+    pub fn parse(request_id: impl AsRef<str>) -> Option<Self> {
+        let request_id = request_id.as_ref().trim();
+        if request_id.is_empty()
+            || request_id.len() > MAX_REQUEST_ID_LEN
+            || !request_id
+                .chars()
+                .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_' | '.' | ':'))
+        {
+            return None;
+        }
+
+        Some(Self(request_id.to_owned()))
+    }
+
+    pub fn from_request(request: &Request) -> Self {
+        get_request_header(request, common::REQUEST_ID_HEADER)
+            .and_then(Self::parse)
+            .unwrap_or_default()
+    }
+
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Default for RequestId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<RequestId> for String {
+    fn from(request_id: RequestId) -> Self {
+        request_id.0
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for RequestId
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        // set by the tracing_wrapper, but don't fail if it isn't installed
+        Ok(parts
+            .extensions
+            .get::<RequestId>()
+            .cloned()
+            .unwrap_or_default())
+    }
+}
@@ -1,5 +1,7 @@
 use uuid::Uuid;
 
+use common::user::UserId;
+
 pub const GAMESERVER_KEY: &str = "gameserver:{}";
 pub const GAMESERVERS_INDEX: &str = "gameservers.index";
 pub const WAITING_GAMESERVERS_INDEX: &str = "gameservers:waiting.index";
@@ -8,6 +10,18 @@ pub fn get_gameserver_key(server_id: Uuid) -> String {
     format!("gameserver:{}", server_id)
 }
 
+pub const GAMESERVER_MAILBOX_INDEX: &str = "gameserver:{}:mailbox.index";
+
+pub fn get_gameserver_mailbox_key(server_id: Uuid) -> String {
+    format!("gameserver:{}:mailbox.index", server_id)
+}
+
+pub const NOTIF_KEY: &str = "notif:{}";
+
+pub fn get_notif_key(notif_id: Uuid) -> String {
+    format!("notif:{}", notif_id)
+}
+
 pub const GAMESESSION_KEY: &str = "gamesession:{}";
 pub const GAMESESSIONS_INDEX: &str = "gamesessions.index";
 pub const GAMESESSIONS_BACKFILL_SET: &str = "gamesessions:backfill";
@@ -15,3 +29,9 @@ pub const GAMESESSIONS_BACKFILL_SET: &str = "gamesessions:backfill";
 pub fn get_gamesession_key(session_id: Uuid) -> String {
     format!("gamesession:{}", session_id)
 }
+
+pub const FIND_SERVER_LOCK_KEY: &str = "gameclient:{}:find_server.lock";
+
+pub fn get_find_server_lock_key(user_id: UserId) -> String {
+    format!("gameclient:{}:find_server.lock", user_id)
+}
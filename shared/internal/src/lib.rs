@@ -1,7 +1,10 @@
 pub mod axum;
 pub mod gameserver;
+pub mod logging;
+pub mod models;
 pub mod notifs;
 pub mod redis;
+pub mod shutdown;
 
 pub const GAMESERVER_NOTIFS_CHANNEL: &str = "gameserver:notifs";
 pub const GAMECLIENT_NOTIFS_CHANNEL: &str = "gameclient:notifs";
@@ -0,0 +1,99 @@
+use std::sync::OnceLock;
+
+use clap::{Args, ValueEnum};
+use serde::{Deserialize, Serialize};
+use tracing::Level;
+use tracing_subscriber::{EnvFilter, FmtSubscriber};
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+#[derive(Args, Debug, Clone, Serialize, Deserialize)]
+pub struct LoggingOptions {
+    #[arg(long, env = "LOG_FORMAT", value_enum, default_value_t = LogFormat::Text)]
+    pub log_format: LogFormat,
+
+    // successful requests to these paths aren't access logged
+    #[arg(
+        long,
+        env = "LOG_EXCLUDE_PATHS",
+        value_delimiter = ',',
+        default_value = "/healthz,/readyz"
+    )]
+    pub log_exclude_paths: Vec<String>,
+
+    // successful requests from user agents containing these aren't access logged
+    #[arg(
+        long,
+        env = "LOG_EXCLUDE_USER_AGENTS",
+        value_delimiter = ',',
+        default_value = "HealthChecker"
+    )]
+    pub log_exclude_user_agents: Vec<String>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct AccessLogFilter {
+    exclude_paths: Vec<String>,
+    exclude_user_agents: Vec<String>,
+}
+
+impl AccessLogFilter {
+    pub fn is_excluded(&self, path: &str, user_agent: Option<&str>) -> bool {
+        self.exclude_paths.iter().any(|exclude| exclude == path)
+            || user_agent.is_some_and(|user_agent| {
+                self.exclude_user_agents
+                    .iter()
+                    .any(|exclude| !exclude.is_empty() && user_agent.contains(exclude.as_str()))
+            })
+    }
+}
+
+static ACCESS_LOG_FILTER: OnceLock<AccessLogFilter> = OnceLock::new();
+
+// defaults to logging everything if logging wasn't initialized
+pub fn access_log_filter() -> &'static AccessLogFilter {
+    ACCESS_LOG_FILTER.get_or_init(AccessLogFilter::default)
+}
+
+pub fn init_logging(crate_name: &str, options: &LoggingOptions) -> anyhow::Result<()> {
+    let env_filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| {
+        // axum logs rejections from built-in extractors with the `axum::rejection`
+        // target, at `TRACE` level. `axum::rejection=trace` enables showing those events
+        format!(
+            "{}=debug,tower_http=debug,axum::rejection=trace",
+            crate_name
+        )
+        .into()
+    });
+
+    let builder = FmtSubscriber::builder()
+        .with_env_filter(env_filter)
+        .with_max_level(Level::INFO);
+
+    match options.log_format {
+        LogFormat::Text => tracing::subscriber::set_global_default(builder.finish())?,
+        LogFormat::Json => tracing::subscriber::set_global_default(
+            builder
+                .json()
+                .flatten_event(true)
+                .with_current_span(true)
+                .with_span_list(false)
+                .finish(),
+        )?,
+    }
+
+    ACCESS_LOG_FILTER
+        .set(AccessLogFilter {
+            exclude_paths: options.log_exclude_paths.clone(),
+            exclude_user_agents: options.log_exclude_user_agents.clone(),
+        })
+        .map_err(|_| anyhow::anyhow!("logging already initialized"))?;
+
+    Ok(())
+}
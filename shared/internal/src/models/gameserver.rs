@@ -4,6 +4,8 @@ use uuid::Uuid;
 use common::gameserver::{GameServerOrchestration, GameServerState};
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "openapi", schema(as = models::GameServerInfo))]
 pub struct GameServerInfo {
     pub server_id: Uuid,
 
@@ -14,6 +16,9 @@ pub struct GameServerInfo {
     pub state: GameServerState,
     pub orchestration: GameServerOrchestration,
 
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub region: Option<String>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub game_session_id: Option<Uuid>,
 }
@@ -28,6 +33,7 @@ impl GameServerInfo {
             port: server_info.port,
             state: server_info.state,
             orchestration: server_info.orchestration,
+            region: server_info.region.clone(),
             game_session_id: server_info
                 .game_session_info
                 .as_ref()
@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use common::user::UserId;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "openapi", schema(as = models::GameSessionInfo))]
+pub struct GameSessionInfo {
+    pub game_session_id: Uuid,
+    pub server_id: Uuid,
+
+    pub max_players: u16,
+
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    #[cfg_attr(feature = "openapi", schema(value_type = Vec<Uuid>))]
+    pub active_player_ids: Vec<UserId>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    #[cfg_attr(feature = "openapi", schema(value_type = Vec<Uuid>))]
+    pub pending_player_ids: Vec<UserId>,
+
+    // last measured round trip time of each active player
+    #[serde(skip_serializing_if = "HashMap::is_empty", default)]
+    #[cfg_attr(feature = "openapi", schema(value_type = HashMap<Uuid, u32>))]
+    pub player_rtt_ms: HashMap<UserId, u32>,
+
+    // ticks simulated without a player's input this session
+    #[serde(default)]
+    pub input_buffer_underruns: u32,
+
+    // player input dropped for getting too far ahead this session
+    #[serde(default)]
+    pub input_buffer_overruns: u32,
+
+    // ticks the simulation fell behind real time this session
+    #[serde(default)]
+    pub tick_overruns: u32,
+}
+
+impl GameSessionInfo {
+    #[inline]
+    pub fn new(server_id: Uuid, session_info: &common::gameserver::GameSessionInfo) -> Self {
+        Self {
+            game_session_id: session_info.game_session_id,
+            server_id,
+            max_players: session_info.max_players,
+            active_player_ids: session_info.active_player_ids.clone(),
+            pending_player_ids: session_info.pending_player_ids.clone(),
+            player_rtt_ms: session_info.player_rtt_ms.clone(),
+            input_buffer_underruns: session_info.input_buffer_underruns,
+            input_buffer_overruns: session_info.input_buffer_overruns,
+            tick_overruns: session_info.tick_overruns,
+        }
+    }
+
+    #[inline]
+    pub fn has_player(&self, user_id: UserId) -> bool {
+        self.active_player_ids.contains(&user_id) || self.pending_player_ids.contains(&user_id)
+    }
+
+    #[inline]
+    pub fn player_slots_remaining(&self) -> u16 {
+        // TODO: this isn't safe if we mess up and have more players than max_players
+        let used_slots = self.active_player_ids.len() + self.pending_player_ids.len();
+        self.max_players - used_slots as u16
+    }
+}
@@ -1,16 +1,27 @@
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use uuid::Uuid;
 
-use common::user::UserId;
+use common::{user::UserId, GameSettings};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct Notification {
+    pub id: Uuid,
     pub recipient: String,
     pub r#type: NotifType,
     pub message: String,
+
+    // id of the api request that triggered the notif
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
 }
 
 impl Notification {
+    pub fn with_request_id(mut self, request_id: impl Into<String>) -> Self {
+        self.request_id = Some(request_id.into());
+        self
+    }
+
     pub fn to_message<T: DeserializeOwned>(self) -> anyhow::Result<T> {
         Ok(serde_json::from_str(&self.message)?)
     }
@@ -22,24 +33,34 @@ pub trait AsNotification: Serialize {
     #[inline]
     fn as_notification(&self, recipient: impl Into<String>) -> anyhow::Result<Notification> {
         Ok(Notification {
+            id: Uuid::new_v4(),
             recipient: recipient.into(),
             r#type: self.get_type(),
             message: serde_json::to_string(self)?,
+            request_id: None,
         })
     }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum NotifType {
     PlacementRequestV1,
     ReservationRequestV1,
+    DrainRequestV1,
+    ShutdownRequestV1,
+    EndSessionRequestV1,
+    KickPlayerRequestV1,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlacementRequestV1 {
     pub game_session_id: Uuid,
     pub player_ids: Vec<UserId>,
+
+    #[serde(default)]
+    pub game_settings: GameSettings,
 }
 
 impl AsNotification for PlacementRequestV1 {
@@ -50,10 +71,15 @@ impl AsNotification for PlacementRequestV1 {
 }
 
 impl PlacementRequestV1 {
-    pub fn new(game_session_id: Uuid, player_ids: Vec<UserId>) -> Self {
+    pub fn new(
+        game_session_id: Uuid,
+        player_ids: Vec<UserId>,
+        game_settings: GameSettings,
+    ) -> Self {
         Self {
             game_session_id,
             player_ids,
+            game_settings,
         }
     }
 }
@@ -79,3 +105,63 @@ impl ReservationRequestV1 {
         }
     }
 }
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct DrainRequestV1 {}
+
+impl AsNotification for DrainRequestV1 {
+    #[inline]
+    fn get_type(&self) -> NotifType {
+        NotifType::DrainRequestV1
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ShutdownRequestV1 {}
+
+impl AsNotification for ShutdownRequestV1 {
+    #[inline]
+    fn get_type(&self) -> NotifType {
+        NotifType::ShutdownRequestV1
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EndSessionRequestV1 {
+    pub game_session_id: Uuid,
+}
+
+impl AsNotification for EndSessionRequestV1 {
+    #[inline]
+    fn get_type(&self) -> NotifType {
+        NotifType::EndSessionRequestV1
+    }
+}
+
+impl EndSessionRequestV1 {
+    pub fn new(game_session_id: Uuid) -> Self {
+        Self { game_session_id }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KickPlayerRequestV1 {
+    pub game_session_id: Uuid,
+    pub player_id: UserId,
+}
+
+impl AsNotification for KickPlayerRequestV1 {
+    #[inline]
+    fn get_type(&self) -> NotifType {
+        NotifType::KickPlayerRequestV1
+    }
+}
+
+impl KickPlayerRequestV1 {
+    pub fn new(game_session_id: Uuid, player_id: UserId) -> Self {
+        Self {
+            game_session_id,
+            player_id,
+        }
+    }
+}
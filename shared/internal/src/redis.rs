@@ -1,8 +1,22 @@
+use std::sync::LazyLock;
+
 use redis::{aio::ConnectionManager, AsyncCommands};
 use tracing::info;
 
 pub type RedisConnection = ConnectionManager;
 
+// only release the lock if we still own it
+pub static RELEASE_LOCK_SCRIPT: LazyLock<redis::Script> = LazyLock::new(|| {
+    redis::Script::new(
+        r"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('DEL', KEYS[1])
+end
+return 0
+",
+    )
+});
+
 async fn ping(conn: &mut RedisConnection) -> anyhow::Result<()> {
     let pong: String = conn.ping().await?;
     if pong != "PONG" {
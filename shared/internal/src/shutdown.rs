@@ -0,0 +1,64 @@
+use tokio::{sync::watch, time::Duration};
+use tracing::{info, warn};
+
+pub type ShutdownReceiver = watch::Receiver<bool>;
+
+async fn signal() {
+    let ctrl_c = async {
+        if let Err(err) = tokio::signal::ctrl_c().await {
+            warn!("failed to listen for ctrl-c: {}", err);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(err) => {
+                warn!("failed to listen for SIGTERM: {}", err);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
+
+// spawns a task that flags shutdown on ctrl-c / SIGTERM
+pub fn init_shutdown_signal() -> ShutdownReceiver {
+    let (tx, rx) = watch::channel(false);
+
+    tokio::spawn(async move {
+        signal().await;
+
+        info!("shutdown signal received, shutting down ...");
+        let _ = tx.send(true);
+    });
+
+    rx
+}
+
+#[inline]
+pub fn is_shutting_down(shutdown: &ShutdownReceiver) -> bool {
+    *shutdown.borrow()
+}
+
+pub async fn wait_for_shutdown(mut shutdown: ShutdownReceiver) {
+    // an error means the sender is gone, which we treat as a shutdown
+    let _ = shutdown.wait_for(|shutting_down| *shutting_down).await;
+}
+
+// resolves once shutdown has been flagged and the timeout has elapsed
+pub async fn shutdown_deadline(shutdown: ShutdownReceiver, timeout: Duration) {
+    wait_for_shutdown(shutdown).await;
+    tokio::time::sleep(timeout).await;
+}
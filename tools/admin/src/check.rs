@@ -0,0 +1,252 @@
+use comfy_table::Table;
+use redis::AsyncCommands;
+use serde::Serialize;
+use uuid::Uuid;
+
+use common::gameserver::GameServerState;
+use internal::{
+    gameserver::{
+        GAMESERVERS_INDEX, GAMESESSIONS_BACKFILL_SET, GAMESESSIONS_INDEX, WAITING_GAMESERVERS_INDEX,
+    },
+    redis::RedisConnection,
+};
+
+use crate::store;
+
+#[derive(Debug, Copy, Clone, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum IssueKind {
+    // index entry (zset member) to remove
+    IndexEntry,
+
+    // backfill set entry (hash field) to remove
+    BackfillEntry,
+}
+
+#[derive(Debug, Serialize)]
+struct Issue {
+    kind: IssueKind,
+    key: &'static str,
+    member: String,
+    reason: String,
+
+    fixed: bool,
+}
+
+impl Issue {
+    fn new(kind: IssueKind, key: &'static str, member: String, reason: impl Into<String>) -> Self {
+        Self {
+            kind,
+            key,
+            member,
+            reason: reason.into(),
+            fixed: false,
+        }
+    }
+
+    async fn fix(&mut self, conn: &mut RedisConnection) -> anyhow::Result<()> {
+        match self.kind {
+            IssueKind::IndexEntry => {
+                let _: () = conn.zrem(self.key, &self.member).await?;
+            }
+            IssueKind::BackfillEntry => {
+                let _: () = conn.hdel(self.key, &self.member).await?;
+            }
+        }
+
+        self.fixed = true;
+
+        Ok(())
+    }
+}
+
+async fn check_gameservers_index(
+    conn: &mut RedisConnection,
+    issues: &mut Vec<Issue>,
+) -> anyhow::Result<()> {
+    for server_id in store::read_gameservers_index(conn).await? {
+        let Ok(id) = Uuid::parse_str(&server_id) else {
+            issues.push(Issue::new(
+                IssueKind::IndexEntry,
+                GAMESERVERS_INDEX,
+                server_id,
+                "invalid server id",
+            ));
+            continue;
+        };
+
+        if store::read_gameserver_info(conn, id).await?.is_none() {
+            issues.push(Issue::new(
+                IssueKind::IndexEntry,
+                GAMESERVERS_INDEX,
+                server_id,
+                "server info missing",
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+async fn check_waiting_gameservers_index(
+    conn: &mut RedisConnection,
+    issues: &mut Vec<Issue>,
+) -> anyhow::Result<()> {
+    for server_id in store::read_waiting_gameservers_index(conn).await? {
+        let Ok(id) = Uuid::parse_str(&server_id) else {
+            issues.push(Issue::new(
+                IssueKind::IndexEntry,
+                WAITING_GAMESERVERS_INDEX,
+                server_id,
+                "invalid server id",
+            ));
+            continue;
+        };
+
+        match store::read_gameserver_info(conn, id).await? {
+            Some(server_info) => {
+                if server_info.state != GameServerState::WaitingForPlacement {
+                    issues.push(Issue::new(
+                        IssueKind::IndexEntry,
+                        WAITING_GAMESERVERS_INDEX,
+                        server_id,
+                        format!("server not waiting for placement: {:?}", server_info.state),
+                    ));
+                }
+            }
+            None => {
+                issues.push(Issue::new(
+                    IssueKind::IndexEntry,
+                    WAITING_GAMESERVERS_INDEX,
+                    server_id,
+                    "server info missing",
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn check_game_sessions_index(
+    conn: &mut RedisConnection,
+    issues: &mut Vec<Issue>,
+) -> anyhow::Result<()> {
+    for game_session_id in store::read_game_sessions_index(conn).await? {
+        let Ok(id) = Uuid::parse_str(&game_session_id) else {
+            issues.push(Issue::new(
+                IssueKind::IndexEntry,
+                GAMESESSIONS_INDEX,
+                game_session_id,
+                "invalid session id",
+            ));
+            continue;
+        };
+
+        if store::read_game_session_info(conn, id).await?.is_none() {
+            issues.push(Issue::new(
+                IssueKind::IndexEntry,
+                GAMESESSIONS_INDEX,
+                game_session_id,
+                "session info missing",
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+async fn check_backfill_game_sessions(
+    conn: &mut RedisConnection,
+    issues: &mut Vec<Issue>,
+) -> anyhow::Result<()> {
+    for (game_session_id, _) in store::read_backfill_game_sessions(conn).await? {
+        let Ok(id) = Uuid::parse_str(&game_session_id) else {
+            issues.push(Issue::new(
+                IssueKind::BackfillEntry,
+                GAMESESSIONS_BACKFILL_SET,
+                game_session_id,
+                "invalid session id",
+            ));
+            continue;
+        };
+
+        let Some(game_session_info) = store::read_game_session_info(conn, id).await? else {
+            issues.push(Issue::new(
+                IssueKind::BackfillEntry,
+                GAMESESSIONS_BACKFILL_SET,
+                game_session_id,
+                "session info missing",
+            ));
+            continue;
+        };
+
+        match store::read_gameserver_info(conn, game_session_info.server_id).await? {
+            Some(server_info) => {
+                if server_info.game_session_id != Some(id) {
+                    issues.push(Issue::new(
+                        IssueKind::BackfillEntry,
+                        GAMESESSIONS_BACKFILL_SET,
+                        game_session_id,
+                        format!("session not on server {}", server_info.server_id),
+                    ));
+                }
+            }
+            None => {
+                issues.push(Issue::new(
+                    IssueKind::BackfillEntry,
+                    GAMESESSIONS_BACKFILL_SET,
+                    game_session_id,
+                    format!("server {} missing", game_session_info.server_id),
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn check(conn: &mut RedisConnection, fix: bool, json: bool) -> anyhow::Result<()> {
+    let mut issues = vec![];
+
+    check_gameservers_index(conn, &mut issues).await?;
+    check_waiting_gameservers_index(conn, &mut issues).await?;
+    check_game_sessions_index(conn, &mut issues).await?;
+    check_backfill_game_sessions(conn, &mut issues).await?;
+
+    if fix {
+        for issue in issues.iter_mut() {
+            issue.fix(conn).await?;
+        }
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&issues)?);
+        return Ok(());
+    }
+
+    if issues.is_empty() {
+        println!("no issues found");
+        return Ok(());
+    }
+
+    let mut table = Table::new();
+    table.set_header(vec!["Key", "Member", "Reason", "Fixed"]);
+
+    for issue in &issues {
+        table.add_row(vec![
+            issue.key.to_string(),
+            issue.member.clone(),
+            issue.reason.clone(),
+            issue.fixed.to_string(),
+        ]);
+    }
+
+    println!("{table}");
+
+    if !fix {
+        println!("{} issues found, run with --fix to repair", issues.len());
+    }
+
+    Ok(())
+}
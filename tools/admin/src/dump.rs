@@ -0,0 +1,114 @@
+use comfy_table::Table;
+use uuid::Uuid;
+
+use internal::redis::RedisConnection;
+
+use crate::store;
+
+fn join_ids(ids: &[Uuid]) -> String {
+    ids.iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub async fn dump_gameservers(conn: &mut RedisConnection, json: bool) -> anyhow::Result<()> {
+    let mut servers = vec![];
+    for server_id in store::read_gameservers_index(conn).await? {
+        let Ok(server_id) = Uuid::parse_str(&server_id) else {
+            eprintln!("invalid server id in index: {}", server_id);
+            continue;
+        };
+
+        if let Some(server_info) = store::read_gameserver_info(conn, server_id).await? {
+            servers.push(server_info);
+        }
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&servers)?);
+        return Ok(());
+    }
+
+    let mut table = Table::new();
+    table.set_header(vec![
+        "Server",
+        "State",
+        "Orchestration",
+        "Region",
+        "Addresses",
+        "Port",
+        "Session",
+    ]);
+
+    for server_info in servers {
+        let addrs = server_info
+            .v4addrs
+            .iter()
+            .chain(server_info.v6addrs.iter())
+            .cloned()
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        table.add_row(vec![
+            server_info.server_id.to_string(),
+            format!("{:?}", server_info.state),
+            format!("{:?}", server_info.orchestration),
+            server_info.region.unwrap_or_default(),
+            addrs,
+            server_info.port.to_string(),
+            server_info
+                .game_session_id
+                .map(|game_session_id| game_session_id.to_string())
+                .unwrap_or_default(),
+        ]);
+    }
+
+    println!("{table}");
+
+    Ok(())
+}
+
+pub async fn dump_game_sessions(conn: &mut RedisConnection, json: bool) -> anyhow::Result<()> {
+    let mut game_sessions = vec![];
+    for game_session_id in store::read_game_sessions_index(conn).await? {
+        let Ok(game_session_id) = Uuid::parse_str(&game_session_id) else {
+            eprintln!("invalid session id in index: {}", game_session_id);
+            continue;
+        };
+
+        if let Some(game_session_info) =
+            store::read_game_session_info(conn, game_session_id).await?
+        {
+            game_sessions.push(game_session_info);
+        }
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&game_sessions)?);
+        return Ok(());
+    }
+
+    let mut table = Table::new();
+    table.set_header(vec![
+        "Session",
+        "Server",
+        "Max Players",
+        "Active Players",
+        "Pending Players",
+    ]);
+
+    for game_session_info in game_sessions {
+        table.add_row(vec![
+            game_session_info.game_session_id.to_string(),
+            game_session_info.server_id.to_string(),
+            game_session_info.max_players.to_string(),
+            join_ids(&game_session_info.active_player_ids),
+            join_ids(&game_session_info.pending_player_ids),
+        ]);
+    }
+
+    println!("{table}");
+
+    Ok(())
+}
@@ -0,0 +1,22 @@
+mod check;
+mod dump;
+mod options;
+mod store;
+
+use common::config;
+use internal::redis;
+
+use options::{Command, Options};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let options: Options = config::load(options::ENV_PREFIX)?;
+
+    let mut conn = redis::connect(options.redis_host.clone()).await?;
+
+    match options.command {
+        Command::Servers => dump::dump_gameservers(&mut conn, options.json).await,
+        Command::Sessions => dump::dump_game_sessions(&mut conn, options.json).await,
+        Command::Check { fix } => check::check(&mut conn, fix, options.json).await,
+    }
+}
@@ -0,0 +1,39 @@
+use clap::{Parser, Subcommand};
+use serde::{Deserialize, Serialize};
+
+use common::config::ConfigArgs;
+
+pub const ENV_PREFIX: &str = "BMP_ADMIN_";
+
+#[derive(Parser, Debug, Serialize, Deserialize)]
+pub struct Options {
+    #[command(flatten)]
+    #[serde(skip)]
+    pub config: ConfigArgs,
+
+    #[arg(long, default_value = "redis://localhost/")]
+    pub redis_host: String,
+
+    #[arg(long, global = true)]
+    #[serde(default)]
+    pub json: bool,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Command {
+    #[command(about = "Dump the registered game servers")]
+    Servers,
+
+    #[command(about = "Dump the active game sessions")]
+    Sessions,
+
+    #[command(about = "Check backend index consistency")]
+    Check {
+        #[arg(long)]
+        fix: bool,
+    },
+}
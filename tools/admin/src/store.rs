@@ -0,0 +1,57 @@
+use redis::AsyncCommands;
+use uuid::Uuid;
+
+use internal::{
+    gameserver::{
+        get_gameserver_key, get_gamesession_key, GAMESERVERS_INDEX, GAMESESSIONS_BACKFILL_SET,
+        GAMESESSIONS_INDEX, WAITING_GAMESERVERS_INDEX,
+    },
+    models,
+    redis::RedisConnection,
+};
+
+pub async fn read_gameserver_info(
+    conn: &mut RedisConnection,
+    server_id: Uuid,
+) -> anyhow::Result<Option<models::gameserver::GameServerInfo>> {
+    let server_info: Option<String> = conn.get(get_gameserver_key(server_id)).await?;
+    if let Some(server_info) = server_info {
+        return Ok(Some(serde_json::from_str(&server_info)?));
+    }
+    Ok(None)
+}
+
+pub async fn read_game_session_info(
+    conn: &mut RedisConnection,
+    game_session_id: Uuid,
+) -> anyhow::Result<Option<models::gamesession::GameSessionInfo>> {
+    let game_session_info: Option<String> = conn.get(get_gamesession_key(game_session_id)).await?;
+    if let Some(game_session_info) = game_session_info {
+        return Ok(Some(serde_json::from_str(&game_session_info)?));
+    }
+    Ok(None)
+}
+
+async fn read_index(conn: &mut RedisConnection, index: &str) -> anyhow::Result<Vec<String>> {
+    Ok(conn.zrange(index, 0, -1).await?)
+}
+
+pub async fn read_gameservers_index(conn: &mut RedisConnection) -> anyhow::Result<Vec<String>> {
+    read_index(conn, GAMESERVERS_INDEX).await
+}
+
+pub async fn read_waiting_gameservers_index(
+    conn: &mut RedisConnection,
+) -> anyhow::Result<Vec<String>> {
+    read_index(conn, WAITING_GAMESERVERS_INDEX).await
+}
+
+pub async fn read_game_sessions_index(conn: &mut RedisConnection) -> anyhow::Result<Vec<String>> {
+    read_index(conn, GAMESESSIONS_INDEX).await
+}
+
+pub async fn read_backfill_game_sessions(
+    conn: &mut RedisConnection,
+) -> anyhow::Result<Vec<(String, u64)>> {
+    Ok(conn.hgetall(GAMESESSIONS_BACKFILL_SET).await?)
+}
@@ -0,0 +1,184 @@
+mod options;
+mod process;
+mod store;
+
+use std::path::Path;
+use std::sync::Arc;
+
+use tokio::{
+    net::TcpStream,
+    task::JoinSet,
+    time::{Duration, Instant},
+};
+use tracing::{info, warn};
+
+use common::config;
+use internal::{logging, shutdown};
+
+use options::Options;
+use process::ChildSpec;
+
+const API_BIN: &str = "bevy-multiplayer-api";
+const NOTIFS_BIN: &str = "bevy-multiplayer-notifs";
+const SERVER_BIN: &str = "bevy-multiplayer-server";
+const CLIENT_BIN: &str = "bevy-multiplayer";
+
+fn bin_path(bin_dir: &Path, name: &str) -> anyhow::Result<std::path::PathBuf> {
+    let path = bin_dir.join(format!("{}{}", name, std::env::consts::EXE_SUFFIX));
+    if !path.exists() {
+        anyhow::bail!(
+            "{} not found, build the workspace first (cargo build --workspace)",
+            path.display()
+        );
+    }
+
+    Ok(path)
+}
+
+// polls until something is listening on the port
+async fn wait_for_port(port: u16, timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        if TcpStream::connect(("127.0.0.1", port)).await.is_ok() {
+            return true;
+        }
+
+        tokio::time::sleep(Duration::from_millis(250)).await;
+    }
+
+    false
+}
+
+fn service_specs(
+    options: &Options,
+    bin_dir: &Path,
+    redis_host: &str,
+) -> anyhow::Result<Vec<ChildSpec>> {
+    Ok(vec![
+        // CONNECT_TOKEN_KEY is passed through if it's set, otherwise use the dev key
+        ChildSpec::new("api", bin_path(bin_dir, API_BIN)?)
+            .arg("--port")
+            .arg(options.api_port)
+            .arg("--redis-host")
+            .arg(redis_host)
+            .arg("--insecure-dev-key"),
+        ChildSpec::new("notifs", bin_path(bin_dir, NOTIFS_BIN)?)
+            .arg("--port")
+            .arg(options.notifs_port)
+            .arg("--redis-host")
+            .arg(redis_host),
+    ])
+}
+
+fn game_specs(options: &Options, bin_dir: &Path) -> anyhow::Result<Vec<ChildSpec>> {
+    let api_url = format!("http://localhost:{}", options.api_port);
+    let notifs_url = format!("ws://localhost:{}", options.notifs_port);
+
+    let mut specs = vec![];
+
+    if options.servers > 0 {
+        let server_bin = bin_path(bin_dir, SERVER_BIN)?;
+        for idx in 0..options.servers {
+            let port = options
+                .server_base_port
+                .checked_add(idx)
+                .ok_or_else(|| anyhow::anyhow!("too many servers for the base port"))?;
+
+            // everything is local so only advertise loopback
+            specs.push(
+                ChildSpec::new(format!("server-{}", idx), server_bin.clone())
+                    .arg("local")
+                    .arg("--headless")
+                    .arg("--port")
+                    .arg(port)
+                    .arg("--advertise-addresses")
+                    .arg("127.0.0.1")
+                    .arg("--api-url")
+                    .arg(&api_url)
+                    .arg("--notifs-url")
+                    .arg(&notifs_url)
+                    .arg("--insecure-dev-key"),
+            );
+        }
+    }
+
+    if options.bots > 0 {
+        let client_bin = bin_path(bin_dir, CLIENT_BIN)?;
+        for idx in 0..options.bots {
+            specs.push(
+                ChildSpec::new(format!("bot-{}", idx), client_bin.clone())
+                    .arg("--bot")
+                    .arg("--api-url")
+                    .arg(&api_url)
+                    .arg("--notifs-url")
+                    .arg(&notifs_url),
+            );
+        }
+    }
+
+    Ok(specs)
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let options: Options = config::load(options::ENV_PREFIX)?;
+
+    logging::init_logging(env!("CARGO_CRATE_NAME"), &options.logging)?;
+
+    // check everything exists before starting anything
+    let bin_dir = options.bin_dir()?;
+    info!("running binaries from {}", bin_dir.display());
+
+    let redis_host = match &options.redis_host {
+        Some(redis_host) => redis_host.clone(),
+        None => {
+            let addr = store::start(options.store_port).await?;
+            format!("redis://{}/", addr)
+        }
+    };
+
+    let services = service_specs(&options, &bin_dir, &redis_host)?;
+    let game = game_specs(&options, &bin_dir)?;
+
+    let shutdown = shutdown::init_shutdown_signal();
+    let options = Arc::new(options);
+
+    let mut children = JoinSet::new();
+    let mut supervise = |spec: ChildSpec| {
+        let options = options.clone();
+        let shutdown = shutdown.clone();
+        children.spawn(async move { process::supervise(spec, &options, shutdown).await });
+    };
+
+    for spec in services {
+        supervise(spec);
+    }
+
+    // servers and bots just churn until the api is up
+    if !game.is_empty() {
+        info!("waiting for the api ...");
+
+        tokio::select! {
+            ready = wait_for_port(options.api_port, options.startup_timeout()) => {
+                if !ready {
+                    warn!("api isn't up after {:?}, starting anyway", options.startup_timeout());
+                }
+            }
+            _ = shutdown::wait_for_shutdown(shutdown.clone()) => (),
+        }
+
+        if !shutdown::is_shutting_down(&shutdown) {
+            for spec in game {
+                supervise(spec);
+            }
+        }
+    }
+
+    info!("dev stack running, ctrl-c to stop ...");
+
+    while children.join_next().await.is_some() {}
+
+    info!("dev stack stopped");
+
+    Ok(())
+}
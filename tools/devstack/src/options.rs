@@ -0,0 +1,101 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+use common::config::ConfigArgs;
+use internal::logging::LoggingOptions;
+
+pub const ENV_PREFIX: &str = "BMP_DEVSTACK_";
+
+#[derive(Parser, Debug, Serialize, Deserialize)]
+pub struct Options {
+    #[command(flatten)]
+    #[serde(skip)]
+    pub config: ConfigArgs,
+
+    #[command(flatten)]
+    #[serde(flatten)]
+    pub logging: LoggingOptions,
+
+    #[arg(long, default_value_t = 1)]
+    pub servers: u16,
+
+    #[arg(long, default_value_t = 0)]
+    pub bots: u16,
+
+    // use an external redis instead of the in-process store
+    #[arg(long)]
+    pub redis_host: Option<String>,
+
+    // 0 lets the OS pick a free port
+    #[arg(long, default_value_t = 0)]
+    pub store_port: u16,
+
+    #[arg(long, default_value_t = 8000)]
+    pub api_port: u16,
+
+    #[arg(long, default_value_t = 8001)]
+    pub notifs_port: u16,
+
+    // servers are given consecutive ports starting here
+    #[arg(long, default_value_t = 5576)]
+    pub server_base_port: u16,
+
+    // where to find the other binaries, defaults to next to this one
+    #[arg(long)]
+    pub bin_dir: Option<PathBuf>,
+
+    #[arg(long)]
+    #[serde(default)]
+    pub no_restart: bool,
+
+    // doubled each time a child crashes right after starting
+    #[arg(long, default_value_t = 1)]
+    pub restart_delay_secs: u64,
+
+    #[arg(long, default_value_t = 30)]
+    pub max_restart_delay_secs: u64,
+
+    // how long to wait for the api to come up before starting servers and bots
+    #[arg(long, default_value_t = 30)]
+    pub startup_timeout_secs: u64,
+
+    // how long children get to exit before they're killed
+    #[arg(long, default_value_t = 10)]
+    pub shutdown_timeout_secs: u64,
+}
+
+impl Options {
+    #[inline]
+    pub fn restart_delay(&self) -> Duration {
+        Duration::from_secs(self.restart_delay_secs)
+    }
+
+    #[inline]
+    pub fn max_restart_delay(&self) -> Duration {
+        Duration::from_secs(self.max_restart_delay_secs)
+    }
+
+    #[inline]
+    pub fn startup_timeout(&self) -> Duration {
+        Duration::from_secs(self.startup_timeout_secs)
+    }
+
+    #[inline]
+    pub fn shutdown_timeout(&self) -> Duration {
+        Duration::from_secs(self.shutdown_timeout_secs)
+    }
+
+    pub fn bin_dir(&self) -> anyhow::Result<PathBuf> {
+        if let Some(bin_dir) = &self.bin_dir {
+            return Ok(bin_dir.clone());
+        }
+
+        let exe = std::env::current_exe()?;
+        exe.parent()
+            .map(|dir| dir.to_path_buf())
+            .ok_or_else(|| anyhow::anyhow!("no parent directory for {}", exe.display()))
+    }
+}
@@ -0,0 +1,166 @@
+use std::path::PathBuf;
+use std::process::{ExitStatus, Stdio};
+
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, BufReader},
+    process::{Child, Command},
+    time::{Duration, Instant},
+};
+use tracing::{error, info, warn};
+
+use internal::shutdown::{self, ShutdownReceiver};
+
+use crate::options::Options;
+
+// children that stay up this long are considered healthy
+// and get their restart backoff reset
+const STABLE_UPTIME: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone)]
+pub struct ChildSpec {
+    pub name: String,
+    pub program: PathBuf,
+    pub args: Vec<String>,
+}
+
+impl ChildSpec {
+    pub fn new(name: impl Into<String>, program: PathBuf) -> Self {
+        Self {
+            name: name.into(),
+            program,
+            args: vec![],
+        }
+    }
+
+    pub fn arg(mut self, arg: impl ToString) -> Self {
+        self.args.push(arg.to_string());
+        self
+    }
+
+    fn spawn(&self) -> anyhow::Result<Child> {
+        let mut child = Command::new(&self.program)
+            .args(&self.args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            // don't leave anything behind if we go down hard
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|err| {
+                anyhow::anyhow!("failed to spawn {}: {}", self.program.display(), err)
+            })?;
+
+        if let Some(stdout) = child.stdout.take() {
+            tokio::spawn(forward_output(self.name.clone(), stdout));
+        }
+
+        if let Some(stderr) = child.stderr.take() {
+            tokio::spawn(forward_output(self.name.clone(), stderr));
+        }
+
+        Ok(child)
+    }
+}
+
+// interleaves the child output with ours, one prefixed line at a time
+async fn forward_output(name: String, output: impl AsyncRead + Unpin) {
+    let mut lines = BufReader::new(output).lines();
+    loop {
+        match lines.next_line().await {
+            Ok(Some(line)) => println!("[{}] {}", name, line),
+            Ok(None) => break,
+            Err(err) => {
+                warn!("failed to read {} output: {}", name, err);
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+fn terminate(child: &Child) -> bool {
+    match child.id() {
+        // SAFETY: the pid belongs to a child we haven't reaped yet
+        Some(pid) => unsafe { libc::kill(pid as libc::pid_t, libc::SIGTERM) == 0 },
+        None => false,
+    }
+}
+
+// no graceful option, so just go straight to killing it
+#[cfg(not(unix))]
+fn terminate(_child: &Child) -> bool {
+    false
+}
+
+async fn stop(name: &str, child: &mut Child, timeout: Duration) {
+    if terminate(child) {
+        match tokio::time::timeout(timeout, child.wait()).await {
+            Ok(Ok(status)) => {
+                info!("{} stopped ({})", name, status);
+                return;
+            }
+            Ok(Err(err)) => warn!("failed to wait for {}: {}", name, err),
+            Err(_) => warn!("{} didn't stop after {:?}, killing it", name, timeout),
+        }
+    }
+
+    if let Err(err) = child.kill().await {
+        error!("failed to kill {}: {}", name, err);
+    }
+}
+
+async fn run_once(
+    spec: &ChildSpec,
+    timeout: Duration,
+    shutdown: &ShutdownReceiver,
+) -> anyhow::Result<Option<ExitStatus>> {
+    let mut child = spec.spawn()?;
+
+    info!("started {} (pid {:?})", spec.name, child.id());
+
+    tokio::select! {
+        status = child.wait() => Ok(Some(status?)),
+        _ = shutdown::wait_for_shutdown(shutdown.clone()) => {
+            stop(&spec.name, &mut child, timeout).await;
+            Ok(None)
+        }
+    }
+}
+
+// runs the child until shutdown, restarting it whenever it exits
+pub async fn supervise(spec: ChildSpec, options: &Options, shutdown: ShutdownReceiver) {
+    let mut restart_delay = options.restart_delay();
+
+    while !shutdown::is_shutting_down(&shutdown) {
+        let started = Instant::now();
+
+        match run_once(&spec, options.shutdown_timeout(), &shutdown).await {
+            // ctrl-c in the terminal goes to the children too
+            Ok(Some(status)) if shutdown::is_shutting_down(&shutdown) => {
+                info!("{} stopped ({})", spec.name, status);
+                break;
+            }
+            Ok(Some(status)) => warn!("{} exited ({})", spec.name, status),
+            Ok(None) => break,
+            Err(err) => error!("{} failed: {}", spec.name, err),
+        }
+
+        if options.no_restart {
+            break;
+        }
+
+        // back off if it keeps dying on startup
+        if started.elapsed() >= STABLE_UPTIME {
+            restart_delay = options.restart_delay();
+        }
+
+        info!("restarting {} in {:?} ...", spec.name, restart_delay);
+
+        tokio::select! {
+            _ = tokio::time::sleep(restart_delay) => (),
+            _ = shutdown::wait_for_shutdown(shutdown.clone()) => break,
+        }
+
+        restart_delay = (restart_delay * 2).min(options.max_restart_delay());
+    }
+}
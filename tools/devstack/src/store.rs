@@ -0,0 +1,1238 @@
+// minimal in-process redis for local development
+// only implements what the services actually use (RESP2, no persistence)
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+use anyhow::anyhow;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{tcp::OwnedWriteHalf, TcpListener, TcpStream},
+    sync::{mpsc, Mutex},
+    time::{Duration, Instant},
+};
+use tracing::{debug, info, warn};
+
+use internal::{axum::TOKEN_BUCKET_SCRIPT, redis::RELEASE_LOCK_SCRIPT};
+
+const PURGE_INTERVAL: Duration = Duration::from_secs(10);
+
+const WRONGTYPE: &str = "WRONGTYPE Operation against a key holding the wrong kind of value";
+
+type Bytes = Vec<u8>;
+
+type CommandResult = Result<Frame, String>;
+
+#[derive(Debug)]
+enum Frame {
+    Simple(&'static str),
+    Error(String),
+    Integer(i64),
+    Bulk(Bytes),
+    Null,
+    Array(Vec<Frame>),
+}
+
+impl Frame {
+    #[inline]
+    fn ok() -> Self {
+        Self::Simple("OK")
+    }
+
+    #[inline]
+    fn bulk(value: impl Into<Bytes>) -> Self {
+        Self::Bulk(value.into())
+    }
+
+    fn bulk_or_null(value: Option<&Bytes>) -> Self {
+        value.map_or(Self::Null, |value| Self::Bulk(value.clone()))
+    }
+
+    fn write(&self, buf: &mut Bytes) {
+        match self {
+            Self::Simple(value) => buf.extend_from_slice(format!("+{}\r\n", value).as_bytes()),
+            Self::Error(err) => buf.extend_from_slice(format!("-{}\r\n", err).as_bytes()),
+            Self::Integer(value) => buf.extend_from_slice(format!(":{}\r\n", value).as_bytes()),
+            Self::Bulk(value) => {
+                buf.extend_from_slice(format!("${}\r\n", value.len()).as_bytes());
+                buf.extend_from_slice(value);
+                buf.extend_from_slice(b"\r\n");
+            }
+            Self::Null => buf.extend_from_slice(b"$-1\r\n"),
+            Self::Array(frames) => {
+                buf.extend_from_slice(format!("*{}\r\n", frames.len()).as_bytes());
+                for frame in frames {
+                    frame.write(buf);
+                }
+            }
+        }
+    }
+}
+
+#[inline]
+fn err(msg: impl AsRef<str>) -> String {
+    format!("ERR {}", msg.as_ref())
+}
+
+fn parse<T: std::str::FromStr>(arg: &[u8]) -> Result<T, String> {
+    std::str::from_utf8(arg)
+        .ok()
+        .and_then(|arg| arg.parse().ok())
+        .ok_or_else(|| err("value is not a valid number or out of range"))
+}
+
+// score bounds, ( means exclusive
+fn parse_bound(arg: &[u8]) -> Result<(f64, bool), String> {
+    let (arg, exclusive) = match arg.strip_prefix(b"(") {
+        Some(arg) => (arg, true),
+        None => (arg, false),
+    };
+
+    let value = match arg {
+        b"-inf" => f64::NEG_INFINITY,
+        b"+inf" | b"inf" => f64::INFINITY,
+        _ => parse(arg).map_err(|_| err("min or max is not a float"))?,
+    };
+
+    Ok((value, exclusive))
+}
+
+fn in_bounds(score: f64, min: (f64, bool), max: (f64, bool)) -> bool {
+    let above = if min.1 { score > min.0 } else { score >= min.0 };
+    let below = if max.1 { score < max.0 } else { score <= max.0 };
+    above && below
+}
+
+// only * and ? are supported
+fn glob_match(pattern: &[u8], value: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => value.is_empty(),
+        Some((b'*', rest)) => (0..=value.len()).any(|idx| glob_match(rest, &value[idx..])),
+        Some((b'?', rest)) => !value.is_empty() && glob_match(rest, &value[1..]),
+        Some((c, rest)) => value.first() == Some(c) && glob_match(rest, &value[1..]),
+    }
+}
+
+fn format_score(score: f64) -> Bytes {
+    score.to_string().into_bytes()
+}
+
+#[derive(Debug)]
+enum Value {
+    String(Bytes),
+    Hash(HashMap<Bytes, Bytes>),
+    SortedSet(HashMap<Bytes, f64>),
+}
+
+impl Value {
+    fn is_empty(&self) -> bool {
+        match self {
+            Self::String(_) => false,
+            Self::Hash(hash) => hash.is_empty(),
+            Self::SortedSet(set) => set.is_empty(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Entry {
+    value: Value,
+    expires_at: Option<Instant>,
+}
+
+impl Entry {
+    #[inline]
+    fn new(value: Value) -> Self {
+        Self {
+            value,
+            expires_at: None,
+        }
+    }
+
+    #[inline]
+    fn is_expired(&self, now: Instant) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+// scripts are emulated natively, keyed by the hash of the real script
+#[derive(Debug, Copy, Clone)]
+enum Script {
+    TokenBucket,
+    ReleaseLock,
+}
+
+#[derive(Debug)]
+struct Connection {
+    id: u64,
+    tx: mpsc::UnboundedSender<Frame>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    data: HashMap<Bytes, Entry>,
+    subscribers: HashMap<Bytes, Vec<(u64, mpsc::UnboundedSender<Frame>)>>,
+    scripts: HashMap<String, Script>,
+}
+
+impl State {
+    fn new() -> Self {
+        let mut scripts = HashMap::new();
+        scripts.insert(
+            TOKEN_BUCKET_SCRIPT.get_hash().to_string(),
+            Script::TokenBucket,
+        );
+        scripts.insert(
+            RELEASE_LOCK_SCRIPT.get_hash().to_string(),
+            Script::ReleaseLock,
+        );
+
+        Self {
+            scripts,
+            ..Default::default()
+        }
+    }
+
+    fn purge_expired(&mut self) {
+        let now = Instant::now();
+        self.data.retain(|_, entry| !entry.is_expired(now));
+    }
+
+    fn entry(&mut self, key: &[u8]) -> Option<&mut Entry> {
+        if self
+            .data
+            .get(key)
+            .is_some_and(|entry| entry.is_expired(Instant::now()))
+        {
+            self.data.remove(key);
+        }
+
+        self.data.get_mut(key)
+    }
+
+    // empty hashes / sets don't exist in redis
+    fn remove_if_empty(&mut self, key: &[u8]) {
+        if self
+            .data
+            .get(key)
+            .is_some_and(|entry| entry.value.is_empty())
+        {
+            self.data.remove(key);
+        }
+    }
+
+    fn string(&mut self, key: &[u8]) -> Result<Option<&Bytes>, String> {
+        match self.entry(key) {
+            None => Ok(None),
+            Some(Entry {
+                value: Value::String(value),
+                ..
+            }) => Ok(Some(value)),
+            Some(_) => Err(WRONGTYPE.to_string()),
+        }
+    }
+
+    fn hash(
+        &mut self,
+        key: &[u8],
+        create: bool,
+    ) -> Result<Option<&mut HashMap<Bytes, Bytes>>, String> {
+        if create && self.entry(key).is_none() {
+            self.data
+                .insert(key.to_vec(), Entry::new(Value::Hash(HashMap::new())));
+        }
+
+        match self.entry(key) {
+            None => Ok(None),
+            Some(Entry {
+                value: Value::Hash(hash),
+                ..
+            }) => Ok(Some(hash)),
+            Some(_) => Err(WRONGTYPE.to_string()),
+        }
+    }
+
+    fn sorted_set(
+        &mut self,
+        key: &[u8],
+        create: bool,
+    ) -> Result<Option<&mut HashMap<Bytes, f64>>, String> {
+        if create && self.entry(key).is_none() {
+            self.data
+                .insert(key.to_vec(), Entry::new(Value::SortedSet(HashMap::new())));
+        }
+
+        match self.entry(key) {
+            None => Ok(None),
+            Some(Entry {
+                value: Value::SortedSet(set),
+                ..
+            }) => Ok(Some(set)),
+            Some(_) => Err(WRONGTYPE.to_string()),
+        }
+    }
+
+    // members ordered by score, then lexically like redis
+    fn sorted_members(&mut self, key: &[u8]) -> Result<Vec<(Bytes, f64)>, String> {
+        let mut members = self
+            .sorted_set(key, false)?
+            .map(|set| {
+                set.iter()
+                    .map(|(member, score)| (member.clone(), *score))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        members.sort_by(|a, b| a.1.total_cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+        Ok(members)
+    }
+
+    fn set(&mut self, key: &[u8], value: Bytes, ttl: Option<Duration>) {
+        self.data.insert(
+            key.to_vec(),
+            Entry {
+                value: Value::String(value),
+                expires_at: ttl.map(|ttl| Instant::now() + ttl),
+            },
+        );
+    }
+
+    fn expire(&mut self, key: &[u8], ttl: Duration) -> bool {
+        if ttl.is_zero() {
+            return self.data.remove(key).is_some();
+        }
+
+        match self.entry(key) {
+            Some(entry) => {
+                entry.expires_at = Some(Instant::now() + ttl);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn ttl(&mut self, key: &[u8]) -> Option<Option<Duration>> {
+        self.entry(key).map(|entry| {
+            entry
+                .expires_at
+                .map(|expires_at| expires_at.saturating_duration_since(Instant::now()))
+        })
+    }
+
+    fn execute(&mut self, args: &[Bytes]) -> CommandResult {
+        let Some((command, args)) = args.split_first() else {
+            return Err(err("empty command"));
+        };
+
+        let command = String::from_utf8_lossy(command).to_uppercase();
+        let arity = |min: usize| {
+            if args.len() < min {
+                Err(err(format!(
+                    "wrong number of arguments for '{}' command",
+                    command.to_lowercase()
+                )))
+            } else {
+                Ok(())
+            }
+        };
+
+        match command.as_str() {
+            "PING" => Ok(match args.first() {
+                Some(message) => Frame::bulk(message.clone()),
+                None => Frame::Simple("PONG"),
+            }),
+            "ECHO" => {
+                arity(1)?;
+                Ok(Frame::bulk(args[0].clone()))
+            }
+            // connection setup, nothing to do for these
+            "CLIENT" | "SELECT" | "READONLY" => Ok(Frame::ok()),
+            "FLUSHALL" | "FLUSHDB" => {
+                self.data.clear();
+                Ok(Frame::ok())
+            }
+            "GET" => {
+                arity(1)?;
+                Ok(Frame::bulk_or_null(self.string(&args[0])?))
+            }
+            "SET" => {
+                arity(2)?;
+                self.set_command(&args[0], &args[1], &args[2..])
+            }
+            "SETEX" | "PSETEX" => {
+                arity(3)?;
+                let ttl: u64 = parse(&args[1])?;
+                let ttl = if command == "SETEX" {
+                    Duration::from_secs(ttl)
+                } else {
+                    Duration::from_millis(ttl)
+                };
+
+                if ttl.is_zero() {
+                    return Err(err(format!(
+                        "invalid expire time in '{}' command",
+                        command.to_lowercase()
+                    )));
+                }
+
+                self.set(&args[0], args[2].clone(), Some(ttl));
+                Ok(Frame::ok())
+            }
+            "DEL" | "UNLINK" => {
+                arity(1)?;
+                let mut count = 0;
+                for key in args {
+                    if self.entry(key).is_some() {
+                        self.data.remove(key);
+                        count += 1;
+                    }
+                }
+                Ok(Frame::Integer(count))
+            }
+            "EXISTS" => {
+                arity(1)?;
+                let count = args.iter().filter(|key| self.entry(key).is_some()).count();
+                Ok(Frame::Integer(count as i64))
+            }
+            "EXPIRE" | "PEXPIRE" => {
+                arity(2)?;
+                let ttl: i64 = parse(&args[1])?;
+                let ttl = ttl.max(0) as u64;
+                let ttl = if command == "EXPIRE" {
+                    Duration::from_secs(ttl)
+                } else {
+                    Duration::from_millis(ttl)
+                };
+
+                Ok(Frame::Integer(self.expire(&args[0], ttl) as i64))
+            }
+            "TTL" | "PTTL" => {
+                arity(1)?;
+                Ok(Frame::Integer(match self.ttl(&args[0]) {
+                    None => -2,
+                    Some(None) => -1,
+                    Some(Some(ttl)) if command == "TTL" => ttl.as_secs() as i64,
+                    Some(Some(ttl)) => ttl.as_millis() as i64,
+                }))
+            }
+            "KEYS" => {
+                arity(1)?;
+                self.purge_expired();
+
+                let keys = self
+                    .data
+                    .keys()
+                    .filter(|key| glob_match(&args[0], key))
+                    .map(|key| Frame::bulk(key.clone()))
+                    .collect();
+                Ok(Frame::Array(keys))
+            }
+            "HSET" => {
+                if args.len() < 3 || args.len() % 2 == 0 {
+                    return Err(err("wrong number of arguments for 'hset' command"));
+                }
+
+                let hash = self.hash(&args[0], true)?.unwrap();
+                let mut added = 0;
+                for field in args[1..].chunks(2) {
+                    if hash.insert(field[0].clone(), field[1].clone()).is_none() {
+                        added += 1;
+                    }
+                }
+                Ok(Frame::Integer(added))
+            }
+            "HGET" => {
+                arity(2)?;
+                let value = self
+                    .hash(&args[0], false)?
+                    .and_then(|hash| hash.get(&args[1]));
+                Ok(Frame::bulk_or_null(value))
+            }
+            "HMGET" => {
+                arity(2)?;
+                let hash = self.hash(&args[0], false)?;
+                let values = args[1..]
+                    .iter()
+                    .map(|field| {
+                        Frame::bulk_or_null(hash.as_ref().and_then(|hash| hash.get(field)))
+                    })
+                    .collect();
+                Ok(Frame::Array(values))
+            }
+            "HDEL" => {
+                arity(2)?;
+                let removed = match self.hash(&args[0], false)? {
+                    Some(hash) => args[1..]
+                        .iter()
+                        .filter(|field| hash.remove(*field).is_some())
+                        .count(),
+                    None => 0,
+                };
+                self.remove_if_empty(&args[0]);
+                Ok(Frame::Integer(removed as i64))
+            }
+            "HGETALL" => {
+                arity(1)?;
+                let values = self
+                    .hash(&args[0], false)?
+                    .map(|hash| {
+                        hash.iter()
+                            .flat_map(|(field, value)| {
+                                [Frame::bulk(field.clone()), Frame::bulk(value.clone())]
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                Ok(Frame::Array(values))
+            }
+            "ZADD" => {
+                if args.len() < 3 || args.len() % 2 == 0 {
+                    return Err(err("wrong number of arguments for 'zadd' command"));
+                }
+
+                let scores = args[1..]
+                    .chunks(2)
+                    .map(|pair| Ok((parse::<f64>(&pair[0])?, pair[1].clone())))
+                    .collect::<Result<Vec<_>, String>>()?;
+
+                let set = self.sorted_set(&args[0], true)?.unwrap();
+                let mut added = 0;
+                for (score, member) in scores {
+                    if set.insert(member, score).is_none() {
+                        added += 1;
+                    }
+                }
+                Ok(Frame::Integer(added))
+            }
+            "ZREM" => {
+                arity(2)?;
+                let removed = match self.sorted_set(&args[0], false)? {
+                    Some(set) => args[1..]
+                        .iter()
+                        .filter(|member| set.remove(*member).is_some())
+                        .count(),
+                    None => 0,
+                };
+                self.remove_if_empty(&args[0]);
+                Ok(Frame::Integer(removed as i64))
+            }
+            "ZCARD" => {
+                arity(1)?;
+                let count = self
+                    .sorted_set(&args[0], false)?
+                    .map(|set| set.len())
+                    .unwrap_or_default();
+                Ok(Frame::Integer(count as i64))
+            }
+            "ZSCORE" => {
+                arity(2)?;
+                let score = self
+                    .sorted_set(&args[0], false)?
+                    .and_then(|set| set.get(&args[1]))
+                    .map(|score| format_score(*score));
+                Ok(Frame::bulk_or_null(score.as_ref()))
+            }
+            "ZRANGE" => {
+                arity(3)?;
+                self.zrange(&args[0], &args[1], &args[2], &args[3..])
+            }
+            "ZRANGEBYSCORE" => {
+                arity(3)?;
+                self.zrangebyscore(&args[0], &args[1], &args[2], &args[3..])
+            }
+            "ZPOPMIN" => {
+                arity(1)?;
+                let count = match args.get(1) {
+                    Some(count) => parse::<usize>(count)?,
+                    None => 1,
+                };
+
+                let members: Vec<_> = self
+                    .sorted_members(&args[0])?
+                    .into_iter()
+                    .take(count)
+                    .collect();
+                if let Some(set) = self.sorted_set(&args[0], false)? {
+                    for (member, _) in &members {
+                        set.remove(member);
+                    }
+                }
+                self.remove_if_empty(&args[0]);
+
+                Ok(Self::range_reply(&members, true))
+            }
+            "ZREMRANGEBYSCORE" => {
+                arity(3)?;
+                let (min, max) = (parse_bound(&args[1])?, parse_bound(&args[2])?);
+                let removed = match self.sorted_set(&args[0], false)? {
+                    Some(set) => {
+                        let before = set.len();
+                        set.retain(|_, score| !in_bounds(*score, min, max));
+                        before - set.len()
+                    }
+                    None => 0,
+                };
+                self.remove_if_empty(&args[0]);
+                Ok(Frame::Integer(removed as i64))
+            }
+            "PUBLISH" => {
+                arity(2)?;
+                Ok(Frame::Integer(self.publish(&args[0], &args[1])))
+            }
+            // only reachable without any channels
+            "SUBSCRIBE" => Err(err("wrong number of arguments for 'subscribe' command")),
+            "SCRIPT" => {
+                arity(1)?;
+                self.script_command(&args[0], &args[1..])
+            }
+            "EVAL" | "EVALSHA" => {
+                arity(2)?;
+                let hash = if command == "EVAL" {
+                    redis::Script::new(&String::from_utf8_lossy(&args[0]))
+                        .get_hash()
+                        .to_string()
+                } else {
+                    String::from_utf8_lossy(&args[0]).to_lowercase()
+                };
+
+                let Some(script) = self.scripts.get(&hash).copied() else {
+                    return Err("NOSCRIPT No matching script. Please use EVAL.".to_string());
+                };
+
+                let key_count: usize = parse(&args[1])?;
+                if args.len() < 2 + key_count {
+                    return Err(err("Number of keys can't be greater than number of args"));
+                }
+
+                let (keys, argv) = args[2..].split_at(key_count);
+                self.eval(script, keys, argv)
+            }
+            _ => Err(err(format!(
+                "unknown command '{}', the dev store doesn't support it",
+                command.to_lowercase()
+            ))),
+        }
+    }
+
+    fn set_command(&mut self, key: &[u8], value: &[u8], options: &[Bytes]) -> CommandResult {
+        let mut nx = false;
+        let mut xx = false;
+        let mut ttl = None;
+
+        let mut options = options.iter();
+        while let Some(option) = options.next() {
+            match option.to_ascii_uppercase().as_slice() {
+                b"NX" => nx = true,
+                b"XX" => xx = true,
+                option @ (b"EX" | b"PX") => {
+                    let value: u64 = parse(options.next().ok_or_else(|| err("syntax error"))?)?;
+                    ttl = Some(if option == b"EX" {
+                        Duration::from_secs(value)
+                    } else {
+                        Duration::from_millis(value)
+                    });
+                }
+                _ => return Err(err("syntax error")),
+            }
+        }
+
+        let exists = self.entry(key).is_some();
+        if (nx && exists) || (xx && !exists) {
+            return Ok(Frame::Null);
+        }
+
+        self.set(key, value.to_vec(), ttl);
+        Ok(Frame::ok())
+    }
+
+    fn zrange(
+        &mut self,
+        key: &[u8],
+        start: &[u8],
+        stop: &[u8],
+        options: &[Bytes],
+    ) -> CommandResult {
+        let with_scores = match options {
+            [] => false,
+            [option] if option.eq_ignore_ascii_case(b"WITHSCORES") => true,
+            _ => return Err(err("syntax error")),
+        };
+
+        let members = self.sorted_members(key)?;
+        let len = members.len() as i64;
+
+        // negative indices count from the end
+        let index = |arg: &[u8]| -> Result<i64, String> {
+            let index: i64 = parse(arg)?;
+            Ok(if index < 0 { len + index } else { index })
+        };
+        let start = index(start)?.max(0);
+        let stop = index(stop)?.min(len - 1);
+
+        let members = if start > stop {
+            &[][..]
+        } else {
+            &members[start as usize..=stop as usize]
+        };
+
+        Ok(Self::range_reply(members, with_scores))
+    }
+
+    fn zrangebyscore(
+        &mut self,
+        key: &[u8],
+        min: &[u8],
+        max: &[u8],
+        options: &[Bytes],
+    ) -> CommandResult {
+        let (min, max) = (parse_bound(min)?, parse_bound(max)?);
+
+        let mut with_scores = false;
+        let mut limit = None;
+
+        let mut options = options.iter();
+        while let Some(option) = options.next() {
+            match option.to_ascii_uppercase().as_slice() {
+                b"WITHSCORES" => with_scores = true,
+                b"LIMIT" => {
+                    let (Some(offset), Some(count)) = (options.next(), options.next()) else {
+                        return Err(err("syntax error"));
+                    };
+                    limit = Some((parse::<usize>(offset)?, parse::<i64>(count)?));
+                }
+                _ => return Err(err("syntax error")),
+            }
+        }
+
+        let members = self
+            .sorted_members(key)?
+            .into_iter()
+            .filter(|(_, score)| in_bounds(*score, min, max));
+
+        // a negative count means no limit
+        let members: Vec<_> = match limit {
+            Some((offset, count)) if count >= 0 => {
+                members.skip(offset).take(count as usize).collect()
+            }
+            Some((offset, _)) => members.skip(offset).collect(),
+            None => members.collect(),
+        };
+
+        Ok(Self::range_reply(&members, with_scores))
+    }
+
+    fn range_reply(members: &[(Bytes, f64)], with_scores: bool) -> Frame {
+        let mut frames = Vec::with_capacity(members.len() * if with_scores { 2 } else { 1 });
+        for (member, score) in members {
+            frames.push(Frame::bulk(member.clone()));
+            if with_scores {
+                frames.push(Frame::Bulk(format_score(*score)));
+            }
+        }
+        Frame::Array(frames)
+    }
+
+    fn publish(&mut self, channel: &[u8], message: &[u8]) -> i64 {
+        let Some(subscribers) = self.subscribers.get_mut(channel) else {
+            return 0;
+        };
+
+        // drop anyone that went away
+        subscribers.retain(|(_, tx)| {
+            tx.send(Frame::Array(vec![
+                Frame::bulk("message"),
+                Frame::bulk(channel),
+                Frame::bulk(message),
+            ]))
+            .is_ok()
+        });
+
+        subscribers.len() as i64
+    }
+
+    fn subscription_count(&self, id: u64) -> i64 {
+        self.subscribers
+            .values()
+            .filter(|subscribers| subscribers.iter().any(|(sid, _)| *sid == id))
+            .count() as i64
+    }
+
+    fn subscribe(&mut self, connection: &Connection, channels: &[Bytes]) {
+        for channel in channels {
+            let subscribers = self.subscribers.entry(channel.clone()).or_default();
+            if !subscribers.iter().any(|(id, _)| *id == connection.id) {
+                subscribers.push((connection.id, connection.tx.clone()));
+            }
+
+            let _ = connection.tx.send(Frame::Array(vec![
+                Frame::bulk("subscribe"),
+                Frame::bulk(channel.clone()),
+                Frame::Integer(self.subscription_count(connection.id)),
+            ]));
+        }
+    }
+
+    fn unsubscribe(&mut self, connection: &Connection, channels: &[Bytes]) {
+        // no channels means all of them
+        let channels = if channels.is_empty() {
+            self.subscribers
+                .iter()
+                .filter(|(_, subscribers)| subscribers.iter().any(|(id, _)| *id == connection.id))
+                .map(|(channel, _)| channel.clone())
+                .collect()
+        } else {
+            channels.to_vec()
+        };
+
+        for channel in channels {
+            if let Some(subscribers) = self.subscribers.get_mut(&channel) {
+                subscribers.retain(|(id, _)| *id != connection.id);
+                if subscribers.is_empty() {
+                    self.subscribers.remove(&channel);
+                }
+            }
+
+            let _ = connection.tx.send(Frame::Array(vec![
+                Frame::bulk("unsubscribe"),
+                Frame::bulk(channel),
+                Frame::Integer(self.subscription_count(connection.id)),
+            ]));
+        }
+    }
+
+    fn disconnect(&mut self, id: u64) {
+        self.subscribers.retain(|_, subscribers| {
+            subscribers.retain(|(sid, _)| *sid != id);
+            !subscribers.is_empty()
+        });
+    }
+
+    fn script_command(&mut self, subcommand: &[u8], args: &[Bytes]) -> CommandResult {
+        match subcommand.to_ascii_uppercase().as_slice() {
+            b"LOAD" => {
+                let [source] = args else {
+                    return Err(err("wrong number of arguments for 'script|load' command"));
+                };
+
+                let hash = redis::Script::new(&String::from_utf8_lossy(source))
+                    .get_hash()
+                    .to_string();
+                if !self.scripts.contains_key(&hash) {
+                    return Err(err("the dev store can't run arbitrary scripts"));
+                }
+
+                Ok(Frame::bulk(hash))
+            }
+            b"EXISTS" => Ok(Frame::Array(
+                args.iter()
+                    .map(|hash| {
+                        let hash = String::from_utf8_lossy(hash).to_lowercase();
+                        Frame::Integer(self.scripts.contains_key(&hash) as i64)
+                    })
+                    .collect(),
+            )),
+            b"FLUSH" => Ok(Frame::ok()),
+            _ => Err(err("unknown subcommand")),
+        }
+    }
+
+    fn eval(&mut self, script: Script, keys: &[Bytes], argv: &[Bytes]) -> CommandResult {
+        match script {
+            Script::TokenBucket => {
+                let ([key], [capacity, rate, now]) = (keys, argv) else {
+                    return Err(err("unexpected token bucket script arguments"));
+                };
+                self.token_bucket(key, parse(capacity)?, parse(rate)?, now)
+            }
+            Script::ReleaseLock => {
+                let ([key], [lock_id]) = (keys, argv) else {
+                    return Err(err("unexpected release lock script arguments"));
+                };
+
+                if self.string(key)? == Some(lock_id) {
+                    self.data.remove(key);
+                    Ok(Frame::Integer(1))
+                } else {
+                    Ok(Frame::Integer(0))
+                }
+            }
+        }
+    }
+
+    // mirrors TOKEN_BUCKET_SCRIPT
+    fn token_bucket(&mut self, key: &[u8], capacity: f64, rate: f64, now: &[u8]) -> CommandResult {
+        let now_value: f64 = parse(now)?;
+
+        let field = |hash: Option<&HashMap<Bytes, Bytes>>, field: &[u8]| {
+            hash.and_then(|hash| hash.get(field))
+                .and_then(|value| parse::<f64>(value).ok())
+        };
+
+        let hash = self.hash(key, false)?;
+        let tokens = field(hash.as_deref(), b"tokens").unwrap_or(capacity);
+        let ts = field(hash.as_deref(), b"ts").unwrap_or(now_value);
+
+        let mut tokens = capacity.min(tokens + (now_value - ts).max(0.0) * rate);
+
+        let mut allowed = 0;
+        let mut retry_after = 0;
+        if tokens >= 1.0 {
+            tokens -= 1.0;
+            allowed = 1;
+        } else {
+            retry_after = ((1.0 - tokens) / rate).ceil() as i64;
+        }
+
+        let hash = self.hash(key, true)?.unwrap();
+        hash.insert(b"tokens".to_vec(), tokens.to_string().into_bytes());
+        hash.insert(b"ts".to_vec(), now.to_vec());
+
+        let ttl = (capacity / rate).ceil();
+        if ttl.is_finite() {
+            self.expire(key, Duration::from_millis(ttl.max(1.0) as u64));
+        }
+
+        Ok(Frame::Array(vec![
+            Frame::Integer(allowed),
+            Frame::Integer(retry_after),
+        ]))
+    }
+}
+
+#[derive(Debug)]
+pub struct Store {
+    state: Mutex<State>,
+    next_connection_id: AtomicU64,
+}
+
+impl Store {
+    fn new() -> Self {
+        Self {
+            state: Mutex::new(State::new()),
+            next_connection_id: AtomicU64::new(1),
+        }
+    }
+
+    async fn execute(&self, connection: &Connection, args: &[Bytes]) {
+        let mut state = self.state.lock().await;
+
+        // (un)subscribe reply per channel, so they're handled separately
+        let reply = match args.split_first() {
+            Some((command, channels))
+                if command.eq_ignore_ascii_case(b"SUBSCRIBE") && !channels.is_empty() =>
+            {
+                state.subscribe(connection, channels);
+                return;
+            }
+            Some((command, channels)) if command.eq_ignore_ascii_case(b"UNSUBSCRIBE") => {
+                state.unsubscribe(connection, channels);
+                return;
+            }
+            _ => state.execute(args),
+        };
+
+        let frame = match reply {
+            Ok(frame) => frame,
+            Err(err) => {
+                debug!("dev store command failed: {}", err);
+                Frame::Error(err)
+            }
+        };
+
+        let _ = connection.tx.send(frame);
+    }
+
+    async fn handle_connection(self: Arc<Self>, stream: TcpStream) -> anyhow::Result<()> {
+        let (reader, writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+
+        // everything goes through the one channel
+        // so replies and pubsub messages stay in order
+        let (tx, rx) = mpsc::unbounded_channel();
+        let writer = tokio::spawn(write_frames(writer, rx));
+
+        let connection = Connection {
+            id: self.next_connection_id.fetch_add(1, Ordering::Relaxed),
+            tx,
+        };
+
+        let result = async {
+            while let Some(args) = read_command(&mut reader).await? {
+                self.execute(&connection, &args).await;
+            }
+            anyhow::Ok(())
+        }
+        .await;
+
+        self.state.lock().await.disconnect(connection.id);
+
+        drop(connection);
+        writer.await??;
+
+        result
+    }
+}
+
+async fn read_line<R: AsyncRead + Unpin>(
+    reader: &mut BufReader<R>,
+) -> anyhow::Result<Option<String>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line).await? == 0 {
+        return Ok(None);
+    }
+
+    Ok(Some(line.trim_end().to_string()))
+}
+
+// commands are always sent as arrays of bulk strings
+async fn read_command<R: AsyncRead + Unpin>(
+    reader: &mut BufReader<R>,
+) -> anyhow::Result<Option<Vec<Bytes>>> {
+    let Some(line) = read_line(reader).await? else {
+        return Ok(None);
+    };
+
+    let count: usize = line
+        .strip_prefix('*')
+        .ok_or_else(|| anyhow!("expected a command array, got {:?}", line))?
+        .parse()?;
+
+    let mut args = Vec::with_capacity(count);
+    for _ in 0..count {
+        let line = read_line(reader)
+            .await?
+            .ok_or_else(|| anyhow!("connection closed mid command"))?;
+
+        let len: usize = line
+            .strip_prefix('$')
+            .ok_or_else(|| anyhow!("expected a bulk string, got {:?}", line))?
+            .parse()?;
+
+        // value plus the trailing \r\n
+        let mut arg = vec![0; len + 2];
+        reader.read_exact(&mut arg).await?;
+        arg.truncate(len);
+
+        args.push(arg);
+    }
+
+    Ok(Some(args))
+}
+
+async fn write_frames(
+    mut writer: OwnedWriteHalf,
+    mut rx: mpsc::UnboundedReceiver<Frame>,
+) -> anyhow::Result<()> {
+    let mut buf = vec![];
+    while let Some(frame) = rx.recv().await {
+        frame.write(&mut buf);
+
+        // batch up anything else that's ready (pipelines)
+        while let Ok(frame) = rx.try_recv() {
+            frame.write(&mut buf);
+        }
+
+        writer.write_all(&buf).await?;
+        buf.clear();
+    }
+
+    Ok(())
+}
+
+// starts the store on localhost, returns the bound address
+pub async fn start(port: u16) -> anyhow::Result<SocketAddr> {
+    let listener = TcpListener::bind(("127.0.0.1", port)).await?;
+    let addr = listener.local_addr()?;
+
+    info!("dev store listening on {}", addr);
+
+    let store = Arc::new(Store::new());
+
+    tokio::spawn({
+        let store = store.clone();
+        async move {
+            let mut interval = tokio::time::interval(PURGE_INTERVAL);
+            loop {
+                interval.tick().await;
+                store.state.lock().await.purge_expired();
+            }
+        }
+    });
+
+    tokio::spawn(async move {
+        loop {
+            let (stream, peer) = match listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(err) => {
+                    warn!("dev store failed to accept connection: {}", err);
+                    continue;
+                }
+            };
+
+            debug!("dev store connection from {}", peer);
+
+            tokio::spawn({
+                let store = store.clone();
+                async move {
+                    if let Err(err) = store.handle_connection(stream).await {
+                        warn!("dev store connection {} failed: {}", peer, err);
+                    }
+                }
+            });
+        }
+    });
+
+    Ok(addr)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use redis::{aio::MultiplexedConnection, AsyncCommands};
+
+    use super::*;
+
+    // set this to also run the commands against a real redis and compare
+    const REDIS_URL_ENV: &str = "DEVSTACK_TEST_REDIS_URL";
+
+    async fn connect(url: &str) -> MultiplexedConnection {
+        redis::Client::open(url)
+            .unwrap()
+            .get_multiplexed_async_connection()
+            .await
+            .unwrap()
+    }
+
+    // the commands api and notifs send, including the scripts (by sha, like redis::Script does)
+    // replies are formatted so the two stores can be compared
+    async fn run_commands(conn: &mut MultiplexedConnection, prefix: &str) -> Vec<String> {
+        let key = |name: &str| format!("{}:{}", prefix, name);
+        let mut results = vec![];
+
+        let _: () = conn.set(key("string"), "value").await.unwrap();
+        let value: Option<String> = conn.get(key("string")).await.unwrap();
+        results.push(format!("get {:?}", value));
+
+        let _: () = conn.set_ex(key("expiring"), "value", 60).await.unwrap();
+        let ttl: i64 = conn.ttl(key("expiring")).await.unwrap();
+        results.push(format!("ttl {}", ttl > 0 && ttl <= 60));
+        let exists: bool = conn.exists(key("expiring")).await.unwrap();
+        results.push(format!("exists {}", exists));
+        let expired: bool = conn.expire(key("string"), 60).await.unwrap();
+        results.push(format!("expire {}", expired));
+
+        let added: i64 = redis::cmd("HSET")
+            .arg(key("hash"))
+            .arg(&[("a", "1"), ("b", "2")])
+            .query_async(conn)
+            .await
+            .unwrap();
+        results.push(format!("hset {}", added));
+        let deleted: i64 = conn.hdel(key("hash"), "a").await.unwrap();
+        results.push(format!("hdel {}", deleted));
+        let hash: BTreeMap<String, String> = conn.hgetall(key("hash")).await.unwrap();
+        results.push(format!("hgetall {:?}", hash));
+
+        let mut keys: Vec<String> = conn.keys(format!("{}:*", prefix)).await.unwrap();
+        keys.sort();
+        results.push(format!("keys {:?}", keys));
+
+        for (member, score) in [("a", 3.0), ("b", 1.0), ("c", 2.5)] {
+            let added: i64 = conn.zadd(key("zset"), member, score).await.unwrap();
+            results.push(format!("zadd {}", added));
+        }
+        let range: Vec<(String, f64)> = conn.zrange_withscores(key("zset"), 0, -1).await.unwrap();
+        results.push(format!("zrange {:?}", range));
+        let range: Vec<String> = conn.zrangebyscore(key("zset"), 2, "+inf").await.unwrap();
+        results.push(format!("zrangebyscore {:?}", range));
+        let removed: i64 = conn.zrembyscore(key("zset"), "-inf", 1).await.unwrap();
+        results.push(format!("zremrangebyscore {}", removed));
+        let removed: i64 = conn.zrem(key("zset"), "c").await.unwrap();
+        results.push(format!("zrem {}", removed));
+        let popped: Vec<(String, f64)> = conn.zpopmin(key("zset"), 1).await.unwrap();
+        results.push(format!("zpopmin {:?}", popped));
+
+        let acquired: bool = redis::cmd("SET")
+            .arg(key("lock"))
+            .arg("owner")
+            .arg("NX")
+            .arg("EX")
+            .arg(60)
+            .query_async(conn)
+            .await
+            .unwrap();
+        results.push(format!("lock {}", acquired));
+        let acquired: bool = redis::cmd("SET")
+            .arg(key("lock"))
+            .arg("other")
+            .arg("NX")
+            .arg("EX")
+            .arg(60)
+            .query_async(conn)
+            .await
+            .unwrap();
+        results.push(format!("lock {}", acquired));
+        for lock_id in ["other", "owner"] {
+            let released: i64 = RELEASE_LOCK_SCRIPT
+                .key(key("lock"))
+                .arg(lock_id)
+                .invoke_async(conn)
+                .await
+                .unwrap();
+            results.push(format!("release {} {}", lock_id, released));
+        }
+
+        // capacity 2, 1 token per ms
+        for now in [1000, 1000, 1000, 1001, 1005] {
+            let (allowed, retry_after): (u64, u64) = TOKEN_BUCKET_SCRIPT
+                .key(key("bucket"))
+                .arg(2)
+                .arg(1)
+                .arg(now)
+                .invoke_async(conn)
+                .await
+                .unwrap();
+            results.push(format!("token bucket {} {} {}", now, allowed, retry_after));
+        }
+        let tokens: Option<String> = conn.hget(key("bucket"), "tokens").await.unwrap();
+        results.push(format!("tokens {:?}", tokens));
+
+        let published: i64 = conn.publish(key("channel"), "message").await.unwrap();
+        results.push(format!("publish {}", published));
+
+        let keys: Vec<String> = conn.keys(format!("{}:*", prefix)).await.unwrap();
+        let _: () = conn.del(keys).await.unwrap();
+
+        results
+    }
+
+    #[tokio::test]
+    async fn store_matches_redis() {
+        let addr = start(0).await.unwrap();
+        let mut conn = connect(&format!("redis://{}", addr)).await;
+
+        let prefix = format!("devstack-test:{}", std::process::id());
+        let results = run_commands(&mut conn, &prefix).await;
+
+        // the scripts are matched by sha, so these fail if a script changes without the store
+        assert!(results.contains(&"release other 0".to_string()));
+        assert!(results.contains(&"release owner 1".to_string()));
+        assert!(results.contains(&"token bucket 1000 0 1".to_string()));
+        assert!(results.contains(&"token bucket 1005 1 0".to_string()));
+
+        let Ok(url) = std::env::var(REDIS_URL_ENV) else {
+            eprintln!("{} not set, skipping redis comparison", REDIS_URL_ENV);
+            return;
+        };
+
+        let mut conn = connect(&url).await;
+        let expected = run_commands(&mut conn, &prefix).await;
+
+        assert_eq!(results, expected);
+    }
+}